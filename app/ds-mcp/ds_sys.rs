@@ -1,8 +1,10 @@
 #![allow(dead_code)]
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, str::FromStr, sync::Arc};
 
 use orion_error::ErrorConv;
-use orion_syspec::{error::SpecResult, system::proj::SysProject};
+use orion_syspec::{
+    error::SpecResult, module::ModelSTD, system::proj::SysProject, vars::RuntimeContext,
+};
 use rmcp::{
     Error as McpError, RoleServer, ServerHandler,
     handler::server::{router::tool::ToolRouter, tool::Parameters},
@@ -22,11 +24,19 @@ pub struct StructRequest {
     pub b: i32,
 }
 
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ModRequest {
+    pub name: String,
+    /// target for modules with `model: auto`; eg: x86-ubt22-host
+    pub model: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct SysMCService {
     counter: Arc<Mutex<i32>>,
     tool_router: ToolRouter<SysMCService>,
     project: SysProject,
+    runtime: RuntimeContext,
 }
 
 #[tool_router]
@@ -39,8 +49,16 @@ impl SysMCService {
             counter: Arc::new(Mutex::new(0)),
             tool_router: Self::tool_router(),
             project: spec,
+            runtime: RuntimeContext::detect()?,
         })
     }
+    fn mod_runtime(&self, model: Option<&String>) -> Result<RuntimeContext, McpError> {
+        let model = match model {
+            Some(x) => Some(ModelSTD::from_str(x).map_err(|e| McpError::invalid_params(e, None))?),
+            None => None,
+        };
+        Ok(self.runtime.clone().with_host_model(model))
+    }
 
     fn _create_resource_text(&self, uri: &str, name: &str) -> Resource {
         RawResource::new(uri, name.to_string()).no_annotation()
@@ -95,12 +113,13 @@ impl SysMCService {
     #[tool(description = "get modul spec by name")]
     fn mod_spec_info(
         &self,
-        Parameters(mod_name): Parameters<String>,
+        Parameters(ModRequest { name, model }): Parameters<ModRequest>,
     ) -> Result<CallToolResult, McpError> {
         let spec = self.project.sys_spec();
-        let found = spec.mod_list().find(mod_name.as_str());
+        let runtime = self.mod_runtime(model.as_ref())?;
+        let found = spec.mod_list().find(name.as_str());
         if let Some(f_mod) = found {
-            if let Some(spec_info) = f_mod.get_target_spec().mcp_err()? {
+            if let Some(spec_info) = f_mod.get_target_spec(&runtime).mcp_err()? {
                 return Ok(CallToolResult::success(vec![Content::json(
                     spec_info.clone(),
                 )?]));
@@ -114,15 +133,16 @@ impl SysMCService {
     #[tool(description = "get modul localize values")]
     fn mod_localize_values(
         &self,
-        Parameters(mod_name): Parameters<String>,
+        Parameters(ModRequest { name, model }): Parameters<ModRequest>,
     ) -> Result<CallToolResult, McpError> {
         let spec = self.project.sys_spec();
+        let runtime = self.mod_runtime(model.as_ref())?;
         let prj_value_path = self.project.value_path();
         let mods_value_path = spec.mod_list().value_path(prj_value_path);
-        let found = spec.mod_list().find(mod_name.as_str());
+        let found = spec.mod_list().find(name.as_str());
         if let Some(f_mod) = found {
             let spec_value_path = f_mod.spec_value_path(mods_value_path);
-            if let Some(spec_info) = f_mod.get_target_spec().mcp_err()? {
+            if let Some(spec_info) = f_mod.get_target_spec(&runtime).mcp_err()? {
                 if let Some(values) = spec_info.get_local_values(spec_value_path).mcp_err()? {
                     return Ok(CallToolResult::success(vec![Content::text(values)]));
                 }
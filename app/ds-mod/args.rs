@@ -1,6 +1,8 @@
 use clap::{ArgAction, Parser};
 use derive_getters::Getters;
-use orion_syspec::infra::DfxArgsGetter;
+use orion_syspec::{
+    addr::AddrType, infra::DfxArgsGetter, module::package::VersionBump, vars::ImportMode,
+};
 
 #[derive(Debug, Parser)] // requires `derive` feature
 #[command(name = "ds-mod")]
@@ -8,18 +10,110 @@ use orion_syspec::infra::DfxArgsGetter;
 pub enum GxModCmd {
     Example,
     /// define new module spec
-    /// eg: ds-mod def new --name mod_name
+    /// eg: ds-mod new --name mod_name --kind helm --targets x86-ubt22-k8s
     New(SpecArgs),
     /// module spec update ref,depends
     Update(UpdateArgs),
     ///localize modules spec
     Localize(LocalArgs),
+    /// show where a value comes from
+    /// eg: ds-mod explain DB_HOST --profile prod
+    Explain(ExplainArgs),
+    /// check template variable refs against vars.yml and value files
+    /// eg: ds-mod lint --value my_value.yml
+    Lint(LintArgs),
+    /// module vars json schema
+    #[command(subcommand)]
+    Schema(SchemaCmd),
+    /// module value files
+    #[command(subcommand)]
+    Values(ValuesCmd),
+    /// search modules in registry index
+    /// eg: ds-mod search mysql
+    Search(SearchArgs),
+    /// show module versions and targets in registry index
+    /// eg: ds-mod info mysql
+    Info(InfoArgs),
+    /// validate and build <name>-<version>.tar.gz with manifest and checksums
+    /// eg: ds-mod package --bump patch --upload https://repo.example.com/mods/
+    Package(PackageArgs),
+    /// validate whole module and report every problem
+    /// eg: ds-mod check --json
+    Check(CheckArgs),
+    /// show which layer each target file comes from when target extends a base module
+    /// eg: ds-mod layers
+    Layers(LayersArgs),
+    /// create module from other formats
+    #[command(subcommand)]
+    Import(ImportCmd),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SchemaCmd {
+    /// export vars.schema.json for every target
+    /// eg: ds-mod schema export
+    Export,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCmd {
+    /// import helm chart dir or addr as k8s module, values.yaml mapped to vars.yml
+    /// eg: ds-mod import helm ./charts/nginx --name web
+    Helm(HelmImportArgs),
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct HelmImportArgs {
+    /// chart dir, git repo or http url
+    pub chart: String,
+    /// module name, default chart name
+    #[arg(short, long)]
+    pub name: Option<String>,
+    /// targets, default x86-ubt22-k8s
+    #[arg(short = 't', long = "targets", value_delimiter = ',')]
+    pub targets: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ValuesCmd {
+    /// validate value.yml against vars schema
+    /// eg: ds-mod values validate --value my_value.yml
+    Validate(ValidateArgs),
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct ValidateArgs {
+    /// value file; default: mod/<target>/values/value.yml
+    #[arg(long = "value")]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct LintArgs {
+    /// use value files instead of values/value.yml, repeatable and later wins; eg: --value cicd_value.yml
+    #[arg(long = "value")]
+    pub value: Vec<String>,
+    /// value files layered on top of values/value.yml (or --value), repeatable and later wins
+    #[arg(long = "value-layer")]
+    pub value_layer: Vec<String>,
+    /// nested key import mode: structured | flatten (a.b.c)
+    #[arg(long = "value-mode", default_value = "structured")]
+    pub value_mode: ImportMode,
 }
 
 #[derive(Debug, Args, Getters)]
 pub struct SpecArgs {
     #[arg(short, long)]
     pub(crate) name: String,
+    /// scaffold kind: host, k8s, systemd, compose, helm, static or a user scaffold
+    #[arg(short = 'k', long = "kind")]
+    pub kind: Option<String>,
+    /// targets, default by kind; eg: --targets x86-ubt22-k8s,arm-mac14-host
+    #[arg(short = 't', long = "targets", value_delimiter = ',')]
+    pub targets: Vec<String>,
+    /// git repo of user scaffolds, each sub dir is a kind
+    #[arg(long = "scaffold")]
+    pub scaffold: Option<String>,
 
     ///output run log
     ///level : 1,2,3,4
@@ -63,13 +157,47 @@ pub struct LocalArgs {
     #[arg(long = "log")]
     pub log: Option<String>,
 
-    /// use vlaue file; eg: --value cicd_value.yml
+    /// use value files instead of values/value.yml, repeatable and later wins; eg: --value cicd_value.yml
+    /// support yaml(helm values)/toml/json/ini/env
     #[arg(long = "value")]
-    pub value: Option<String>,
+    pub value: Vec<String>,
+    /// value files layered on top of values/value.yml (or --value), repeatable and later wins;
+    /// eg: --value-layer .env
+    #[arg(long = "value-layer")]
+    pub value_layer: Vec<String>,
+    /// nested key import mode: structured | flatten (a.b.c)
+    #[arg(long = "value-mode", default_value = "structured")]
+    pub value_mode: ImportMode,
+    /// use value profile overlay; eg: --profile prod  (values/profiles/prod.yml)
+    #[arg(long = "profile")]
+    pub profile: Option<String>,
+    /// not use module  user value.yml
+    #[arg(long = "default", default_value = "false" , action = ArgAction::SetTrue)]
+    pub use_default_value: bool,
+}
+#[derive(Debug, Args, Getters)]
+pub struct ExplainArgs {
+    /// variable name
+    pub key: String,
+    /// use value files instead of values/value.yml, repeatable and later wins; eg: --value cicd_value.yml
+    /// support yaml(helm values)/toml/json/ini/env
+    #[arg(long = "value")]
+    pub value: Vec<String>,
+    /// value files layered on top of values/value.yml (or --value), repeatable and later wins;
+    /// eg: --value-layer .env
+    #[arg(long = "value-layer")]
+    pub value_layer: Vec<String>,
+    /// nested key import mode: structured | flatten (a.b.c)
+    #[arg(long = "value-mode", default_value = "structured")]
+    pub value_mode: ImportMode,
+    /// use value profile overlay; eg: --profile prod
+    #[arg(long = "profile")]
+    pub profile: Option<String>,
     /// not use module  user value.yml
     #[arg(long = "default", default_value = "false" , action = ArgAction::SetTrue)]
     pub use_default_value: bool,
 }
+
 impl DfxArgsGetter for LocalArgs {
     fn debug_level(&self) -> usize {
         self.debug
@@ -89,3 +217,71 @@ impl DfxArgsGetter for SpecArgs {
         self.log.clone()
     }
 }
+
+#[derive(Debug, Args, Getters)]
+pub struct SearchArgs {
+    /// match module name or desc; empty for all
+    #[arg(default_value = "")]
+    pub keyword: String,
+    /// registry index address, repeatable; default: ~/.galaxy/registry.yml
+    /// eg: --registry https://example.com/index.yml
+    #[arg(long = "registry")]
+    pub registry: Vec<AddrType>,
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct InfoArgs {
+    /// module name
+    pub name: String,
+    /// registry index address, repeatable; default: ~/.galaxy/registry.yml
+    #[arg(long = "registry")]
+    pub registry: Vec<AddrType>,
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct PackageArgs {
+    #[arg(short = 'd', long = "debug", default_value = "0")]
+    pub debug: usize,
+    /// config log ; eg: --log  cmd=debug,parse=info
+    #[arg(long = "log")]
+    pub log: Option<String>,
+    /// output dir
+    #[arg(long = "out", default_value = "dist")]
+    pub out: String,
+    /// bump version.txt before package: major | minor | patch
+    #[arg(long = "bump")]
+    pub bump: Option<VersionBump>,
+    /// upload address, http url or local dir; url ending with / appends package name
+    #[arg(long = "upload")]
+    pub upload: Option<String>,
+    /// http upload method: PUT | POST
+    #[arg(long = "method", default_value = "PUT")]
+    pub method: String,
+}
+
+impl DfxArgsGetter for PackageArgs {
+    fn debug_level(&self) -> usize {
+        self.debug
+    }
+
+    fn log_setting(&self) -> Option<String> {
+        self.log.clone()
+    }
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct CheckArgs {
+    /// output json report
+    #[arg(long = "json", default_value = "false" , action = ArgAction::SetTrue)]
+    pub json: bool,
+    /// probe artifact addresses (http/git/local)
+    #[arg(long = "remote", default_value = "false" , action = ArgAction::SetTrue)]
+    pub remote: bool,
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct LayersArgs {
+    /// only show files of the target
+    #[arg(short = 't', long = "target")]
+    pub target: Option<String>,
+}
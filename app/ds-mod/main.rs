@@ -10,7 +10,7 @@ use crate::args::GxModCmd;
 use clap::Parser;
 use orion_syspec::{
     error::{SpecResult, report_error},
-    vars::setup_start_env_vars,
+    vars::RuntimeContext,
 };
 use spec::do_mod_cmd;
 
@@ -29,9 +29,9 @@ async fn main() {
 pub struct GxMod {}
 impl GxMod {
     pub async fn run() -> SpecResult<()> {
-        setup_start_env_vars()?;
+        let runtime = RuntimeContext::detect()?;
         let cmd = GxModCmd::parse();
-        do_mod_cmd(cmd).await?;
+        do_mod_cmd(cmd, runtime).await?;
         Ok(())
     }
 }
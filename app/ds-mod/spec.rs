@@ -1,17 +1,27 @@
 use orion_error::{ErrorConv, ErrorOwe};
-use orion_syspec::error::SpecResult;
+use orion_error::{StructError, UvsConfFrom};
+use orion_syspec::addr::{AddrType, GitAddr};
+use orion_syspec::const_vars::MOD_DIR;
+use orion_syspec::error::{LocalizeReason, SpecReason, SpecResult, ToErr};
 use orion_syspec::infra::configure_dfx_logging;
+use orion_syspec::module::ModelSTD;
+use orion_syspec::module::check::ModChecker;
+use orion_syspec::module::helm::{ChartMeta, fetch_chart};
+use orion_syspec::module::overlay::ModExtends;
 use orion_syspec::module::proj::ModProject;
+use orion_syspec::module::registry::Registry;
+use orion_syspec::module::scaffold::ScaffoldCatalog;
 use orion_syspec::module::spec::make_mod_spec_example;
 use orion_syspec::types::{Localizable, LocalizeOptions, Persistable};
 use orion_syspec::update::UpdateOptions;
-use orion_syspec::vars::ValueDict;
+use orion_syspec::vars::{RuntimeContext, ValueDict};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use crate::args::{self};
 
-pub async fn do_mod_cmd(cmd: args::GxModCmd) -> SpecResult<()> {
-    let current_dir = std::env::current_dir().expect("无法获取当前目录");
+pub async fn do_mod_cmd(cmd: args::GxModCmd, runtime: RuntimeContext) -> SpecResult<()> {
+    let current_dir = runtime.cwd().clone();
     match cmd {
         args::GxModCmd::Example => {
             let spec = make_mod_spec_example().err_conv()?;
@@ -21,23 +31,212 @@ pub async fn do_mod_cmd(cmd: args::GxModCmd) -> SpecResult<()> {
             let project_dir = current_dir.join(spec_args.name());
             std::fs::create_dir(&project_dir).owe_res()?;
             configure_dfx_logging(&spec_args);
-            let spec = ModProject::make_new(&project_dir, spec_args.name.as_str()).err_conv()?;
-            spec.save().err_conv()?;
+            if spec_args.kind.is_none() && spec_args.scaffold.is_none() {
+                let spec =
+                    ModProject::make_new(&project_dir, spec_args.name.as_str()).err_conv()?;
+                spec.save().err_conv()?;
+                return Ok(());
+            }
+            let mut catalog = ScaffoldCatalog::builtin();
+            if let Some(repo) = &spec_args.scaffold {
+                let options = UpdateOptions::default().with_runtime(runtime.clone());
+                catalog = catalog.with_git(&GitAddr::from(repo), &options).await?;
+            }
+            let kind = spec_args.kind.as_deref().unwrap_or("host");
+            let targets = parse_targets(&spec_args.targets)?;
+            ModProject::make_scaffold(
+                &project_dir,
+                spec_args.name.as_str(),
+                catalog.find(kind)?,
+                &targets,
+            )?;
         }
         args::GxModCmd::Update(dfx) => {
             configure_dfx_logging(&dfx);
+            let options = UpdateOptions::from((dfx.force, ValueDict::default()))
+                .with_runtime(runtime.clone());
+            // 基础层需在加载前拉取并合并到 .extends/merged
+            let mod_root = current_dir.join(MOD_DIR);
+            ModExtends::fetch_all(&mod_root, &options).await?;
+            ModExtends::materialize_all(&mod_root)?;
             let spec = ModProject::load(&current_dir).err_conv()?;
-            let options = UpdateOptions::from((dfx.force, ValueDict::default()));
             spec.update(&options).await.err_conv()?;
         }
         args::GxModCmd::Localize(args) => {
             configure_dfx_logging(&args);
             let spec = ModProject::load(&current_dir).err_conv()?;
-            let dict = spec.load_global_value(args.value())?;
-            spec.localize(None, LocalizeOptions::new(dict, args.use_default_value))
-                .await
-                .err_conv()?;
+            let dict = spec.load_global_value(
+                args.value(),
+                args.value_layer(),
+                args.value_mode,
+                &runtime,
+            )?;
+            let options = LocalizeOptions::new(dict, args.use_default_value)
+                .with_profile(args.profile.clone())
+                .with_runtime(runtime.clone());
+            spec.localize(None, options).await.err_conv()?;
+        }
+        args::GxModCmd::Explain(args) => {
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            let options = LocalizeOptions::new(ValueDict::default(), args.use_default_value)
+                .with_profile(args.profile.clone())
+                .with_runtime(runtime.clone());
+            for (model, explain) in spec.explain(
+                args.key(),
+                args.value(),
+                args.value_layer(),
+                args.value_mode,
+                &options,
+            )? {
+                println!("[{}]", model);
+                println!("{}", explain);
+            }
+        }
+        args::GxModCmd::Search(args) => {
+            let registry = Registry::load(args.registry.clone())?;
+            let index = registry
+                .fetch(&UpdateOptions::default().with_runtime(runtime.clone()))
+                .await?;
+            println!("{:<20} {:<12} desc", "module", "latest");
+            for module in index.search(args.keyword()) {
+                println!("{}", module);
+            }
+        }
+        args::GxModCmd::Info(args) => {
+            let registry = Registry::load(args.registry.clone())?;
+            let index = registry
+                .fetch(&UpdateOptions::default().with_runtime(runtime.clone()))
+                .await?;
+            let module = index.find(args.name())?;
+            println!("{}", module);
+            for ver in module.versions() {
+                let targets: Vec<String> = ver.targets().iter().map(|x| x.to_string()).collect();
+                println!("  {:<12} {}", ver.version(), targets.join(", "));
+            }
+        }
+        args::GxModCmd::Package(args) => {
+            configure_dfx_logging(&args);
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            // 校验通过后才升级版本
+            spec.check_package(&runtime)?;
+            if let Some(bump) = args.bump {
+                println!("version: {}", spec.bump_version(bump)?);
+            }
+            let package = spec.package(&runtime.resolve(args.out()))?;
+            println!("package: {}", package.file().display());
+            println!("sha256: {}", package.sha256());
+            if let Some(addr) = args.upload() {
+                let addr = AddrType::from_str(addr).map_err(StructError::from_conf)?;
+                package.upload(&addr, args.method()).await?;
+            }
+        }
+        args::GxModCmd::Check(args) => {
+            let report = ModChecker::new(args.remote).check(&current_dir).await?;
+            // json 输出在 stdout, 失败时返回错误, 报告由 report_error 写到 stderr
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&report).owe_data()?);
+            } else {
+                for issue in report.issues() {
+                    println!("{}", issue);
+                }
+                println!("{} errors, {} warnings", report.errors(), report.warnings());
+            }
+            if report.has_error() {
+                return SpecReason::from(LocalizeReason::Validate(format!(
+                    "{} module check errors",
+                    report.errors()
+                )))
+                .err_result();
+            }
+        }
+        args::GxModCmd::Layers(args) => {
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            let layers = spec.target_layers()?;
+            if layers.is_empty() {
+                println!("no target extends a base module");
+            }
+            for (target, extends, layers) in layers {
+                if args
+                    .target
+                    .as_ref()
+                    .is_some_and(|x| *x != target.to_string())
+                {
+                    continue;
+                }
+                println!("{} extends {}", target, extends.extends());
+                for file in layers.files() {
+                    println!("  {}", file);
+                }
+                for file in layers.deleted() {
+                    println!("  {:<40} {:<10}", file, "deleted");
+                }
+            }
+        }
+        args::GxModCmd::Schema(args::SchemaCmd::Export) => {
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            for file in spec.export_schema()? {
+                println!("export schema: {}", file.display());
+            }
+        }
+        args::GxModCmd::Import(args::ImportCmd::Helm(args)) => {
+            let options = UpdateOptions::default().with_runtime(runtime.clone());
+            let chart_dir = fetch_chart(&args.chart, &options).await?;
+            let meta = ChartMeta::load(&chart_dir)?;
+            let name = args.name.clone().unwrap_or(meta.name().clone());
+            let project_dir = current_dir.join(&name);
+            std::fs::create_dir(&project_dir).owe_res()?;
+            let targets = parse_targets(&args.targets)?;
+            ModProject::import_helm(&project_dir, Some(&name), &chart_dir, &targets)?;
+            println!(
+                "import chart {} {} to {}",
+                meta.name(),
+                meta.version(),
+                project_dir.display()
+            );
+        }
+        args::GxModCmd::Values(args::ValuesCmd::Validate(args)) => {
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            let issues = spec.validate_values(args.value(), &runtime)?;
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                return SpecReason::from(LocalizeReason::Validate(format!(
+                    "{} value issues",
+                    issues.len()
+                )))
+                .err_result();
+            }
+            println!("values ok");
+        }
+        args::GxModCmd::Lint(args) => {
+            let spec = ModProject::load(&current_dir).err_conv()?;
+            let mut errors = 0;
+            for (model, issues) in
+                spec.lint(args.value(), args.value_layer(), args.value_mode, &runtime)?
+            {
+                println!("[{}]", model);
+                for issue in &issues {
+                    println!("{}", issue);
+                }
+                errors += issues.iter().filter(|x| x.is_error()).count();
+            }
+            if errors > 0 {
+                return SpecReason::from(LocalizeReason::Validate(format!(
+                    "{} undefined variable refs",
+                    errors
+                )))
+                .err_result();
+            }
+            println!("lint ok");
         }
     }
     Ok(())
 }
+
+fn parse_targets(targets: &[String]) -> SpecResult<Vec<ModelSTD>> {
+    targets
+        .iter()
+        .map(|x| ModelSTD::from_str(x).map_err(StructError::from_conf))
+        .collect()
+}
@@ -1,6 +1,6 @@
 use clap::{ArgAction, Parser};
 use derive_getters::Getters;
-use orion_syspec::infra::DfxArgsGetter;
+use orion_syspec::{addr::AddrType, infra::DfxArgsGetter, module::ModelSTD, vars::ImportMode};
 
 #[derive(Debug, Parser)] // requires `derive` feature
 #[command(name = "ds-sys")]
@@ -9,6 +9,20 @@ pub enum GSysCmd {
     New(NewArgs),
     Update(UpdateArgs),
     Localize(LocalArgs),
+    /// show where a module value comes from
+    /// eg: ds-sys explain mysql DB_HOST
+    Explain(ExplainArgs),
+    /// list modules with newer versions in remote git tags
+    Outdated(OutdatedArgs),
+    /// add module ref from registry index to mod_list.yml
+    /// eg: ds-sys add mysql@8.0.3
+    Add(AddArgs),
+    /// sum enabled modules' res_spec and check against resource.yml
+    /// eg: ds-sys capacity --model x86-ubt22-k8s
+    Capacity(CapacityArgs),
+    /// show module ports allocated per node/namespace and conflicts
+    /// eg: ds-sys ports --conflict
+    Ports(PortsArgs),
 }
 
 #[derive(Debug, Args, Getters)]
@@ -32,6 +46,10 @@ pub struct UpdateArgs {
     /// 1,2,3: force update remote git
     #[arg(short = 'f', long = "force", default_value = "0")]
     pub force: usize,
+    /// target for modules with `model: auto`, default detect from host;
+    /// eg: --model x86-ubt22-host
+    #[arg(long = "model")]
+    pub model: Option<ModelSTD>,
 }
 impl DfxArgsGetter for UpdateArgs {
     fn debug_level(&self) -> usize {
@@ -51,14 +69,56 @@ pub struct LocalArgs {
     #[arg(long = "log")]
     pub log: Option<String>,
 
-    /// use vlaue file; eg: --value cicd_value.yml
+    /// use value files instead of values/value.yml, repeatable and later wins; eg: --value cicd_value.yml
+    /// support yaml(helm values)/toml/json/ini/env
     #[arg(long = "value")]
-    pub value: Option<String>,
+    pub value: Vec<String>,
+    /// value files layered on top of values/value.yml (or --value), repeatable and later wins;
+    /// eg: --value-layer .env
+    #[arg(long = "value-layer")]
+    pub value_layer: Vec<String>,
+    /// nested key import mode: structured | flatten (a.b.c)
+    #[arg(long = "value-mode", default_value = "structured")]
+    pub value_mode: ImportMode,
+    /// use value profile overlay; eg: --profile prod  (values/profiles/prod.yml)
+    #[arg(long = "profile")]
+    pub profile: Option<String>,
 
     /// enable default module mode
     #[arg(long = "default", default_value = "false" , action = ArgAction::SetTrue)]
     pub use_default_value: bool,
+    /// target for modules with `model: auto`; eg: --model x86-ubt22-host
+    #[arg(long = "model")]
+    pub model: Option<ModelSTD>,
 }
+#[derive(Debug, Args, Getters)]
+pub struct ExplainArgs {
+    /// module name
+    pub module: String,
+    /// variable name
+    pub key: String,
+    /// use value files instead of values/value.yml, repeatable and later wins; eg: --value cicd_value.yml
+    /// support yaml(helm values)/toml/json/ini/env
+    #[arg(long = "value")]
+    pub value: Vec<String>,
+    /// value files layered on top of values/value.yml (or --value), repeatable and later wins;
+    /// eg: --value-layer .env
+    #[arg(long = "value-layer")]
+    pub value_layer: Vec<String>,
+    /// nested key import mode: structured | flatten (a.b.c)
+    #[arg(long = "value-mode", default_value = "structured")]
+    pub value_mode: ImportMode,
+    /// use value profile overlay; eg: --profile prod
+    #[arg(long = "profile")]
+    pub profile: Option<String>,
+    /// enable default module mode
+    #[arg(long = "default", default_value = "false" , action = ArgAction::SetTrue)]
+    pub use_default_value: bool,
+    /// target for modules with `model: auto`; eg: --model x86-ubt22-host
+    #[arg(long = "model")]
+    pub model: Option<ModelSTD>,
+}
+
 impl DfxArgsGetter for LocalArgs {
     fn debug_level(&self) -> usize {
         self.debug
@@ -68,3 +128,84 @@ impl DfxArgsGetter for LocalArgs {
         self.log.clone()
     }
 }
+
+#[derive(Debug, Args, Getters)]
+pub struct OutdatedArgs {
+    #[arg(short = 'd', long = "debug", default_value = "0")]
+    pub debug: usize,
+    /// config log ; eg: --log  cmd=debug,parse=info
+    #[arg(long = "log")]
+    pub log: Option<String>,
+    /// show up-to-date modules too
+    #[arg(long = "all", default_value = "false" , action = ArgAction::SetTrue)]
+    pub all: bool,
+}
+
+impl DfxArgsGetter for OutdatedArgs {
+    fn debug_level(&self) -> usize {
+        self.debug
+    }
+
+    fn log_setting(&self) -> Option<String> {
+        self.log.clone()
+    }
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct AddArgs {
+    /// module name with optional version or semver range; eg: mysql@8.0.3, redis@^7
+    pub module: String,
+    /// registry index address, repeatable; default: ~/.galaxy/registry.yml
+    #[arg(long = "registry")]
+    pub registry: Vec<AddrType>,
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct CapacityArgs {
+    #[arg(short = 'd', long = "debug", default_value = "0")]
+    pub debug: usize,
+    /// config log ; eg: --log  cmd=debug,parse=info
+    #[arg(long = "log")]
+    pub log: Option<String>,
+    /// target for modules with `model: auto`; eg: --model x86-ubt22-host
+    #[arg(long = "model")]
+    pub model: Option<ModelSTD>,
+    /// resource file; default: sys/resource.yml
+    #[arg(short = 'r', long = "resource")]
+    pub resource: Option<String>,
+}
+
+impl DfxArgsGetter for CapacityArgs {
+    fn debug_level(&self) -> usize {
+        self.debug
+    }
+
+    fn log_setting(&self) -> Option<String> {
+        self.log.clone()
+    }
+}
+
+#[derive(Debug, Args, Getters)]
+pub struct PortsArgs {
+    #[arg(short = 'd', long = "debug", default_value = "0")]
+    pub debug: usize,
+    /// config log ; eg: --log  cmd=debug,parse=info
+    #[arg(long = "log")]
+    pub log: Option<String>,
+    /// target for modules with `model: auto`; eg: --model x86-ubt22-host
+    #[arg(long = "model")]
+    pub model: Option<ModelSTD>,
+    /// only show ports moved from their default
+    #[arg(long = "conflict", default_value = "false" , action = ArgAction::SetTrue)]
+    pub conflict: bool,
+}
+
+impl DfxArgsGetter for PortsArgs {
+    fn debug_level(&self) -> usize {
+        self.debug
+    }
+
+    fn log_setting(&self) -> Option<String> {
+        self.log.clone()
+    }
+}
@@ -11,7 +11,7 @@ use args::GSysCmd;
 use clap::Parser;
 use orion_syspec::{
     error::{SpecResult, report_error},
-    vars::setup_start_env_vars,
+    vars::RuntimeContext,
 };
 use spec::do_sys_cmd;
 
@@ -30,10 +30,10 @@ async fn main() {
 pub struct GxSys {}
 impl GxSys {
     pub async fn run() -> SpecResult<()> {
-        setup_start_env_vars()?;
+        let runtime = RuntimeContext::detect()?;
         let cmd = GSysCmd::parse();
         debug!("galaxy flow running .....");
-        do_sys_cmd(cmd).await?;
+        do_sys_cmd(cmd, runtime).await?;
         Ok(())
     }
 }
@@ -2,16 +2,19 @@ use orion_error::ErrorConv;
 use orion_syspec::error::SpecResult;
 use orion_syspec::infra::configure_dfx_logging;
 use orion_syspec::module::proj::load_project_global_value;
+use orion_syspec::module::registry::Registry;
+use orion_syspec::resource::SysResource;
 use orion_syspec::system::proj::SysProject;
+use orion_syspec::system::spec::SysModelSpec;
 use orion_syspec::tools::make_new_path;
 use orion_syspec::types::LocalizeOptions;
 use orion_syspec::update::UpdateOptions;
-use orion_syspec::vars::ValueDict;
+use orion_syspec::vars::{RuntimeContext, ValueDict};
 
 use crate::args::GSysCmd;
 
-pub async fn do_sys_cmd(cmd: GSysCmd) -> SpecResult<()> {
-    let current_dir = std::env::current_dir().expect("无法获取当前目录");
+pub async fn do_sys_cmd(cmd: GSysCmd, runtime: RuntimeContext) -> SpecResult<()> {
+    let current_dir = runtime.cwd().clone();
     match cmd {
         GSysCmd::New(args) => {
             let new_prj = current_dir.join(args.name());
@@ -21,17 +24,96 @@ pub async fn do_sys_cmd(cmd: GSysCmd) -> SpecResult<()> {
         }
         GSysCmd::Update(dfx) => {
             configure_dfx_logging(&dfx);
-            let options = UpdateOptions::from((dfx.force, ValueDict::default()));
+            let options = UpdateOptions::from((dfx.force, ValueDict::default()))
+                .with_runtime(runtime.clone().with_host_model(dfx.model.clone()));
             let spec = SysProject::load(&current_dir).err_conv()?;
-            spec.update(&options).await.err_conv()?;
+            for choice in spec.update(&options).await.err_conv()? {
+                println!("{}", choice);
+            }
         }
         GSysCmd::Localize(args) => {
             configure_dfx_logging(&args);
             let spec = SysProject::load(&current_dir).err_conv()?;
-            let dict = load_project_global_value(spec.root_local(), args.value())?;
-            spec.localize(LocalizeOptions::new(dict, args.use_default_value))
-                .await
-                .err_conv()?;
+            let dict = load_project_global_value(
+                spec.root_local(),
+                args.value(),
+                args.value_layer(),
+                args.value_mode,
+                &runtime,
+            )?;
+            let options = LocalizeOptions::new(dict, args.use_default_value)
+                .with_profile(args.profile.clone())
+                .with_runtime(runtime.clone().with_host_model(args.model.clone()));
+            spec.localize(options).await.err_conv()?;
+        }
+        GSysCmd::Explain(args) => {
+            let spec = SysProject::load(&current_dir).err_conv()?;
+            let options = LocalizeOptions::new(ValueDict::default(), args.use_default_value)
+                .with_profile(args.profile.clone())
+                .with_runtime(runtime.clone().with_host_model(args.model.clone()));
+            let explain = spec.explain(
+                args.module(),
+                args.key(),
+                args.value(),
+                args.value_layer(),
+                args.value_mode,
+                &options,
+            )?;
+            println!("{}", explain);
+        }
+        GSysCmd::Add(args) => {
+            let (name, version) = match args.module().split_once('@') {
+                Some((name, version)) => (name, Some(version)),
+                None => (args.module().as_str(), None),
+            };
+            let registry = Registry::load(args.registry.clone())?;
+            let index = registry
+                .fetch(&UpdateOptions::default().with_runtime(runtime.clone()))
+                .await?;
+            let found = index.find(name)?.select(version)?;
+            let mut sys = SysModelSpec::load_from(&current_dir.join("sys")).err_conv()?;
+            sys.append_mod_ref(found.to_mod_ref(name))?;
+            println!("add {}@{}", name, found.version());
+        }
+        GSysCmd::Outdated(args) => {
+            configure_dfx_logging(&args);
+            let spec = SysProject::load(&current_dir).err_conv()?;
+            let options = UpdateOptions::from((0, ValueDict::default())).with_runtime(runtime);
+            println!(
+                "{:<20} {:<12} {:<12} {:<12}",
+                "module", "current", "wanted", "latest"
+            );
+            for item in spec.sys_spec().outdated(&options)? {
+                if args.all || item.is_outdated() {
+                    println!("{}", item);
+                }
+            }
+        }
+        GSysCmd::Capacity(args) => {
+            configure_dfx_logging(&args);
+            let spec = SysProject::load(&current_dir).err_conv()?;
+            let resource = match &args.resource {
+                Some(path) => SysResource::load(&current_dir.join(path))?,
+                None => spec.sys_spec().load_resource()?,
+            };
+            let runtime = runtime.with_host_model(args.model.clone());
+            let report = spec.sys_spec().capacity(&resource, &runtime)?;
+            println!("{}", report);
+        }
+        GSysCmd::Ports(args) => {
+            configure_dfx_logging(&args);
+            let spec = SysProject::load(&current_dir).err_conv()?;
+            let runtime = runtime.with_host_model(args.model.clone());
+            let plan = spec.sys_spec().port_plan(&runtime)?;
+            println!(
+                "{:<20} {:<12} {:<16} {:<5} {:<6}",
+                "module", "place", "var", "proto", "port"
+            );
+            for item in plan.iter() {
+                if !args.conflict || item.is_moved() {
+                    println!("{}", item);
+                }
+            }
         }
     }
     Ok(())
@@ -9,8 +9,9 @@ use home::home_dir;
 use log::warn;
 use orion_error::UvsResFrom;
 
+use super::version::{ResolvedVersion, parse_version_req, select_tag};
 use crate::{
-    error::SpecResult,
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
     log_guard,
     tools::{ensure_path, get_repo_name},
     types::AsyncUpdateable,
@@ -25,6 +26,9 @@ pub struct GitAddr {
     res: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tag: Option<String>,
+    // semver 范围, 如 ^1.2 / ~0.3.1; 未指定 tag 时按远程 tag 解析
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     branch: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -44,6 +48,7 @@ impl EnvEvalable<GitAddr> for GitAddr {
             repo: self.repo.env_eval(dict),
             res: self.res.env_eval(dict),
             tag: self.tag.env_eval(dict),
+            version: self.version.env_eval(dict),
             branch: self.branch.env_eval(dict),
             rev: self.rev.env_eval(dict),
             path: self.path.env_eval(dict),
@@ -64,6 +69,19 @@ impl GitAddr {
         self.tag = Some(tag.into());
         self
     }
+    pub fn version<S: Into<String>>(mut self, version: S) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+    pub fn repo(&self) -> &String {
+        &self.repo
+    }
+    pub fn tag_name(&self) -> Option<&String> {
+        self.tag.as_ref()
+    }
+    pub fn version_req(&self) -> Option<&String> {
+        self.version.as_ref()
+    }
     pub fn branch<S: Into<String>>(mut self, branch: S) -> Self {
         self.branch = Some(branch.into());
         self
@@ -251,6 +269,9 @@ impl GitAddr {
 #[async_trait]
 impl AsyncUpdateable for GitAddr {
     async fn update_local(&self, path: &Path, options: &UpdateOptions) -> SpecResult<PathBuf> {
+        if let Some((addr, _)) = self.resolve_version()? {
+            return addr.update_local(path, options).await;
+        }
         let mut name = get_repo_name(self.repo.as_str()).unwrap_or("unknow".into());
         if let Some(postfix) = self
             .rev
@@ -330,6 +351,56 @@ impl AsyncUpdateable for GitAddr {
 }
 
 impl GitAddr {
+    // 不下载仓库, 只列出远程 tag
+    pub fn remote_tags(&self) -> SpecResult<Vec<String>> {
+        let mut ctx = WithContext::want("list remote tags");
+        ctx.with("repo", &self.repo);
+        let mut remote = git2::Remote::create_detached(self.repo.as_str())
+            .owe_res()
+            .with(&ctx)?;
+        let callbacks = self.build_remote_callbacks();
+        let conn = remote
+            .connect_auth(git2::Direction::Fetch, Some(callbacks), None)
+            .owe_res()
+            .with(&ctx)?;
+        let mut tags: Vec<String> = Vec::new();
+        for head in conn.list().owe_res().with(&ctx)? {
+            if let Some(tag) = head.name().strip_prefix("refs/tags/") {
+                let tag = tag.trim_end_matches("^{}").to_string();
+                if !tags.contains(&tag) {
+                    tags.push(tag);
+                }
+            }
+        }
+        Ok(tags)
+    }
+
+    // version 范围解析为具体 tag; 已指定 tag 或未设置 version 时返回 None
+    pub fn resolve_version(&self) -> SpecResult<Option<(GitAddr, ResolvedVersion)>> {
+        let Some(req_text) = &self.version else {
+            return Ok(None);
+        };
+        if self.tag.is_some() {
+            warn!(target: "addr/git", "{} tag is set, ignore version {}", self.repo, req_text);
+            return Ok(None);
+        }
+        let req = parse_version_req(req_text)?;
+        let tags = self.remote_tags()?;
+        let Some((tag, version)) = select_tag(&req, &tags) else {
+            return SpecReason::from(ElementReason::Version(format!(
+                "no tag of {} matches {}",
+                self.repo, req_text
+            )))
+            .err_result();
+        };
+        info!(target: "addr/git", "{} {} resolved to {}", self.repo, req_text, tag);
+        let resolved = ResolvedVersion::new(req_text.as_str(), tag.as_str(), &version);
+        // 解析后固定到 tag, 不再保留 version 范围
+        let mut addr = self.clone().tag(tag.as_str());
+        addr.version = None;
+        Ok(Some((addr, resolved)))
+    }
+
     pub fn sync_repo(&self, target_dir: &Path) -> Result<(), git2::Error> {
         // 尝试打开现有仓库
         match Repository::open(target_dir) {
@@ -582,4 +653,45 @@ mod tests {
         assert!(head.shorthand().unwrap_or("").contains("develop"));
         Ok(())
     }
+
+    #[test]
+    fn test_git_addr_resolve_version() -> SpecResult<()> {
+        let temp_dir = tempdir().owe_res()?;
+        let repo = git2::Repository::init(temp_dir.path()).owe_res()?;
+        let sig = git2::Signature::now("test", "test@example.com").owe_res()?;
+        let tree_id = repo.index().owe_res()?.write_tree().owe_res()?;
+        let tree = repo.find_tree(tree_id).owe_res()?;
+        let commit = repo
+            .commit(Some("HEAD"), &sig, &sig, "init", &tree, &[])
+            .owe_res()?;
+        let obj = repo.find_object(commit, None).owe_res()?;
+        for tag in ["v1.0.0", "v1.2.0", "v1.3.0-beta.1", "v2.0.0"] {
+            repo.tag_lightweight(tag, &obj, false).owe_res()?;
+        }
+        let addr = GitAddr::from(temp_dir.path().display().to_string());
+        let mut tags = addr.remote_tags()?;
+        tags.sort();
+        assert_eq!(tags, vec!["v1.0.0", "v1.2.0", "v1.3.0-beta.1", "v2.0.0"]);
+
+        let (resolved_addr, resolved) = addr.clone().version("^1").resolve_version()?.assert();
+        assert_eq!(resolved_addr.tag_name().map(|x| x.as_str()), Some("v1.2.0"));
+        assert_eq!(resolved_addr.version_req(), None);
+        assert!(resolved_addr.resolve_version()?.is_none());
+        assert_eq!(resolved.version(), "1.2.0");
+        let (_, resolved) = addr
+            .clone()
+            .version(">=1.3.0-beta.0, <2")
+            .resolve_version()?
+            .assert();
+        assert_eq!(resolved.tag(), "v1.3.0-beta.1");
+        assert!(addr.clone().version("^3").resolve_version().is_err());
+        assert!(
+            addr.clone()
+                .tag("v1.0.0")
+                .version("^1")
+                .resolve_version()?
+                .is_none()
+        );
+        Ok(())
+    }
 }
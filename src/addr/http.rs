@@ -36,6 +36,10 @@ impl HttpAddr {
         }
     }
 
+    pub fn with_url<S: Into<String>>(mut self, url: S) -> Self {
+        self.url = url.into();
+        self
+    }
     pub fn with_credentials<S: Into<String>>(mut self, username: S, password: S) -> Self {
         self.username = Some(username.into());
         self.password = Some(password.into());
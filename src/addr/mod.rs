@@ -2,6 +2,7 @@ pub mod git;
 pub mod http;
 pub mod local;
 pub mod types;
+pub mod version;
 
 pub use git::GitAddr;
 pub use http::HttpAddr;
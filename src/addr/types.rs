@@ -1,10 +1,11 @@
 use crate::{predule::*, vars::EnvDict};
 
 use derive_more::From;
+use std::str::FromStr;
 
 use crate::{types::AsyncUpdateable, vars::EnvEvalable};
 
-use super::{GitAddr, HttpAddr, LocalAddr};
+use super::{GitAddr, HttpAddr, LocalAddr, version::ResolvedVersion};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -27,10 +28,53 @@ impl EnvEvalable<AddrType> for AddrType {
     }
 }
 
+impl AddrType {
+    // git 地址的 version 范围解析为具体 tag, 其他地址原样返回
+    pub fn resolve_version(&self) -> SpecResult<(AddrType, Option<ResolvedVersion>)> {
+        if let AddrType::Git(addr) = self
+            && let Some((addr, resolved)) = addr.resolve_version()?
+        {
+            return Ok((AddrType::Git(addr), Some(resolved)));
+        }
+        Ok((self.clone(), None))
+    }
+}
+
+// 命令行中的地址: *.git 或 git@ 为 git, http(s) 为 http, 其余为本地路径
+impl FromStr for AddrType {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty address".into());
+        }
+        if s.ends_with(".git") || s.starts_with("git@") {
+            Ok(AddrType::Git(GitAddr::from(s)))
+        } else if s.starts_with("http://") || s.starts_with("https://") {
+            Ok(AddrType::Http(HttpAddr::from(s)))
+        } else {
+            Ok(AddrType::Local(LocalAddr::from(s)))
+        }
+    }
+}
+
+impl std::fmt::Display for AddrType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AddrType::Git(v) => match v.tag_name() {
+                Some(tag) => write!(f, "{}@{}", v.repo(), tag),
+                None => write!(f, "{}", v.repo()),
+            },
+            AddrType::Http(v) => write!(f, "{}", v.url()),
+            AddrType::Local(v) => write!(f, "{}", v.path()),
+        }
+    }
+}
+
 #[async_trait]
 impl AsyncUpdateable for AddrType {
     async fn update_local(&self, path: &Path, options: &UpdateOptions) -> SpecResult<PathBuf> {
-        let ins = self.clone().env_eval(options.values());
+        let ins = self.clone().env_eval(&options.env_dict());
         match ins {
             AddrType::Git(addr) => addr.update_local(path, options).await,
             AddrType::Http(addr) => addr.update_local(path, options).await,
@@ -44,7 +88,7 @@ impl AsyncUpdateable for AddrType {
         name: &str,
         options: &UpdateOptions,
     ) -> SpecResult<PathBuf> {
-        let ins = self.clone().env_eval(options.values());
+        let ins = self.clone().env_eval(&options.env_dict());
         match ins {
             AddrType::Git(addr) => addr.update_rename(path, name, options).await,
             AddrType::Http(addr) => addr.update_rename(path, name, options).await,
@@ -0,0 +1,158 @@
+use std::fmt::Display;
+
+use derive_getters::Getters;
+use semver::{Version, VersionReq};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::{ElementReason, SpecReason, SpecResult, ToErr};
+
+// tag 允许 v 前缀: v1.2.0 / 1.2.0-beta.1
+pub fn tag_version(tag: &str) -> Option<Version> {
+    Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+pub fn parse_version_req(req: &str) -> SpecResult<VersionReq> {
+    VersionReq::parse(req)
+        .map_err(|e| SpecReason::from(ElementReason::Version(format!("{}: {}", req, e))).to_err())
+}
+
+// 满足 req 的最高版本; 预发布版本仅在 req 中写明同版本号的预发布时匹配
+pub fn select_tag<'a>(req: &VersionReq, tags: &'a [String]) -> Option<(&'a String, Version)> {
+    tags.iter()
+        .filter_map(|x| tag_version(x).map(|v| (x, v)))
+        .filter(|(_, v)| req.matches(v))
+        .max_by(|a, b| a.1.cmp(&b.1))
+}
+
+// 最高版本; with_pre 为 false 时忽略预发布
+pub fn latest_tag(tags: &[String], with_pre: bool) -> Option<(&String, Version)> {
+    tags.iter()
+        .filter_map(|x| tag_version(x).map(|v| (x, v)))
+        .filter(|(_, v)| with_pre || v.pre.is_empty())
+        .max_by(|a, b| a.1.cmp(&b.1))
+}
+
+// 版本范围解析结果, 保存在 mods/<name>/resolved.yml
+#[derive(Getters, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedVersion {
+    req: String,
+    tag: String,
+    version: String,
+}
+
+impl ResolvedVersion {
+    pub fn new<S: Into<String>>(req: S, tag: S, version: &Version) -> Self {
+        Self {
+            req: req.into(),
+            tag: tag.into(),
+            version: version.to_string(),
+        }
+    }
+}
+
+// ds-sys outdated 的一行
+#[derive(Getters, Clone, Debug, PartialEq, Serialize)]
+pub struct OutdatedItem {
+    name: String,
+    current: Option<String>,
+    // 满足版本范围的最新版本
+    wanted: Option<String>,
+    // 不考虑版本范围的最新版本
+    latest: Option<String>,
+}
+
+impl OutdatedItem {
+    pub fn new<S: Into<String>>(
+        name: S,
+        current: Option<Version>,
+        wanted: Option<Version>,
+        latest: Option<Version>,
+    ) -> Self {
+        let newer = |x: Option<Version>| x.filter(|v| current.as_ref().is_none_or(|c| v > c));
+        Self {
+            name: name.into(),
+            current: current.as_ref().map(|x| x.to_string()),
+            wanted: newer(wanted).map(|x| x.to_string()),
+            latest: newer(latest).map(|x| x.to_string()),
+        }
+    }
+    pub fn is_outdated(&self) -> bool {
+        self.wanted.is_some() || self.latest.is_some()
+    }
+}
+
+impl Display for OutdatedItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let show = |x: &Option<String>| x.clone().unwrap_or("-".into());
+        write!(
+            f,
+            "{:<20} {:<12} {:<12} {:<12}",
+            self.name,
+            show(&self.current),
+            show(&self.wanted),
+            show(&self.latest)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags() -> Vec<String> {
+        [
+            "v1.0.0",
+            "v1.2.3",
+            "1.3.0-beta.1",
+            "v1.4.0-rc.1",
+            "v2.0.0",
+            "latest",
+            "v0.3.1",
+            "v0.3.5",
+            "v0.4.0",
+        ]
+        .iter()
+        .map(|x| x.to_string())
+        .collect()
+    }
+
+    fn select(req: &str) -> Option<String> {
+        let tags = tags();
+        select_tag(&parse_version_req(req).unwrap(), &tags).map(|x| x.0.clone())
+    }
+
+    #[test]
+    fn test_select_tag() {
+        assert_eq!(select("^1.2").as_deref(), Some("v1.2.3"));
+        assert_eq!(select("~0.3.1").as_deref(), Some("v0.3.5"));
+        assert_eq!(select("^0.3").as_deref(), Some("v0.3.5"));
+        assert_eq!(
+            select(">=1.3.0-beta.0, <2").as_deref(),
+            Some("1.3.0-beta.1")
+        );
+        assert_eq!(select("^1.4.0-rc.0").as_deref(), Some("v1.4.0-rc.1"));
+        assert_eq!(select("^3"), None);
+        assert!(parse_version_req("^x").is_err());
+        let tags = tags();
+        assert_eq!(latest_tag(&tags, false).unwrap().0, "v2.0.0");
+    }
+
+    #[test]
+    fn test_outdated_item() {
+        let item = OutdatedItem::new(
+            "redis",
+            Some(Version::new(1, 2, 0)),
+            Some(Version::new(1, 2, 3)),
+            Some(Version::new(2, 0, 0)),
+        );
+        assert!(item.is_outdated());
+        assert_eq!(item.wanted().as_deref(), Some("1.2.3"));
+        let item = OutdatedItem::new(
+            "mysql",
+            Some(Version::new(2, 0, 0)),
+            Some(Version::new(2, 0, 0)),
+            Some(Version::new(2, 0, 0)),
+        );
+        assert!(!item.is_outdated());
+    }
+}
@@ -6,26 +6,53 @@ pub const SYS_MODEL_SPC_ROOT: &str = "./example/sys-model-spec";
 pub const WORK_GXL: &str = "work.gxl";
 pub const ADM_GXL: &str = "adm.gxl";
 pub const MOD_LIST_YML: &str = "mod_list.yml";
+// 模块间依赖声明, 位于 mod/ 下
+pub const MOD_REQUIRES_YML: &str = "requires.yml";
+// 由依赖图生成的模块顺序
+pub const MOD_ORDER_YML: &str = "mod_order.yml";
+pub const VERSION_TXT: &str = "version.txt";
+// 模块 version 范围解析出的 tag
+pub const RESOLVED_YML: &str = "resolved.yml";
+// 模块包内的文件清单
+pub const MANIFEST_YML: &str = "manifest.yml";
 pub const RESOURCE_YML: &str = "resource.yml";
 pub const NET_RES_YML: &str = "net_res.yml";
 pub const VARS_YML: &str = "vars.yml";
+// target 继承的基础模块声明
+pub const EXTENDS_YML: &str = "extends.yml";
+// 合并后各文件的来源层
+pub const LAYERS_YML: &str = ".layers.yml";
+// 拉取的基础模块
+pub const EXTENDS_DIR: &str = ".extends";
+pub const VARS_SCHEMA_JSON: &str = "vars.schema.json";
 pub const SPEC_YML: &str = "spec.yml";
 pub const VALUE_FILE: &str = "value.yml";
 pub const DEFAULT_VALUE_FILE: &str = "_default.yml";
 pub const USER_VALUE_FILE: &str = "value.yml";
 pub const SAMPLE_VALUE_FILE: &str = "_value.yml";
 pub const VALUE_DIR: &str = "values";
+pub const PROFILES_DIR: &str = "profiles";
 pub const GLOBAL_VALUE_FILE: &str = "value/value.yml";
 pub const USED_JSON: &str = "_used.json";
 pub const USED_READABLE_FILE: &str = "_used.yml";
+// expr 生成的随机值, 重复 localize 时保持不变
+pub const GENERATED_VALUE_FILE: &str = "_generated.yml";
 pub const ARTIFACT_YML: &str = "artifact.yml";
 pub const DEPENDS_YML: &str = "depends.yml";
 pub const CONF_SPEC_YML: &str = "conf.yml";
 pub const SETTING_YML: &str = "setting.yml";
 pub const LOGS_SPEC_YML: &str = "logs.yml";
 pub const RES_SPEC_YML: &str = "res.yml";
+// 模块监听端口声明 (spec/ports.yml); 系统下为端口范围配置
+pub const PORTS_YML: &str = "ports.yml";
+// 系统端口分配记录
+pub const PORT_ALLOC_YML: &str = "port_alloc.yml";
 pub const SPEC_DIR: &str = "spec";
 pub const MOD_DIR: &str = "mod";
+// 系统下各模块的本地目录
+pub const MODS_DIR: &str = "mods";
+// 内联模块源文件目录, update 时复制到 mods/<name>
+pub const INLINE_MODS_DIR: &str = "inline_mods";
 pub const LOCAL_DIR: &str = "local";
 pub const CONFS_DIR: &str = "confs";
 pub const WORKFLOWS_DIR: &str = "workflows";
@@ -18,16 +18,31 @@ pub enum SpecReason {
 pub enum ElementReason {
     #[error("miss:{0}")]
     Miss(String),
+    #[error("cycle:{0}")]
+    Cycle(String),
+    #[error("version:{0}")]
+    Version(String),
+    #[error("duplicate:{0}")]
+    Duplicate(String),
 }
 #[derive(Clone, Debug, Serialize, PartialEq, Error)]
 pub enum LocalizeReason {
     #[error("miss:{0}")]
     Templatize(String),
+    #[error("secret:{0}")]
+    Secret(String),
+    #[error("validate:{0}")]
+    Validate(String),
+    #[error("expr:{0}")]
+    Expr(String),
 }
 impl ErrorCode for ElementReason {
     fn error_code(&self) -> i32 {
         match self {
             ElementReason::Miss(_) => 531,
+            ElementReason::Cycle(_) => 532,
+            ElementReason::Version(_) => 533,
+            ElementReason::Duplicate(_) => 534,
         }
     }
 }
@@ -36,6 +51,9 @@ impl ErrorCode for LocalizeReason {
     fn error_code(&self) -> i32 {
         match self {
             LocalizeReason::Templatize(_) => 541,
+            LocalizeReason::Secret(_) => 542,
+            LocalizeReason::Validate(_) => 543,
+            LocalizeReason::Expr(_) => 544,
         }
     }
 }
@@ -76,58 +94,58 @@ pub type SpecError = StructError<SpecReason>;
 pub const PATH_NOT_EXIST: &str = "path not exists";
 
 pub fn report_error(e: StructError<SpecReason>) {
-    println!("Run Error (Code: {})", e.error_code());
-    println!("--------------------------");
+    eprintln!("Run Error (Code: {})", e.error_code());
+    eprintln!("--------------------------");
     if let Some(target) = e.target() {
-        println!("[TARGET]:\n{}\n", target);
+        eprintln!("[TARGET]:\n{}\n", target);
     }
-    println!("[REASON]:");
+    eprintln!("[REASON]:");
     match e.get_reason() {
         SpecReason::Uvs(uvs_reason) => match uvs_reason {
             UvsReason::LogicError(e) => {
-                println!("LOGIC ERROR: {}\n", e);
+                eprintln!("LOGIC ERROR: {}\n", e);
             }
             UvsReason::BizError(e) => {
-                println!("BIZ ERROR: {}\n", e);
+                eprintln!("BIZ ERROR: {}\n", e);
             }
             UvsReason::DataError(e, _) => {
-                println!("DATA ERROR: {}\n", e);
+                eprintln!("DATA ERROR: {}\n", e);
             }
             UvsReason::SysError(e) => {
-                println!("SYS ERROR: {}\n", e);
+                eprintln!("SYS ERROR: {}\n", e);
             }
             UvsReason::ResError(e) => {
-                println!("RES ERROR: {}\n", e);
+                eprintln!("RES ERROR: {}\n", e);
             }
             UvsReason::ConfError(e) => {
-                println!("CONF ERROR: {}\n", e);
+                eprintln!("CONF ERROR: {}\n", e);
             }
             UvsReason::RuleError(e) => {
-                println!("RULE ERROR: {}\n", e);
+                eprintln!("RULE ERROR: {}\n", e);
             }
             UvsReason::PrivacyError(e) => {
-                println!("PRIVACY ERROR: {}\n", e);
+                eprintln!("PRIVACY ERROR: {}\n", e);
             }
         },
 
         SpecReason::Localize(e) => {
-            println!("Localize ERROR: {}\n", e);
+            eprintln!("Localize ERROR: {}\n", e);
         }
         SpecReason::Element(e) => {
-            println!("Element ERROR: {}\n", e);
+            eprintln!("Element ERROR: {}\n", e);
         }
         SpecReason::UnKnow => {
-            println!("Unknow Error!\n");
+            eprintln!("Unknow Error!\n");
         }
     }
     if let Some(pos) = e.position() {
-        println!("\n[POSITION]:\n{}", pos);
+        eprintln!("\n[POSITION]:\n{}", pos);
     }
     if let Some(detail) = e.detail() {
-        println!("\n[DETAIL]:\n{}", detail);
+        eprintln!("\n[DETAIL]:\n{}", detail);
     }
-    println!("\n[CONTEXT]:\n");
+    eprintln!("\n[CONTEXT]:\n");
     for x in e.context() {
-        println!("{}", x)
+        eprintln!("{}", x)
     }
 }
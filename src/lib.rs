@@ -8,6 +8,7 @@ pub mod const_vars;
 pub mod error;
 pub mod module;
 pub mod resource;
+pub mod secret;
 pub mod software;
 pub mod spec;
 pub mod system;
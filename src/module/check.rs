@@ -0,0 +1,515 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use derive_getters::Getters;
+use derive_more::Display as DisplayEx;
+use glob::Pattern;
+use orion_error::{ErrorOwe, StructErrorTrait};
+use serde_derive::Serialize;
+
+use super::{
+    ModelSTD, localize::LocalizeTemplate, model::ModTargetPaths, overlay::ModExtends,
+    require::ModRequires, setting::Setting,
+};
+use crate::{
+    addr::AddrType,
+    artifact::ArtifactPackage,
+    const_vars::{
+        EXTENDS_YML, MOD_DIR, MOD_REQUIRES_YML, SPEC_DIR, VARS_YML, VERSION_TXT, WORKFLOWS_DIR,
+    },
+    error::{SpecReason, SpecResult},
+    module::{depend::DependencySet, require::parse_version},
+    resource::CaculateResSpec,
+    software::LogsSpec,
+    task::OperationType,
+    tools::get_sub_dirs,
+    types::{Configable, Persistable},
+    vars::{ValueDict, VarCollection},
+    workflow::{act::Workflow, prj::GxlProject},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, DisplayEx)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckLevel {
+    #[display("error")]
+    Error,
+    #[display("warning")]
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, DisplayEx)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckKind {
+    #[display("missing")]
+    Missing,
+    #[display("invalid")]
+    Invalid,
+    #[display("model")]
+    Model,
+    #[display("workflow")]
+    Workflow,
+    #[display("template")]
+    Template,
+    #[display("setting")]
+    Setting,
+    #[display("artifact")]
+    Artifact,
+    #[display("extends")]
+    Extends,
+}
+
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct CheckIssue {
+    level: CheckLevel,
+    kind: CheckKind,
+    path: PathBuf,
+    message: String,
+}
+
+impl Display for CheckIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}: {}",
+            self.path.display(),
+            self.level,
+            self.kind,
+            self.message
+        )
+    }
+}
+
+// 一次检查收集的全部问题
+#[derive(Getters, Clone, Debug, Default, Serialize)]
+pub struct CheckReport {
+    issues: Vec<CheckIssue>,
+    errors: usize,
+    warnings: usize,
+}
+
+impl CheckReport {
+    fn push<S: Into<String>>(&mut self, level: CheckLevel, kind: CheckKind, path: &Path, msg: S) {
+        match level {
+            CheckLevel::Error => self.errors += 1,
+            CheckLevel::Warning => self.warnings += 1,
+        }
+        self.issues.push(CheckIssue {
+            level,
+            kind,
+            path: path.to_path_buf(),
+            message: msg.into(),
+        });
+    }
+    fn error<S: Into<String>>(&mut self, kind: CheckKind, path: &Path, msg: S) {
+        self.push(CheckLevel::Error, kind, path, msg)
+    }
+    fn warning<S: Into<String>>(&mut self, kind: CheckKind, path: &Path, msg: S) {
+        self.push(CheckLevel::Warning, kind, path, msg)
+    }
+    pub fn has_error(&self) -> bool {
+        self.errors > 0
+    }
+}
+
+fn err_text(e: &orion_error::StructError<SpecReason>) -> String {
+    match e.get_detail() {
+        Some(detail) => format!("{}: {}", e.get_reason(), detail),
+        None => e.get_reason().to_string(),
+    }
+}
+
+// 模块工程检查; remote 为 true 时探测 artifact 地址是否可达
+#[derive(Clone, Debug, Default)]
+pub struct ModChecker {
+    remote: bool,
+    report: CheckReport,
+}
+
+impl ModChecker {
+    pub fn new(remote: bool) -> Self {
+        Self {
+            remote,
+            report: CheckReport::default(),
+        }
+    }
+
+    pub async fn check(mut self, prj_root: &Path) -> SpecResult<CheckReport> {
+        let version = prj_root.join(VERSION_TXT);
+        if version.exists() {
+            let text = std::fs::read_to_string(&version).owe_res()?;
+            if let Err(e) = parse_version(VERSION_TXT, &text) {
+                self.report
+                    .error(CheckKind::Invalid, &version, err_text(&e));
+            }
+        } else {
+            self.report
+                .warning(CheckKind::Missing, &version, "version not set");
+        }
+        let src = prj_root.join(MOD_DIR);
+        if !src.is_dir() {
+            self.report
+                .error(CheckKind::Missing, &src, "module dir not exists");
+            return Ok(self.report);
+        }
+        let vars = src.join(VARS_YML);
+        if vars.exists() {
+            self.check_vars(&vars);
+        }
+        self.check_conf::<ModRequires>(&src.join(MOD_REQUIRES_YML), false);
+        for target_root in get_sub_dirs(&src)? {
+            self.check_target(&target_root).await?;
+        }
+        Ok(self.report)
+    }
+
+    pub async fn check_target(&mut self, target_root: &Path) -> SpecResult<()> {
+        let name = target_root
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if let Err(e) = ModelSTD::from_str(&name) {
+            self.report.error(
+                CheckKind::Model,
+                target_root,
+                format!("unknown model dir: {}", e),
+            );
+        }
+        let Some(target_root) = self.merge_extends(target_root) else {
+            return Ok(());
+        };
+        let target_root = target_root.as_path();
+        let paths = ModTargetPaths::from(&target_root.to_path_buf());
+        let artifact = self.check_conf::<ArtifactPackage>(paths.artifact_path(), true);
+        self.check_conf::<DependencySet>(paths.depends_path(), true);
+        self.check_conf::<LogsSpec>(paths.logs_path(), true);
+        self.check_conf::<CaculateResSpec>(paths.res_path(), true);
+        if paths.vars_path().exists() {
+            self.check_vars(paths.vars_path());
+        } else {
+            self.report
+                .error(CheckKind::Missing, paths.vars_path(), "file not exists");
+        }
+        let setting = self.check_conf::<Setting>(paths.setting_path(), false);
+        self.check_workflows(target_root);
+        if let Err(e) = GxlProject::load_from(target_root) {
+            self.report
+                .error(CheckKind::Workflow, target_root, err_text(&e));
+        }
+        self.check_setting(target_root, setting.as_ref())?;
+        self.check_templates(target_root, setting.as_ref())?;
+        if self.remote
+            && let Some(artifact) = artifact
+        {
+            self.check_artifact(paths.artifact_path(), &artifact).await;
+        }
+        Ok(())
+    }
+
+    // 有 extends 时检查已有的合并结果; 不联网, 也不写入源目录
+    // 未合并或合并已过期时报告问题, 需先 update
+    fn merge_extends(&mut self, target_root: &Path) -> Option<PathBuf> {
+        let extends_path = target_root.join(EXTENDS_YML);
+        if !extends_path.exists() {
+            return Some(target_root.to_path_buf());
+        }
+        if let Err(e) = ModExtends::from_conf(&extends_path) {
+            self.report
+                .error(CheckKind::Extends, &extends_path, err_text(&e));
+            return None;
+        }
+        match ModExtends::spec_root(target_root) {
+            Ok(root) => Some(root),
+            Err(e) => {
+                self.report
+                    .error(CheckKind::Extends, &extends_path, err_text(&e));
+                None
+            }
+        }
+    }
+
+    fn check_conf<T>(&mut self, path: &Path, required: bool) -> Option<T>
+    where
+        T: serde::de::DeserializeOwned + serde::Serialize,
+    {
+        if !path.exists() {
+            if required {
+                self.report
+                    .error(CheckKind::Missing, path, "file not exists");
+            }
+            return None;
+        }
+        match T::from_conf(path) {
+            Ok(obj) => Some(obj),
+            Err(e) => {
+                self.report.error(CheckKind::Invalid, path, err_text(&e));
+                None
+            }
+        }
+    }
+
+    fn check_vars(&mut self, path: &Path) {
+        if let Err(e) = VarCollection::eval_from_file(&ValueDict::default(), path) {
+            self.report.error(CheckKind::Invalid, path, err_text(&e));
+        }
+    }
+
+    // 非 .gxl 文件无法加载; 未知文件名按 Other 处理, 不会被标准流程调用
+    fn check_workflows(&mut self, target_root: &Path) {
+        let dir = target_root.join(WORKFLOWS_DIR);
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            self.report
+                .error(CheckKind::Missing, &dir, "workflows dir not exists");
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            match Workflow::load_from(&path) {
+                Ok(Workflow::Gxl(act)) if *act.task() == OperationType::Other => {
+                    self.report.warning(
+                        CheckKind::Workflow,
+                        &path,
+                        "not a known workflow (setup/update/port/backup/uninstall)",
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => self.report.error(CheckKind::Workflow, &path, err_text(&e)),
+            }
+        }
+    }
+
+    // include/exclude 至少要匹配到 target 下的一个文件
+    fn check_setting(&mut self, target_root: &Path, setting: Option<&Setting>) -> SpecResult<()> {
+        let Some(targets) = setting
+            .and_then(|x| x.localize().as_ref())
+            .and_then(|x| x.templatize_path().as_ref())
+        else {
+            return Ok(());
+        };
+        let files = target_files(target_root)?;
+        let setting_path = target_root.join(crate::const_vars::SETTING_YML);
+        for (label, globs) in [
+            ("include", targets.includes()),
+            ("exclude", targets.excludes()),
+        ] {
+            for glob in globs {
+                let full = target_root.join(glob);
+                let pattern = match Pattern::new(&full.to_string_lossy()) {
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        self.report.error(
+                            CheckKind::Setting,
+                            &setting_path,
+                            format!("{} {}: {}", label, glob, e),
+                        );
+                        continue;
+                    }
+                };
+                let hit = files
+                    .iter()
+                    .any(|x| x.starts_with(&full) || pattern.matches_path(x));
+                if !hit {
+                    self.report.warning(
+                        CheckKind::Setting,
+                        &setting_path,
+                        format!("{} {} matches no file", label, glob),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_templates(&mut self, target_root: &Path, setting: Option<&Setting>) -> SpecResult<()> {
+        let tpl_path = setting
+            .map(|x| x.templatize_path(target_root))
+            .unwrap_or_default();
+        let localizer = LocalizeTemplate::from_setting(setting);
+        for tpl_file in target_files(&target_root.join(SPEC_DIR))? {
+            if !tpl_path.is_include(&tpl_file) || tpl_path.is_exclude(&tpl_file) {
+                continue;
+            }
+            // 非文本文件不做模板渲染
+            if std::fs::read_to_string(&tpl_file).is_err() {
+                continue;
+            }
+            if let Err(e) = localizer.template_refs(&tpl_file) {
+                self.report
+                    .error(CheckKind::Template, &tpl_file, err_text(&e));
+            }
+        }
+        Ok(())
+    }
+
+    async fn check_artifact(&mut self, path: &Path, artifact: &ArtifactPackage) {
+        for item in artifact.iter() {
+            if let Err(msg) = probe_addr(item.addr()).await {
+                self.report.warning(
+                    CheckKind::Artifact,
+                    path,
+                    format!("{} unreachable: {}", item.name(), msg),
+                );
+            }
+        }
+    }
+}
+
+fn target_files(root: &Path) -> SpecResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !root.exists() {
+        return Ok(files);
+    }
+    for entry in walkdir::WalkDir::new(root).sort_by_file_name() {
+        let entry = entry.owe_res()?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+async fn probe_addr(addr: &AddrType) -> Result<(), String> {
+    match addr {
+        AddrType::Local(local) => {
+            if Path::new(local.path()).exists() {
+                Ok(())
+            } else {
+                Err("path not exists".into())
+            }
+        }
+        AddrType::Git(git) => git.remote_tags().map(|_| ()).map_err(|e| err_text(&e)),
+        AddrType::Http(http) => {
+            let mut request = reqwest::Client::new().head(http.url());
+            if let (Some(u), Some(p)) = (http.username(), http.password()) {
+                request = request.basic_auth(u, Some(p));
+            }
+            request
+                .send()
+                .await
+                .and_then(|x| x.error_for_status())
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use httpmock::{Method::HEAD, MockServer};
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        addr::{HttpAddr, LocalAddr},
+        artifact::Artifact,
+        const_vars::{ARTIFACT_YML, EXTENDS_DIR, SETTING_YML},
+        module::spec::ModuleSpec,
+    };
+
+    fn kinds(report: &CheckReport, level: CheckLevel) -> Vec<String> {
+        report
+            .issues()
+            .iter()
+            .filter(|x| *x.level() == level)
+            .map(|x| format!("{}:{}", x.kind(), path_name(x.path())))
+            .collect()
+    }
+    fn path_name(path: &Path) -> String {
+        path.file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_mod_check() {
+        let temp = tempfile::tempdir().assert();
+        ModuleSpec::make_new("chk")
+            .assert()
+            .save_to(temp.path(), None)
+            .assert();
+        let dir = temp.path().join("chk");
+        let src = dir.join(MOD_DIR);
+        let ok = ModChecker::new(false).check(&dir).await.assert();
+        assert!(!ok.has_error(), "{:?}", ok.issues());
+
+        let target = src.join(ModelSTD::arm_mac14_host().to_string());
+        std::fs::rename(&target, src.join("x86-nope-host")).assert();
+        let target = src.join("x86-nope-host");
+        let paths = ModTargetPaths::from(&target);
+        std::fs::remove_file(paths.logs_path()).assert();
+        write(paths.res_path(), "cpu: [").assert();
+        write(target.join(WORKFLOWS_DIR).join("deploy.sh"), "echo").assert();
+        write(target.join(WORKFLOWS_DIR).join("extra.gxl"), "").assert();
+        write(target.join(SPEC_DIR).join("app.conf"), "port={{#if PORT}}").assert();
+        write(
+            target.join(SETTING_YML),
+            "localize:\n  templatize_path:\n    excludes:\n      - spec/none/*.md\n",
+        )
+        .assert();
+        let report = ModChecker::new(false).check(&dir).await.assert();
+        let errors = kinds(&report, CheckLevel::Error);
+        assert!(errors.contains(&"model:x86-nope-host".to_string()));
+        assert!(errors.contains(&"missing:logs.yml".to_string()));
+        assert!(errors.contains(&"invalid:res.yml".to_string()));
+        assert!(errors.contains(&"workflow:deploy.sh".to_string()));
+        assert!(errors.contains(&"template:app.conf".to_string()));
+        assert_eq!(report.errors(), &errors.len());
+        let warnings = kinds(&report, CheckLevel::Warning);
+        assert!(warnings.contains(&"workflow:extra.gxl".to_string()));
+        assert!(warnings.contains(&"setting:setting.yml".to_string()));
+        assert!(warnings.contains(&"missing:version.txt".to_string()));
+        let json = serde_json::to_value(&report).assert();
+        assert_eq!(json["errors"], errors.len());
+    }
+
+    #[tokio::test]
+    async fn test_check_extends_unmerged() {
+        let temp = tempfile::tempdir().assert();
+        ModuleSpec::make_new("chk")
+            .assert()
+            .save_to(temp.path(), None)
+            .assert();
+        let dir = temp.path().join("chk");
+        let target = dir
+            .join(MOD_DIR)
+            .join(ModelSTD::arm_mac14_host().to_string());
+        ModExtends::new(LocalAddr::from("../../../base"))
+            .save_conf(&target.join(EXTENDS_YML))
+            .assert();
+        let report = ModChecker::new(false).check(&dir).await.assert();
+        let errors = kinds(&report, CheckLevel::Error);
+        assert!(errors.contains(&"extends:extends.yml".to_string()));
+        // 检查不拉取基础层, 也不写入源目录
+        assert!(!target.join(EXTENDS_DIR).exists());
+    }
+
+    #[tokio::test]
+    async fn test_check_artifact() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(HEAD).path("/ok.tar.gz");
+            then.status(200);
+        });
+        let dir = tempfile::tempdir().assert();
+        let artifact = ArtifactPackage::from(vec![
+            Artifact::new("ok", HttpAddr::from(server.url("/ok.tar.gz")), "ok.tar.gz"),
+            Artifact::new(
+                "miss",
+                HttpAddr::from(server.url("/miss.tar.gz")),
+                "miss.tar.gz",
+            ),
+        ]);
+        let path = dir.path().join(ARTIFACT_YML);
+        let mut checker = ModChecker::new(true);
+        checker.check_artifact(&path, &artifact).await;
+        assert_eq!(checker.report.warnings(), &1);
+        assert!(checker.report.issues()[0].message().starts_with("miss"));
+    }
+}
@@ -62,8 +62,10 @@ impl DependencySet {
         //options.
         for dep in self.deps().iter() {
             if dep.is_enable() {
-                dep.update(&self.dep_root().path(options.values()), options)
-                    .await?;
+                let dep_root = options
+                    .runtime()
+                    .resolve(self.dep_root().path(&options.env_dict()));
+                dep.update(&dep_root, options).await?;
             }
         }
         Ok(())
@@ -109,7 +111,7 @@ impl AsyncUpdateable for Dependency {
 impl Dependency {
     pub async fn update(&self, root: &Path, options: &UpdateOptions) -> SpecResult<PathBuf> {
         //let item_path = path.join(self.local());
-        let path = root.join(self.local().path(options.values()));
+        let path = root.join(self.local().path(&options.env_dict()));
         if let Some(rename) = self.rename() {
             self.update_rename(&path, rename, options).await
         } else {
@@ -0,0 +1,474 @@
+use crate::predule::*;
+
+use std::{collections::HashMap, str::FromStr};
+
+use fs_extra::dir::CopyOptions;
+use home::home_dir;
+use log::warn;
+use orion_error::UvsResFrom;
+use serde_yaml::{Mapping, Value};
+
+use super::{
+    ModelSTD,
+    proj::ModProject,
+    registry::addr_cache_key,
+    scaffold::ScaffoldCatalog,
+    setting::{LocalizeConf, Setting, TemplateTargets},
+};
+use crate::{
+    addr::{AddrType, path_file_name},
+    const_vars::{LOCAL_DIR, MOD_DIR, SPEC_DIR},
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    types::AsyncUpdateable,
+    vars::{ValueType, VarCollection, VarDefinition},
+};
+
+pub const HELM_CHART_YAML: &str = "Chart.yaml";
+pub const HELM_VALUES_YAML: &str = "values.yaml";
+pub const HELM_VALUES_SCHEMA: &str = "values.schema.json";
+
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct ChartMeta {
+    name: String,
+    version: String,
+    #[serde(
+        default,
+        rename = "appVersion",
+        skip_serializing_if = "Option::is_none"
+    )]
+    app_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+}
+
+// values.schema.json 中的类型与说明
+#[derive(Clone, Debug, Default)]
+struct SchemaField {
+    kind: Option<String>,
+    desc: Option<String>,
+}
+
+fn schema_fields(schema: &serde_json::Value, prefix: &str, out: &mut HashMap<String, SchemaField>) {
+    let Some(props) = schema.get("properties").and_then(|x| x.as_object()) else {
+        return;
+    };
+    for (key, prop) in props {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        let text = |name: &str| prop.get(name).and_then(|x| x.as_str()).map(String::from);
+        out.insert(
+            path.clone(),
+            SchemaField {
+                kind: text("type"),
+                desc: text("description").or_else(|| text("title")),
+            },
+        );
+        schema_fields(prop, &path, out);
+    }
+}
+
+// bitnami 风格的 `## @param <path> <desc>` 注释
+fn param_comments(values: &str) -> HashMap<String, String> {
+    values
+        .lines()
+        .filter_map(|x| x.trim().strip_prefix("## @param "))
+        .filter_map(|x| x.split_once(' '))
+        .map(|(path, desc)| (path.to_string(), desc.trim().to_string()))
+        .collect()
+}
+
+// image.pullPolicy -> IMAGE_PULL_POLICY
+pub fn helm_var_name(path: &str) -> Option<String> {
+    let mut name = String::new();
+    for seg in path.split('.') {
+        if seg.is_empty() || !seg.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+        if !name.is_empty() {
+            name.push('_');
+        }
+        let mut prev_lower = false;
+        for c in seg.chars() {
+            if c.is_ascii_uppercase() && prev_lower {
+                name.push('_');
+            }
+            prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+            name.push(c.to_ascii_uppercase());
+        }
+    }
+    Some(name)
+}
+
+fn scalar_value(value: &Value, kind: Option<&str>) -> Option<ValueType> {
+    match value {
+        Value::String(v) => Some(ValueType::from(v.as_str())),
+        Value::Bool(v) => Some(ValueType::from(*v)),
+        // 负整数无法表示为 Int, 保留原值, 避免渲染成 -1.0
+        Value::Number(v) => match v.as_u64() {
+            Some(n) => Some(ValueType::from(n)),
+            None if v.is_f64() => v.as_f64().map(ValueType::from),
+            None => None,
+        },
+        // 空值按 schema 类型给默认值
+        Value::Null => match kind {
+            Some("string") => Some(ValueType::from("")),
+            Some("boolean") => Some(ValueType::from(false)),
+            Some("integer") => Some(ValueType::from(0u64)),
+            Some("number") => Some(ValueType::from(0f64)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// import_helm 固定生成的变量
+const HELM_RESERVED_VARS: [&str; 2] = ["HELM_CHART", "NAMESPACE"];
+
+// values.yaml 转为 vars 与模板: 标量叶子替换为 {{{yaml VAR}}}, 列表与对象保留原值
+#[derive(Getters, Clone, Debug)]
+pub struct HelmValues {
+    vars: VarCollection,
+    template: String,
+}
+
+struct ValuesMapper {
+    schema: HashMap<String, SchemaField>,
+    params: HashMap<String, String>,
+    vars: Vec<VarDefinition>,
+    // 变量名 -> values 路径
+    names: HashMap<String, String>,
+}
+
+fn var_holder(name: &str) -> String {
+    format!("{{{{{{yaml {}}}}}}}", name)
+}
+
+impl ValuesMapper {
+    // 变量名已被占用时改名: 保留名加 HELM_ 前缀, 其他追加序号
+    fn unique_name(&self, name: String, path: &str) -> String {
+        if !self.names.contains_key(&name) {
+            return name;
+        }
+        let base = if HELM_RESERVED_VARS.contains(&name.as_str()) {
+            format!("HELM_{}", name)
+        } else {
+            name.clone()
+        };
+        let mut renamed = base.clone();
+        let mut seq = 2;
+        while self.names.contains_key(&renamed) {
+            renamed = format!("{}_{}", base, seq);
+            seq += 1;
+        }
+        warn!(
+            target: "mod/helm",
+            "helm value {} maps to {} used by {}, rename to {}",
+            path, name, self.names[&name], renamed
+        );
+        renamed
+    }
+
+    fn map(&mut self, path: &str, value: &Value) -> Value {
+        if let Value::Mapping(map) = value {
+            let mut out = Mapping::new();
+            for (key, sub) in map {
+                let sub_path = match key.as_str() {
+                    Some(k) if path.is_empty() => k.to_string(),
+                    Some(k) => format!("{}.{}", path, k),
+                    None => {
+                        out.insert(key.clone(), sub.clone());
+                        continue;
+                    }
+                };
+                out.insert(key.clone(), self.map(&sub_path, sub));
+            }
+            return Value::Mapping(out);
+        }
+        let field = self.schema.get(path).cloned().unwrap_or_default();
+        let (Some(name), Some(var_value)) = (
+            helm_var_name(path),
+            scalar_value(value, field.kind.as_deref()),
+        ) else {
+            return value.clone();
+        };
+        let name = self.unique_name(name, path);
+        self.names.insert(name.clone(), path.to_string());
+        let mut var = VarDefinition::from((name.as_str(), var_value));
+        if let Some(desc) = field.desc.or_else(|| self.params.get(path).cloned()) {
+            var = var.with_desc(desc);
+        }
+        self.vars.push(var);
+        Value::String(var_holder(&name))
+    }
+}
+
+impl HelmValues {
+    pub fn load(chart_dir: &Path) -> SpecResult<Self> {
+        let mut ctx = WithContext::want("map helm values");
+        let values_path = chart_dir.join(HELM_VALUES_YAML);
+        ctx.with_path("values", &values_path);
+        let text = std::fs::read_to_string(&values_path).owe_res().with(&ctx)?;
+        let values: Value = serde_yaml::from_str(&text).owe_data().with(&ctx)?;
+        let mut schema = HashMap::new();
+        let schema_path = chart_dir.join(HELM_VALUES_SCHEMA);
+        if schema_path.exists() {
+            let data = std::fs::read_to_string(&schema_path)
+                .owe_res()
+                .with(&schema_path)?;
+            let json: serde_json::Value =
+                serde_json::from_str(&data).owe_data().with(&schema_path)?;
+            schema_fields(&json, "", &mut schema);
+        }
+        let mut mapper = ValuesMapper {
+            schema,
+            params: param_comments(&text),
+            vars: Vec::new(),
+            names: HELM_RESERVED_VARS
+                .iter()
+                .map(|x| (x.to_string(), String::from("<reserved>")))
+                .collect(),
+        };
+        let mapped = mapper.map("", &values);
+        // 占位符由 yaml helper 输出转义后的值, 去掉 serde_yaml 加的引号
+        let mut template = serde_yaml::to_string(&mapped).owe_data().with(&ctx)?;
+        for var in &mapper.vars {
+            let holder = var_holder(var.name());
+            template = template.replace(&format!("'{}'", holder), &holder);
+        }
+        Ok(Self {
+            vars: VarCollection::define(mapper.vars),
+            template,
+        })
+    }
+}
+
+impl ChartMeta {
+    pub fn load(chart_dir: &Path) -> SpecResult<Self> {
+        let path = chart_dir.join(HELM_CHART_YAML);
+        if !path.exists() {
+            return SpecReason::from(ElementReason::Miss(format!(
+                "helm chart: {}",
+                path.display()
+            )))
+            .err_result();
+        }
+        let text = std::fs::read_to_string(&path).owe_res().with(&path)?;
+        serde_yaml::from_str(&text).owe_data().with(&path)
+    }
+}
+
+// 本地目录直接使用, 其他地址拉取到 ~/.cache/galaxy/helm/<地址的 sha256 前缀>
+pub async fn fetch_chart(chart: &str, options: &UpdateOptions) -> SpecResult<PathBuf> {
+    let cache = home_dir()
+        .ok_or(StructError::from_res("unget home".into()))?
+        .join(".cache/galaxy/helm");
+    fetch_chart_in(&cache, chart, options).await
+}
+
+pub async fn fetch_chart_in(
+    cache: &Path,
+    chart: &str,
+    options: &UpdateOptions,
+) -> SpecResult<PathBuf> {
+    let local = PathBuf::from(chart);
+    if local.join(HELM_CHART_YAML).exists() {
+        return Ok(local);
+    }
+    let addr = AddrType::from_str(chart).map_err(StructError::from_conf)?;
+    let dst = cache.join(addr_cache_key(&addr)?);
+    std::fs::create_dir_all(&dst).owe_res().with(&dst)?;
+    addr.update_local(&dst, options).await
+}
+
+impl ModProject {
+    // chart 原样放在 spec/<chart> 下且不做模板处理, spec/values.yaml 由 vars 渲染后覆盖 chart 默认值
+    pub fn import_helm(
+        prj_path: &Path,
+        name: Option<&str>,
+        chart_dir: &Path,
+        targets: &[ModelSTD],
+    ) -> SpecResult<Self> {
+        let meta = ChartMeta::load(chart_dir)?;
+        let name = name.unwrap_or(meta.name.as_str());
+        let chart_name = path_file_name(chart_dir)?;
+        let values = HelmValues::load(chart_dir)?;
+        let mut vars = vec![
+            VarDefinition::from((
+                "HELM_CHART",
+                format!("./{}/{}", LOCAL_DIR, chart_name).as_str(),
+            ))
+            .with_desc(format!("chart {} {}", meta.name, meta.version)),
+            VarDefinition::from(("NAMESPACE", "default")),
+        ];
+        vars.extend(values.vars.vars().iter().cloned());
+        let setting = Setting::new(Some(LocalizeConf::new(
+            Some(TemplateTargets::new(
+                vec![],
+                vec![format!("{}/{}", SPEC_DIR, chart_name)],
+            )),
+            None,
+        )));
+        let targets = if targets.is_empty() {
+            vec![ModelSTD::x86_ubt22_k8s()]
+        } else {
+            targets.to_vec()
+        };
+        let scaffold = ScaffoldCatalog::builtin()
+            .find("helm")?
+            .clone()
+            .with_vars(VarCollection::define(vars))
+            .with_files(vec![(
+                HELM_VALUES_YAML.to_string(),
+                values.template.clone(),
+            )])
+            .with_setting(setting);
+        let prj = ModProject::make_scaffold(prj_path, name, &scaffold, &targets)?;
+        for target in &targets {
+            let spec_path = prj_path
+                .join(MOD_DIR)
+                .join(target.to_string())
+                .join(SPEC_DIR);
+            fs_extra::dir::copy(chart_dir, &spec_path, &CopyOptions::new().overwrite(true))
+                .owe_res()
+                .with(chart_dir)?;
+        }
+        info!(target: "mod/helm", "import chart {} to {}", meta.name, prj_path.display());
+        Ok(prj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        const_vars::SETTING_YML,
+        tpl::TplHandleBars,
+        types::{Localizable, LocalizeOptions},
+    };
+
+    #[test]
+    fn test_helm_var_name() {
+        assert_eq!(helm_var_name("replicaCount").unwrap(), "REPLICA_COUNT");
+        assert_eq!(
+            helm_var_name("image.pullPolicy").unwrap(),
+            "IMAGE_PULL_POLICY"
+        );
+        assert_eq!(helm_var_name("tls.certFile2").unwrap(), "TLS_CERT_FILE2");
+        assert!(helm_var_name("podLabels.app-name").is_none());
+    }
+
+    #[test]
+    fn test_helm_values() {
+        let values = HelmValues::load(&PathBuf::from("./test/helm/nginx")).assert();
+        let dict = values.vars().value_dict();
+        assert_eq!(dict.get("REPLICA_COUNT"), Some(&ValueType::from(1u64)));
+        assert_eq!(
+            dict.get("IMAGE_REPOSITORY"),
+            Some(&ValueType::from("bitnami/nginx"))
+        );
+        let replica = values
+            .vars()
+            .vars()
+            .iter()
+            .find(|x| x.name() == "REPLICA_COUNT")
+            .unwrap();
+        assert_eq!(
+            replica.desc().map(|x| x.as_str()),
+            Some("Number of replicas to deploy")
+        );
+        assert!(
+            values
+                .template()
+                .contains("replicaCount: {{{yaml REPLICA_COUNT}}}")
+        );
+        assert!(
+            values
+                .template()
+                .contains("repository: {{{yaml IMAGE_REPOSITORY}}}")
+        );
+    }
+
+    #[test]
+    fn test_helm_values_escape() {
+        let temp = tempfile::tempdir().assert();
+        std::fs::write(
+            temp.path().join(HELM_VALUES_YAML),
+            "motd: hello\nport: 80\n",
+        )
+        .assert();
+        let values = HelmValues::load(temp.path()).assert();
+        // 引号, 冒号, 换行等按 yaml 转义, 渲染结果仍是原值
+        let motd = "it's: \"ok\"\n# not comment";
+        let data = serde_json::json!({ "MOTD": motd, "PORT": 8080 });
+        let rendered = TplHandleBars::init()
+            .render_data(values.template(), &data)
+            .assert();
+        let parsed: Value = serde_yaml::from_str(&rendered).assert();
+        assert_eq!(parsed["motd"], Value::from(motd));
+        assert_eq!(parsed["port"], Value::from(8080));
+    }
+
+    #[test]
+    fn test_helm_values_conflict() {
+        let temp = tempfile::tempdir().assert();
+        let values = temp.path().join(HELM_VALUES_YAML);
+        // 转换后同名时追加序号, 与保留变量同名时加 HELM_ 前缀
+        let names = |text: &str| {
+            std::fs::write(&values, text).assert();
+            let mapped = HelmValues::load(temp.path()).assert();
+            let names: Vec<String> = mapped
+                .vars()
+                .vars()
+                .iter()
+                .map(|x| x.name().to_string())
+                .collect();
+            (names, mapped.template().clone())
+        };
+        let (found, template) =
+            names("imagePullPolicy: Always\nimage:\n  pullPolicy: IfNotPresent\n");
+        assert_eq!(found, vec!["IMAGE_PULL_POLICY", "IMAGE_PULL_POLICY_2"]);
+        assert!(template.contains("pullPolicy: {{{yaml IMAGE_PULL_POLICY_2}}}"));
+        let (found, _) = names("namespace: demo\n");
+        assert_eq!(found, vec!["HELM_NAMESPACE"]);
+        // 负整数保留原值
+        let (found, template) = names("offset: -1\nratio: 0.5\n");
+        assert_eq!(found, vec!["RATIO"]);
+        assert!(template.contains("offset: -1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_import_helm() {
+        let temp = tempfile::tempdir().assert();
+        let prj_path = temp.path().join("nginx");
+        std::fs::create_dir_all(&prj_path).assert();
+        ModProject::import_helm(&prj_path, None, &PathBuf::from("./test/helm/nginx"), &[]).assert();
+        let prj = ModProject::load(&prj_path).assert();
+        assert_eq!(prj.mod_spec().name(), "nginx");
+        let target_root = prj_path
+            .join(MOD_DIR)
+            .join(ModelSTD::x86_ubt22_k8s().to_string());
+        assert!(target_root.join(SETTING_YML).exists());
+        assert!(
+            target_root
+                .join("spec/nginx/templates/deployment.yaml")
+                .exists()
+        );
+
+        prj.mod_spec()
+            .localize(None, LocalizeOptions::for_test())
+            .await
+            .assert();
+        let local = target_root.join(LOCAL_DIR);
+        let values = std::fs::read_to_string(local.join(HELM_VALUES_YAML)).assert();
+        assert!(values.contains("replicaCount: 1"));
+        // chart 模板保持 helm 的 {{ }} 原样
+        let deployment =
+            std::fs::read_to_string(local.join("nginx/templates/deployment.yaml")).assert();
+        assert!(deployment.contains("{{ include"));
+    }
+}
@@ -95,6 +95,14 @@ impl ModIniter for ModWorkflows {
     }
 }
 
+// 模块工程 .gitignore 中的本地产物名
+pub fn mod_ignore_names() -> Vec<&'static str> {
+    MOD_GITIGNORE
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect()
+}
 pub fn mod_init_gitignore(path: &Path) -> SpecResult<()> {
     let ignore_path = path.join(".gitignore");
     if !ignore_path.exists() {
@@ -0,0 +1,291 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use derive_getters::Getters;
+use derive_more::Display as DisplayEx;
+use orion_error::{ErrorOwe, ErrorWith, ToStructError};
+use serde_derive::Serialize;
+
+use crate::{
+    const_vars::{SPEC_DIR, USER_VALUE_FILE, VALUE_DIR},
+    error::{ElementReason, SpecReason, SpecResult},
+    tpl::RefKind,
+    types::Yamlable,
+    vars::{
+        Expr, ImportMode, ValueDict, ValueType, VarCollection, env_var_refs, import_value_file,
+        var_name_lines,
+    },
+};
+
+use super::{
+    model::{ModModelSpec, ModTargetPaths},
+    overlay::ModExtends,
+};
+
+#[derive(Clone, Debug, PartialEq, Serialize, DisplayEx)]
+#[serde(rename_all = "lowercase")]
+pub enum LintKind {
+    #[display("undefined")]
+    Undefined,
+    #[display("unused")]
+    Unused,
+    #[display("shadowed")]
+    Shadowed,
+}
+
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct LintIssue {
+    kind: LintKind,
+    path: PathBuf,
+    line: Option<usize>,
+    name: String,
+    message: String,
+}
+
+impl LintIssue {
+    fn new<S: Into<String>>(
+        kind: LintKind,
+        path: &Path,
+        line: Option<usize>,
+        name: &str,
+        message: S,
+    ) -> Self {
+        Self {
+            kind,
+            path: path.to_path_buf(),
+            line,
+            name: name.to_string(),
+            message: message.into(),
+        }
+    }
+    // 未定义的引用会导致渲染失败, 其余为警告
+    pub fn is_error(&self) -> bool {
+        self.kind == LintKind::Undefined
+    }
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}: {}: {}", self.kind, self.name, self.message)
+    }
+}
+
+impl ModModelSpec {
+    // 检查 spec/ 下模板引用的变量与 vars.yml、value 文件是否一致
+    pub fn lint(&self, global: &ValueDict) -> SpecResult<Vec<LintIssue>> {
+        let local = self
+            .local()
+            .clone()
+            .ok_or(SpecReason::from(ElementReason::Miss("local-path".into())).to_err())?;
+        let spec_root = ModExtends::spec_root(&local)?;
+        let vars_path = ModTargetPaths::from(&spec_root).vars_path().clone();
+        let user_file = local.join(VALUE_DIR).join(USER_VALUE_FILE);
+        let user = if user_file.exists() {
+            import_value_file(&user_file, ImportMode::Structured)?
+        } else {
+            ValueDict::new()
+        };
+
+        // 加载时 vars 已求值, 需从原文件中取 ${VAR} 引用
+        let vars = if vars_path.exists() {
+            VarCollection::from_yml(&vars_path)?
+        } else {
+            self.vars().clone()
+        };
+
+        let mut declared: HashSet<String> = user.keys().chain(global.keys()).cloned().collect();
+        declared.extend(vars.vars().iter().map(|x| x.name().to_string()));
+        // mod/vars.yml 中适用于当前 target 的共享变量
+        declared.extend(
+            self.effective_vars()?
+                .vars()
+                .iter()
+                .map(|x| x.name().to_string()),
+        );
+        // ${VAR} 引用也算作使用
+        let mut used: HashSet<String> = vars
+            .vars()
+            .iter()
+            .map(|x| x.var_value())
+            .chain(user.values().cloned())
+            .chain(global.values().cloned())
+            .filter_map(|x| match x {
+                ValueType::String(s) => Some(env_var_refs(&s)),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        used.extend(
+            vars.vars()
+                .iter()
+                .filter_map(|x| x.expr())
+                .filter_map(|x| Expr::from_str(x).ok())
+                .flat_map(|x| x.var_refs()),
+        );
+
+        let mut issues = Vec::new();
+        let tpl_path = self.templatize_path(&spec_root);
+        let localizer = self.localizer();
+        for tpl_file in template_files(&spec_root.join(SPEC_DIR))? {
+            if !tpl_path.is_include(&tpl_file) || tpl_path.is_exclude(&tpl_file) {
+                continue;
+            }
+            for tpl_ref in localizer.template_refs(&tpl_file)? {
+                let name = tpl_ref.name();
+                let line = Some(*tpl_ref.line());
+                match tpl_ref.kind() {
+                    RefKind::Var => {
+                        if !declared.contains(name) {
+                            issues.push(LintIssue::new(
+                                LintKind::Undefined,
+                                &tpl_file,
+                                line,
+                                name,
+                                "not defined in vars.yml or value files",
+                            ));
+                        }
+                        used.insert(name.clone());
+                    }
+                    RefKind::BlockParam if declared.contains(name) => {
+                        issues.push(LintIssue::new(
+                            LintKind::Shadowed,
+                            &tpl_file,
+                            line,
+                            name,
+                            "block param shadows variable",
+                        ));
+                    }
+                    RefKind::BlockParam => {}
+                }
+            }
+        }
+
+        let content = std::fs::read_to_string(&vars_path).unwrap_or_default();
+        let mut checked = HashSet::new();
+        for var in vars.vars() {
+            let name = var.name();
+            if !checked.insert(name.to_string()) {
+                continue;
+            }
+            let lines = var_name_lines(&content, name);
+            if !used.contains(name) {
+                issues.push(LintIssue::new(
+                    LintKind::Unused,
+                    &vars_path,
+                    lines.first().copied(),
+                    name,
+                    "declared but never referenced",
+                ));
+            }
+            for line in lines.iter().skip(1) {
+                issues.push(LintIssue::new(
+                    LintKind::Shadowed,
+                    &vars_path,
+                    Some(*line),
+                    name,
+                    format!("redeclared, first at line {}", lines[0]),
+                ));
+            }
+        }
+        Ok(issues)
+    }
+}
+
+fn template_files(tpl_root: &Path) -> SpecResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !tpl_root.exists() {
+        return Ok(files);
+    }
+    for entry in walkdir::WalkDir::new(tpl_root).sort_by_file_name() {
+        let entry = entry.owe_data().with(tpl_root)?;
+        if entry.file_type().is_file() {
+            files.push(entry.path().to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use orion_error::TestAssert;
+
+    use crate::{
+        const_vars::VARS_YML,
+        module::{CpuArch, ModelSTD, OsCPE, RunSPC},
+        resource::CaculateResSpec,
+        types::Persistable,
+        vars::VarDefinition,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_lint_target() {
+        let dir = tempfile::tempdir().assert();
+        let spec = ModModelSpec::init(
+            ModelSTD::new(CpuArch::X86, OsCPE::UBT22, RunSPC::Host),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            CaculateResSpec::new(1, 1),
+            VarCollection::define(vec![
+                VarDefinition::from(("HOST", "127.0.0.1")),
+                VarDefinition::from(("PORT", 80)),
+                VarDefinition::from(("URL", "${HOST}:80")),
+                VarDefinition::from(("UNUSED", "x")),
+                VarDefinition::from(("MEM", 1)),
+                VarDefinition::from(("MEM_LIMIT", 0)).with_expr("MEM * 0.8"),
+            ]),
+            None,
+        );
+        let local = dir.path().join(spec.model().to_string());
+        spec.save_to(dir.path(), None).assert();
+        let mut vars = std::fs::read_to_string(local.join(VARS_YML)).assert();
+        vars.push_str("- name: PORT\n  value: 81\n");
+        write(local.join(VARS_YML), vars).assert();
+        write(
+            local.join(SPEC_DIR).join("app.conf"),
+            "url={{URL}}\nport={{PORT}}{{MEM_LIMIT}}\n{{#each LIST as |HOST|}}{{HOST}}{{/each}}\nuser={{USR}}\n",
+        )
+        .assert();
+        let spec = ModModelSpec::load_from(&local).assert();
+        let mut global = ValueDict::new();
+        global.insert("LIST", ValueType::List(vec![]));
+
+        let issues = spec.lint(&global).assert();
+        let found: Vec<_> = issues
+            .iter()
+            .map(|x| (x.kind().clone(), x.name().as_str(), *x.line()))
+            .collect();
+        assert!(found.contains(&(LintKind::Undefined, "USR", Some(4))));
+        assert!(found.contains(&(LintKind::Shadowed, "HOST", Some(3))));
+        assert!(
+            found
+                .iter()
+                .any(|x| x.0 == LintKind::Unused && x.1 == "UNUSED")
+        );
+        assert!(
+            found
+                .iter()
+                .any(|x| x.0 == LintKind::Shadowed && x.1 == "PORT")
+        );
+        assert!(
+            !found
+                .iter()
+                .any(|x| x.1 == "HOST" && x.0 == LintKind::Unused)
+        );
+        assert_eq!(issues.iter().filter(|x| x.is_error()).count(), 1);
+        let undefined = issues.iter().find(|x| x.is_error()).unwrap();
+        assert!(undefined.to_string().contains("app.conf:4: undefined: USR"));
+    }
+}
@@ -8,20 +8,23 @@ use serde::Serialize;
 use crate::{
     error::SpecResult,
     module::setting::TemplatePath,
-    tpl::{CommentFmt, CustTmplLabel, LabelCoverter, TplHandleBars},
+    secret::SecretResolver,
+    tpl::{CommentFmt, CustTmplLabel, LabelCoverter, TplHandleBars, TplRef, template_refs},
 };
 
-use super::setting::TemplateConfig;
+use super::setting::{Setting, TemplateConfig};
 
 pub struct LocalizeTemplate<'a> {
     handlebars: TplHandleBars<'a>,
     cust_cover: CustTmplLabel,
+    secrets: SecretResolver,
 }
 impl Default for LocalizeTemplate<'_> {
     fn default() -> Self {
         Self {
             handlebars: TplHandleBars::init(),
             cust_cover: CustTmplLabel::None,
+            secrets: SecretResolver::default(),
         }
     }
 }
@@ -31,10 +34,35 @@ impl LocalizeTemplate<'_> {
         Self {
             handlebars: TplHandleBars::init(),
             cust_cover: CustTmplLabel::Setting(convert),
+            secrets: SecretResolver::default(),
         }
     }
+    pub fn from_setting(setting: Option<&Setting>) -> Self {
+        match setting.and_then(|x| x.template_config()) {
+            Some(cust) => Self::new(cust),
+            None => Self::default(),
+        }
+    }
+    // 使用 LocalizeOptions 中配置的 provider
+    pub fn with_secrets(mut self, secrets: SecretResolver) -> Self {
+        self.secrets = secrets;
+        self
+    }
 }
 impl LocalizeTemplate<'_> {
+    // 与渲染相同的标签转换后解析模板中的变量引用
+    pub fn template_refs(&self, tpl_path: &Path) -> SpecResult<Vec<TplRef>> {
+        let mut err_ctx = WithContext::want("parse tpl refs");
+        err_ctx.with_path("tpl", tpl_path);
+        let template = std::fs::read_to_string(tpl_path)
+            .owe_data()
+            .with(&err_ctx)?;
+        let template = self
+            .cust_cover
+            .convert(&CommentFmt::from(tpl_path.extension()), template)
+            .with(&err_ctx)?;
+        template_refs(&template).with(&err_ctx)
+    }
     pub fn render_path(
         &self,
         tpl: &PathBuf,
@@ -50,6 +78,8 @@ impl LocalizeTemplate<'_> {
         let data: serde_json::Value = serde_json::from_str(content.as_str())
             .owe_data()
             .with(&err_ctx)?;
+        // secret 只在渲染时解析, 不写入 _used.json/_used.yml
+        let data = self.secrets.resolve_json(data).with(&err_ctx)?;
         if tpl.is_dir() {
             self.render_dir_impl(tpl, dst, &data, setting)
                 .with(&err_ctx)
@@ -219,6 +249,30 @@ mod tests {
         assert_eq!(output, "Hello, World!");
     }
 
+    #[test]
+    fn test_render_with_secret() {
+        let tmp_dir = tempdir().unwrap();
+        let tpl_file = tmp_dir.path().join("db.conf");
+        std::fs::write(&tpl_file, "password={{DB_PASS}}").unwrap();
+        let pass_file = tmp_dir.path().join("db.pass");
+        std::fs::write(&pass_file, "s3cret\n").unwrap();
+
+        let data_file = tmp_dir.path().join("data.json");
+        let data = format!(
+            r#"{{"DB_PASS": "${{secret:file:{}}}"}}"#,
+            pass_file.display()
+        );
+        std::fs::write(&data_file, data.as_str()).unwrap();
+
+        let output = tmp_dir.path().join("output/db.conf");
+        LocalizeTemplate::default()
+            .render_path(&tpl_file, &output, &data_file, &TemplatePath::default())
+            .assert();
+        assert_eq!(std::fs::read_to_string(&output).unwrap(), "password=s3cret");
+        // 数据文件中仍然只有引用
+        assert_eq!(std::fs::read_to_string(&data_file).unwrap(), data);
+    }
+
     #[test]
     fn test_render_directory() {
         let tmp_dir = tempdir().unwrap().path().to_path_buf();
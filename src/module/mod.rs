@@ -1,38 +1,87 @@
+pub mod check;
 pub mod depend;
+pub mod helm;
 pub mod init;
+pub mod lint;
 pub mod localize;
 pub mod metrc;
 pub mod model;
+pub mod overlay;
+pub mod package;
+pub mod ports;
 pub mod proj;
 pub mod refs;
+pub mod registry;
+pub mod require;
+pub mod scaffold;
 pub mod setting;
 pub mod spec;
+pub mod target;
+pub mod when;
+use derive_getters::Getters;
 use derive_more::{Display, From};
 use serde::Serializer;
-use serde_derive::{Deserialize, Serialize};
 use std::{fmt::Display, str::FromStr};
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display, PartialEq, Eq, Hash)]
+use target::{TargetPart, TargetRegistry};
+
+// 序列化为名称字符串, 解析时经 TargetRegistry 处理别名
+macro_rules! target_part_serde {
+    ($name:ident) => {
+        impl serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&self.to_string())
+            }
+        }
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
 pub enum CpuArch {
     #[display("x86")]
     X86,
     #[display("arm")]
     Arm,
+    // 由 TargetRegistry 登记的其他架构
+    #[display("{_0}")]
+    Other(String),
+}
+
+impl CpuArch {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "x86" => Self::X86,
+            "arm" => Self::Arm,
+            _ => Self::Other(name.to_string()),
+        }
+    }
 }
 
 impl FromStr for CpuArch {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "x86" => Ok(Self::X86),
-            "arm" => Ok(Self::Arm),
-            _ => Err(s.to_string()),
-        }
+        TargetRegistry::global()
+            .canonical(TargetPart::Arch, s)
+            .map(Self::from_name)
+            .ok_or_else(|| s.to_string())
     }
 }
+target_part_serde!(CpuArch);
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
 pub enum OsCPE {
     #[display("mac14")]
     MAC14,
@@ -42,42 +91,83 @@ pub enum OsCPE {
     UBT22,
     #[display("cos7")]
     COS7,
+    #[display("{_0}")]
+    Other(String),
+}
+
+impl OsCPE {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "mac14" => Self::MAC14,
+            "win10" => Self::WIN10,
+            "ubt22" => Self::UBT22,
+            "cos7" => Self::COS7,
+            _ => Self::Other(name.to_string()),
+        }
+    }
+    // ubt22 -> (ubt, 22)
+    fn split_version(&self) -> (String, Option<u32>) {
+        let name = self.to_string();
+        let pos = name
+            .rfind(|c: char| !c.is_ascii_digit())
+            .map(|x| x + 1)
+            .unwrap_or(0);
+        let (family, version) = name.split_at(pos);
+        (family.to_string(), version.parse().ok())
+    }
+    pub fn family(&self) -> String {
+        self.split_version().0
+    }
+    pub fn version(&self) -> Option<u32> {
+        self.split_version().1
+    }
 }
 
 impl FromStr for OsCPE {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "mac14" => Ok(Self::MAC14),
-            "win10" => Ok(Self::WIN10),
-            "ubt22" => Ok(Self::UBT22),
-            "cos7" => Ok(Self::COS7),
-            _ => Err(s.to_string()),
-        }
+        TargetRegistry::global()
+            .canonical(TargetPart::Os, s)
+            .map(Self::from_name)
+            .ok_or_else(|| s.to_string())
     }
 }
+target_part_serde!(OsCPE);
 
-#[derive(Clone, Debug, Serialize, Deserialize, Display, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Display, PartialEq, Eq, Hash)]
 pub enum RunSPC {
     #[display("host")]
     Host,
     #[display("k8s")]
     K8S,
+    #[display("{_0}")]
+    Other(String),
+}
+
+impl RunSPC {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "host" => Self::Host,
+            "k8s" => Self::K8S,
+            _ => Self::Other(name.to_string()),
+        }
+    }
 }
+
 impl FromStr for RunSPC {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "host" => Ok(Self::Host),
-            "k8s" => Ok(Self::K8S),
-            _ => Err(s.to_string()),
-        }
+        TargetRegistry::global()
+            .canonical(TargetPart::Spc, s)
+            .map(Self::from_name)
+            .ok_or_else(|| s.to_string())
     }
 }
+target_part_serde!(RunSPC);
 
-#[derive(Clone, Debug, From, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, From, PartialEq, Eq, Hash, Getters)]
 pub struct ModelSTD {
     arch: CpuArch,
     os: OsCPE,
@@ -134,6 +224,10 @@ impl ModelSTD {
     pub fn new(arch: CpuArch, os: OsCPE, spc: RunSPC) -> Self {
         Self { arch, os, spc }
     }
+    // 按指定的 target 登记表解析; FromStr / serde 使用全局登记表
+    pub fn parse_with(registry: &TargetRegistry, value: &str) -> Result<Self, String> {
+        registry.parse_model(value)
+    }
 }
 
 impl Display for ModelSTD {
@@ -144,15 +238,6 @@ impl Display for ModelSTD {
 impl FromStr for ModelSTD {
     type Err = String;
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = value.split('-').collect();
-        if parts.len() != 3 {
-            return Err(value.into());
-        }
-
-        let arch = CpuArch::from_str(parts[0])?;
-        let os = OsCPE::from_str(parts[1])?;
-        let spc = RunSPC::from_str(parts[2])?;
-
-        Ok(ModelSTD { arch, os, spc })
+        Self::parse_with(TargetRegistry::global(), value)
     }
 }
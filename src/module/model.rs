@@ -1,7 +1,7 @@
 use crate::{
     const_vars::{
-        DEFAULT_VALUE_FILE, LOCAL_DIR, SAMPLE_VALUE_FILE, USED_JSON, USED_READABLE_FILE,
-        USER_VALUE_FILE, VALUE_DIR,
+        DEFAULT_VALUE_FILE, GENERATED_VALUE_FILE, LOCAL_DIR, SAMPLE_VALUE_FILE, USED_JSON,
+        USED_READABLE_FILE, USER_VALUE_FILE, VALUE_DIR,
     },
     predule::*,
     tools::ensure_path,
@@ -9,12 +9,16 @@ use crate::{
 use std::{fs::read_to_string, str::FromStr};
 
 use async_trait::async_trait;
+use log::warn;
 
 use super::{
     ModelSTD,
     depend::DependencySet,
     localize::LocalizeTemplate,
-    setting::{Setting, TemplateConfig},
+    overlay::ModExtends,
+    ports::ModPorts,
+    setting::{Setting, TemplatePath},
+    when::target_vars,
 };
 use crate::tools::make_clean_path;
 use crate::types::LocalizeOptions;
@@ -22,17 +26,22 @@ use crate::{
     addr::path_file_name,
     artifact::ArtifactPackage,
     const_vars::{
-        ARTIFACT_YML, CONF_SPEC_YML, DEPENDS_YML, LOGS_SPEC_YML, RES_SPEC_YML, SETTING_YML,
-        SPEC_DIR, VARS_YML,
+        ARTIFACT_YML, CONF_SPEC_YML, DEPENDS_YML, LOGS_SPEC_YML, PORTS_YML, RES_SPEC_YML,
+        SETTING_YML, SPEC_DIR, VARS_YML,
     },
-    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    error::{ElementReason, LocalizeReason, SpecReason, SpecResult, ToErr},
     resource::CaculateResSpec,
+    secret::{GeneratedSecret, conceal_generated},
     software::LogsSpec,
     tools::get_sub_dirs,
     types::{
         AsyncUpdateable, Configable, JsonAble, Localizable, Persistable, ValueConfable, ValuePath,
     },
-    vars::{OriginDict, ValueDict, VarCollection},
+    vars::{
+        MOD_CUST_SOURCE, MOD_DEFAULT_SOURCE, MOD_EXPR_SOURCE, MOD_SHARED_SOURCE, OriginDict,
+        OriginMap, ValueDict, ValueExplainer, ValueIssue, ValueType, VarCollection, VarExplain,
+        profile_source, profile_value_file, validate_value_file,
+    },
     workflow::{act::ModWorkflows, prj::GxlProject},
 };
 
@@ -44,7 +53,12 @@ pub struct ModModelSpec {
     gxl_prj: GxlProject,
     logs_spec: LogsSpec,
     res_spec: CaculateResSpec,
+    // spec/ports.yml 声明的监听端口
+    ports: ModPorts,
     vars: VarCollection,
+    // 模块级 mod/vars.yml, 按 when 条件筛选后作为 vars.yml 的底层
+    #[serde(skip)]
+    shared_vars: VarCollection,
     local: Option<PathBuf>,
     setting: Option<Setting>,
     depends: DependencySet,
@@ -55,30 +69,202 @@ impl ModModelSpec {
         self.depends = depends;
         self
     }
+    pub fn with_ports(mut self, ports: ModPorts) -> Self {
+        self.ports = ports;
+        self
+    }
+    pub fn with_shared_vars(mut self, shared_vars: VarCollection) -> Self {
+        self.shared_vars = shared_vars;
+        self
+    }
+    // 当前 target 生效的变量定义: 共享 vars 中匹配的部分, 再由 target 的 vars.yml 覆盖
+    pub fn effective_vars(&self) -> SpecResult<VarCollection> {
+        Ok(target_vars(&self.shared_vars, &self.model)?.merge(&self.vars))
+    }
 
+    // 优先级: global-profile > global > mod-profile > mod-cust > mod-default
+    // 相同 key 按 vars.yml 中声明的 merge 策略深度合并, !reset 删除低优先级的值
     fn build_used_value(
         &self,
         options: LocalizeOptions,
         value_paths: &TargetValuePaths,
     ) -> Result<OriginDict, StructError<SpecReason>> {
-        let mut used = OriginDict::from(options.global_value().clone());
-        used.set_source("global");
-        if value_paths.user_value_file().exists() && !options.use_default_value() {
-            let mut user_dict = OriginDict::from(ValueDict::eval_from_file(
-                &used.export_dict(),
-                value_paths.user_value_file(),
+        let vars = self.effective_vars()?;
+        let mut used = self.merge_used_value(&vars, &options, value_paths)?;
+        let computed = self.expr_value(&vars, &used, &options, value_paths, true)?;
+        let mut expr_dict = OriginDict::from(computed);
+        expr_dict.set_source(MOD_EXPR_SOURCE);
+        used.overlay(expr_dict);
+        Ok(used)
+    }
+
+    // expr 之前的各层
+    fn merge_used_value(
+        &self,
+        vars: &VarCollection,
+        options: &LocalizeOptions,
+        value_paths: &TargetValuePaths,
+    ) -> SpecResult<OriginDict> {
+        let policy = vars.merge_policy();
+        let mut used = options.global_value().clone();
+        for (source, value_file) in mod_value_layers(options, value_paths) {
+            if !value_file.exists() {
+                if source != MOD_CUST_SOURCE {
+                    warn!(target: "mod/target", "{} not found: {}", source, value_file.display());
+                }
+                continue;
+            }
+            let mut dict = OriginDict::from(ValueDict::eval_from_file(
+                &options.eval_dict(&used),
+                &value_file,
             )?);
-            user_dict.set_source("mod-cust");
-            used.merge(&user_dict);
-            info!( target:"mod/target", "use  model value : {}" , value_paths.user_value_file().display());
+            dict.set_source(source.as_str());
+            used.merge_with(&dict, &policy);
+            info!( target:"mod/target", "use  model value : {}" , value_file.display());
         }
         let mut default_dict =
-            OriginDict::from(self.vars.value_dict().env_eval(&used.export_dict()));
-        default_dict.set_source("mod-default");
-        used.merge(&default_dict);
+            OriginDict::from(vars.value_dict().env_eval(&options.eval_dict(&used)));
+        default_dict.set_source(MOD_DEFAULT_SOURCE);
+        used.merge_with(&default_dict, &policy);
+        let mut port_dict = OriginDict::from(self.ports.value_dict());
+        port_dict.set_source(MOD_DEFAULT_SOURCE);
+        used.merge_with(&port_dict, &policy);
+        used.strip_reset();
         Ok(used)
     }
 
+    // 计算 expr 变量, 被 value 文件覆盖的不再计算; res 为资源规格
+    // 生成的随机值缓存在 _generated.yml, 结果中以 ${secret:gen:KEY} 引用代替明文
+    fn expr_value(
+        &self,
+        vars: &VarCollection,
+        used: &OriginDict,
+        options: &LocalizeOptions,
+        value_paths: &TargetValuePaths,
+        persist: bool,
+    ) -> SpecResult<ValueDict> {
+        if !vars.vars().iter().any(|x| x.expr().is_some()) {
+            return Ok(ValueDict::new());
+        }
+        let skip = used
+            .iter()
+            .filter(|(_, v)| v.origin().as_deref() != Some(MOD_DEFAULT_SOURCE))
+            .map(|(k, _)| k.clone())
+            .collect();
+        let mut scope = options.eval_dict(used);
+        let res = serde_json::to_value(&self.res_spec)
+            .and_then(serde_json::from_value::<ValueType>)
+            .owe_data()?;
+        scope.insert("res", res);
+        let generated_file = value_paths.generated_file();
+        let mut generated = if generated_file.exists() {
+            ValueDict::from_valconf(generated_file)?
+        } else {
+            ValueDict::new()
+        };
+        let cached = generated.len();
+        let computed = vars.eval_expr(&scope, &skip, &mut generated)?;
+        if persist && generated.len() != cached {
+            generated.save_valconf(generated_file)?;
+        }
+        let mut concealed = ValueDict::new();
+        for (k, v) in computed.iter() {
+            concealed.insert(k.clone(), conceal_generated(v.clone(), &generated));
+        }
+        Ok(concealed)
+    }
+
+    // 变量来源追溯, 层次顺序与 build_used_value 一致
+    pub fn explain(
+        &self,
+        key: &str,
+        dst_path: Option<ValuePath>,
+        global: ValueExplainer,
+        options: &LocalizeOptions,
+    ) -> SpecResult<VarExplain> {
+        let local = self
+            .local
+            .clone()
+            .ok_or(SpecReason::from(ElementReason::Miss("local-path".into())).to_err())?;
+        let localize_path = dst_path.unwrap_or(ValuePath::new(local.join(VALUE_DIR)));
+        let value_paths = TargetValuePaths::from(localize_path.path());
+        let mut explainer = global;
+        for (source, value_file) in mod_value_layers(options, &value_paths) {
+            explainer = explainer.with_value_file(source, value_file);
+        }
+        let spec_root = ModExtends::spec_root(&local)?;
+        let vars_path = ModTargetPaths::from(&spec_root).vars_path().clone();
+        let shared_path = local.parent().map(|x| x.join(VARS_YML)).unwrap_or_default();
+        let explain = explainer
+            .with_vars_file(MOD_DEFAULT_SOURCE, vars_path.clone())
+            .with_defined_vars(
+                MOD_SHARED_SOURCE,
+                shared_path,
+                target_vars(&self.shared_vars, &self.model)?,
+            )
+            .explain(key)?;
+        // expr 变量只在未被 value 文件覆盖时生效, 排在最前
+        let vars = self.effective_vars()?;
+        let Some(expr) = vars
+            .vars()
+            .iter()
+            .find(|x| x.name() == key)
+            .and_then(|x| x.expr())
+        else {
+            return Ok(explain);
+        };
+        let used = self.merge_used_value(&vars, options, &value_paths)?;
+        let computed = self.expr_value(&vars, &used, options, &value_paths, false)?;
+        match computed.get(key) {
+            Some(value) => Ok(explain.with_computed(
+                MOD_EXPR_SOURCE,
+                vars_path,
+                ValueType::from(expr.as_str()),
+                value.clone(),
+            )),
+            None => Ok(explain),
+        }
+    }
+
+    // setting.yml 中的 include/exclude
+    pub fn templatize_path(&self, local: &Path) -> TemplatePath {
+        self.setting
+            .as_ref()
+            .map(|x| x.templatize_path(local))
+            .unwrap_or_default()
+    }
+    // setting.yml 中的自定义模板标签
+    pub fn localizer(&self) -> LocalizeTemplate<'_> {
+        LocalizeTemplate::from_setting(self.setting.as_ref())
+    }
+
+    pub fn value_schema(&self) -> SpecResult<serde_json::Value> {
+        Ok(self.effective_vars()?.json_schema())
+    }
+    pub fn validate_value_file(&self, value_file: &Path) -> SpecResult<Vec<ValueIssue>> {
+        validate_value_file(&self.value_schema()?, value_file)
+    }
+    // localize 前校验用户 value.yml
+    fn check_user_value(
+        &self,
+        options: &LocalizeOptions,
+        value_paths: &TargetValuePaths,
+    ) -> SpecResult<()> {
+        if options.use_default_value() || !value_paths.user_value_file().exists() {
+            return Ok(());
+        }
+        let issues = self.validate_value_file(value_paths.user_value_file())?;
+        if !issues.is_empty() {
+            let msg = issues
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            return SpecReason::from(LocalizeReason::Validate(msg)).err_result();
+        }
+        Ok(())
+    }
+
     fn crate_sample_value_file(
         &self,
         value_paths: &TargetValuePaths,
@@ -88,7 +274,7 @@ impl ModModelSpec {
                 .sample_value_file()
                 .parent()
                 .map(std::fs::create_dir_all);
-            let vars_dict = self.vars.value_dict();
+            let vars_dict = self.effective_vars()?.value_dict();
             vars_dict.save_valconf(value_paths.sample_value_file())?;
             info!( target:"mod/target", "crate  value.yml at : {}" ,value_paths.sample_value_file().display() );
         }
@@ -115,9 +301,13 @@ impl ModModelSpec {
     }
 
     pub fn clean_other(root: &Path, node: &ModelSTD) -> SpecResult<()> {
+        Self::clean_others(root, std::slice::from_ref(node))
+    }
+    // 只保留 keep 中的 target
+    pub fn clean_others(root: &Path, keep: &[ModelSTD]) -> SpecResult<()> {
         let subs = get_sub_dirs(root)?;
         for sub in subs {
-            if !sub.ends_with(node.to_string().as_str()) {
+            if !keep.iter().any(|x| sub.ends_with(x.to_string().as_str())) {
                 Self::clean_path(&sub)?;
             }
         }
@@ -138,6 +328,7 @@ pub struct ModTargetPaths {
     conf_path: PathBuf,
     logs_path: PathBuf,
     res_path: PathBuf,
+    ports_path: PathBuf,
     vars_path: PathBuf,
     setting_path: PathBuf,
     artifact_path: PathBuf,
@@ -152,6 +343,7 @@ impl From<&PathBuf> for ModTargetPaths {
             conf_path: spec_path.join(CONF_SPEC_YML),
             logs_path: spec_path.join(LOGS_SPEC_YML),
             res_path: spec_path.join(RES_SPEC_YML),
+            ports_path: spec_path.join(PORTS_YML),
             vars_path: target_root.join(VARS_YML),
             setting_path: target_root.join(SETTING_YML),
             artifact_path: spec_path.join(ARTIFACT_YML),
@@ -164,24 +356,57 @@ impl From<&PathBuf> for ModTargetPaths {
 
 #[derive(Getters, Clone, Debug)]
 pub struct TargetValuePaths {
+    value_root: PathBuf,
     used_readable: PathBuf,
     default_value_file: PathBuf,
     user_value_file: PathBuf,
     sample_value_file: PathBuf,
     used_json_path: PathBuf,
+    generated_file: PathBuf,
 }
 impl From<&PathBuf> for TargetValuePaths {
     fn from(value_root: &PathBuf) -> Self {
         Self {
+            value_root: value_root.to_path_buf(),
             used_readable: value_root.join(USED_READABLE_FILE),
             default_value_file: value_root.join(DEFAULT_VALUE_FILE),
             user_value_file: value_root.join(USER_VALUE_FILE),
             sample_value_file: value_root.join(SAMPLE_VALUE_FILE),
             used_json_path: value_root.join(crate::const_vars::USED_JSON),
+            generated_file: value_root.join(GENERATED_VALUE_FILE),
         }
     }
 }
 
+impl TargetValuePaths {
+    pub fn profile_value_file(&self, profile: &str) -> PathBuf {
+        profile_value_file(&self.value_root, profile)
+    }
+}
+
+// 模块层的 value 文件, 优先级从高到低: mod-profile > mod-cust
+// build_used_value 与 explain 共用, 保证两者层次一致
+fn mod_value_layers(
+    options: &LocalizeOptions,
+    value_paths: &TargetValuePaths,
+) -> Vec<(String, PathBuf)> {
+    let mut layers = Vec::new();
+    if options.use_default_value() {
+        return layers;
+    }
+    if let Some(profile) = options.profile() {
+        layers.push((
+            profile_source("mod", profile),
+            value_paths.profile_value_file(profile),
+        ));
+    }
+    layers.push((
+        MOD_CUST_SOURCE.to_string(),
+        value_paths.user_value_file().clone(),
+    ));
+    layers
+}
+
 impl Persistable<ModModelSpec> for ModModelSpec {
     fn save_to(&self, root: &Path, name: Option<String>) -> SpecResult<()> {
         let target_path = root.join(name.unwrap_or(self.model().to_string()));
@@ -205,6 +430,9 @@ impl Persistable<ModModelSpec> for ModModelSpec {
         self.logs_spec.save_conf(paths.logs_path())?;
 
         self.res_spec.save_conf(paths.res_path())?;
+        if !self.ports.is_empty() {
+            self.ports.save_conf(paths.ports_path())?;
+        }
         self.vars.save_conf(paths.vars_path())?;
         self.gxl_prj.save_to(&paths.target_root, None)?;
         flag.flag_suc();
@@ -218,8 +446,10 @@ impl Persistable<ModModelSpec> for ModModelSpec {
             info!(target: "spec/mod/target", "load target  success!:{}", target_root.display()),
             error!(target: "spec/mod/target", "load target failed!:{}", target_root.display())
         );
-        let paths = ModTargetPaths::from(&target_root.to_path_buf());
         ctx.with_path("root", target_root);
+        // 有 extends.yml 时读取 update 生成的合并目录
+        let spec_root = ModExtends::spec_root(target_root).with(&ctx)?;
+        let paths = ModTargetPaths::from(&spec_root);
         let target = ModelSTD::from_str(path_file_name(target_root)?.as_str())
             .owe_res()
             .with(&ctx)?;
@@ -242,9 +472,24 @@ impl Persistable<ModModelSpec> for ModModelSpec {
         let depends = DependencySet::from_conf(paths.depends_path()).with(&ctx)?;
         ctx.with_path("res_spec", paths.res_path());
         let res_spec = CaculateResSpec::from_conf(paths.res_path()).with(&ctx)?;
+        let ports = if paths.ports_path().exists() {
+            ctx.with_path("ports", paths.ports_path());
+            ModPorts::from_conf(paths.ports_path()).with(&ctx)?
+        } else {
+            ModPorts::default()
+        };
         ctx.with_path("vars", paths.vars_path());
         let vars =
             VarCollection::eval_from_file(&ValueDict::default(), paths.vars_path()).with(&ctx)?;
+        // 模块级共享 vars: <mod>/vars.yml, 使用时按 when 筛选
+        let shared_path = target_root.parent().map(|x| x.join(VARS_YML));
+        let shared_vars = match shared_path {
+            Some(path) if path.exists() => {
+                ctx.with_path("shared_vars", &path);
+                VarCollection::eval_from_file(&ValueDict::default(), &path).with(&ctx)?
+            }
+            _ => VarCollection::default(),
+        };
 
         let gxl_prj = GxlProject::load_from(paths.target_root()).with(&ctx)?;
         flag.flag_suc();
@@ -255,8 +500,10 @@ impl Persistable<ModModelSpec> for ModModelSpec {
             //conf_spec,
             logs_spec,
             res_spec,
+            ports,
             local: Some(target_root.to_path_buf()),
             vars,
+            shared_vars,
             setting,
             depends,
             gxl_prj,
@@ -282,8 +529,10 @@ impl ModModelSpec {
             //conf_spec,
             logs_spec: LogsSpec::tpl_init(),
             res_spec,
+            ports: ModPorts::default(),
             local: None,
             vars,
+            shared_vars: VarCollection::default(),
             setting,
             depends: DependencySet::default(),
         }
@@ -316,7 +565,8 @@ impl Localizable for ModModelSpec {
                 .to_err()
                 .with(&ctx),
         )?;
-        let tpl = local.join(crate::const_vars::SPEC_DIR);
+        let spec_root = ModExtends::spec_root(&local).with(&ctx)?;
+        let tpl = spec_root.join(crate::const_vars::SPEC_DIR);
         let localize_path = dst_path.unwrap_or(ValuePath::new(local.join(VALUE_DIR)));
 
         let value_root = localize_path.path(); //.join(VALUE_DIR);
@@ -329,33 +579,24 @@ impl Localizable for ModModelSpec {
         ctx.with_path("dst", &local_path);
         self.crate_sample_value_file(&value_paths)?;
         debug!(target : "/mod/target/loc", "value export");
+        self.check_user_value(&options, &value_paths).with(&ctx)?;
+        let secrets = options
+            .secrets()
+            .clone()
+            .with_provider(GeneratedSecret::new(value_paths.generated_file().clone()));
         let used = self.build_used_value(options, &value_paths)?;
-        used.export_origin()
-            //.env_eval()
-            .save_valconf(value_paths.used_readable())?;
+        // _used.yml 只供查看, secret 引用脱敏; _used.json 保留引用, 渲染时解析
+        let readable: OriginMap = used
+            .export_origin()
+            .into_iter()
+            .map(|(k, v)| (k, v.redacted()))
+            .collect();
+        readable.save_valconf(value_paths.used_readable())?;
         used.export_value().save_json(&used_value_file)?;
 
         debug!(target : "/mod/target/loc", "use value: {}", used_value_file.display());
-        let tpl_path_opt = self
-            .setting
-            .as_ref()
-            .and_then(|x| x.localize().clone())
-            .and_then(|x| x.templatize_path().clone())
-            .map(|x| x.export_paths(&local));
-
-        let tpl_path = tpl_path_opt.unwrap_or_default();
-        let tpl_custom = self
-            .setting
-            .as_ref()
-            .and_then(|x| x.localize().clone())
-            .and_then(|x| x.templatize_cust().clone())
-            .map(TemplateConfig::from);
-
-        let localizer = if let Some(cust) = tpl_custom {
-            LocalizeTemplate::new(cust)
-        } else {
-            LocalizeTemplate::default()
-        };
+        let tpl_path = self.templatize_path(&spec_root);
+        let localizer = self.localizer().with_secrets(secrets);
         localizer
             .render_path(&tpl, &local_path, &used_value_file, &tpl_path)
             .with(&ctx)?;
@@ -377,7 +618,9 @@ pub mod test {
         module::{
             CpuArch, OsCPE, RunSPC,
             init::{ModIniter, ModPrjIniter},
+            ports::PortSpec,
         },
+        secret::SecretResolver,
         tools::{make_clean_path, test_init},
         vars::{OriginValue, ValueType, VarDefinition},
     };
@@ -462,6 +705,11 @@ pub mod test {
         Ok(())
     }
 
+    fn generated_value(value_root: &Path, key: &str) -> String {
+        let generated = ValueDict::from_valconf(&value_root.join(GENERATED_VALUE_FILE)).unwrap();
+        generated.get(key).unwrap().to_string()
+    }
+
     fn build_spec(vars: VarCollection) -> ModModelSpec {
         ModModelSpec::init(
             ModelSTD::new(CpuArch::X86, OsCPE::UBT22, RunSPC::K8S),
@@ -509,6 +757,166 @@ pub mod test {
         );
     }
 
+    struct MockSecret;
+    impl crate::secret::SecretProvider for MockSecret {
+        fn scheme(&self) -> &str {
+            "mock"
+        }
+        fn fetch(&self, key: &str) -> SpecResult<String> {
+            Ok(format!("pass-of-{}", key))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_localize_with_secrets() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let vars =
+            VarCollection::define(vec![VarDefinition::from(("DB_PASS", "${secret:mock:db}"))]);
+        let spec = build_spec(vars);
+        spec.save_to(temp_dir.path(), None).assert();
+        let target_root = temp_dir.path().join(spec.model().to_string());
+        std::fs::write(target_root.join("spec/db.conf"), "pass={{{DB_PASS}}}").unwrap();
+        let loaded = ModModelSpec::load_from(&target_root).assert();
+        let options = LocalizeOptions::new(ValueDict::new(), true)
+            .with_secrets(SecretResolver::empty().with_provider(MockSecret));
+        loaded.localize(None, options).await.assert();
+
+        let rendered = std::fs::read_to_string(target_root.join("local/db.conf")).unwrap();
+        assert_eq!(rendered, "pass=pass-of-db");
+        let value_paths = TargetValuePaths::from(&target_root.join(VALUE_DIR));
+        let readable = std::fs::read_to_string(value_paths.used_readable()).unwrap();
+        assert!(readable.contains("******"));
+        assert!(!readable.contains("pass-of-db"));
+    }
+
+    #[tokio::test]
+    async fn test_localize_with_generated() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("DB_PASS", "")).with_expr("random_password(20)"),
+        ]);
+        let spec = build_spec(vars);
+        spec.save_to(temp_dir.path(), None).assert();
+        let target_root = temp_dir.path().join(spec.model().to_string());
+        std::fs::write(target_root.join("spec/db.conf"), "pass={{{DB_PASS}}}").unwrap();
+        let loaded = ModModelSpec::load_from(&target_root).assert();
+        let options = LocalizeOptions::new(ValueDict::new(), true);
+        loaded.localize(None, options).await.assert();
+
+        // 渲染结果为明文, _used.yml / _used.json 中不出现
+        let value_root = target_root.join(VALUE_DIR);
+        let pass = generated_value(&value_root, "DB_PASS");
+        let rendered = std::fs::read_to_string(target_root.join("local/db.conf")).unwrap();
+        assert_eq!(rendered, format!("pass={}", pass));
+        let value_paths = TargetValuePaths::from(&value_root);
+        for used_file in [value_paths.used_readable(), value_paths.used_json_path()] {
+            let content = std::fs::read_to_string(used_file).unwrap();
+            assert!(!content.contains(pass.as_str()));
+        }
+    }
+
+    #[test]
+    fn test_build_used_value_with_ports() {
+        test_init();
+        let spec = build_spec(VarCollection::default()).with_ports(ModPorts::new(vec![
+            PortSpec::new("http", 8080),
+            PortSpec::new("admin", 9000),
+        ]));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let value_paths = TargetValuePaths::from(&temp_dir.path().to_path_buf());
+        // 系统分配的端口以全局值传入, 覆盖声明的默认端口
+        let mut global_dict = ValueDict::new();
+        global_dict.insert("PORT_HTTP", ValueType::Int(30000));
+        let used = spec
+            .build_used_value(LocalizeOptions::new(global_dict, false), &value_paths)
+            .unwrap();
+        assert_eq!(
+            used.get("PORT_HTTP").unwrap().value(),
+            &ValueType::Int(30000)
+        );
+        assert_eq!(
+            used.get("PORT_ADMIN"),
+            Some(&OriginValue::from(ValueType::Int(9000)).with_origin(MOD_DEFAULT_SOURCE))
+        );
+    }
+
+    #[test]
+    fn test_build_used_value_with_expr() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let value_root = temp_dir.path().to_path_buf();
+        std::fs::write(value_root.join(USER_VALUE_FILE), "ROOT: /data").unwrap();
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("ROOT", "/opt")),
+            VarDefinition::from(("DATA_DIR", "")).with_expr("'${ROOT}/' + lower('PG')"),
+            VarDefinition::from(("MEM_LIMIT", 0)).with_expr("res.mem_size * 256"),
+            VarDefinition::from(("DB_PASS", "")).with_expr("random_password()"),
+        ]);
+        let spec = build_spec(vars);
+        let value_paths = TargetValuePaths::from(&value_root);
+        let used = spec
+            .build_used_value(LocalizeOptions::new(ValueDict::new(), false), &value_paths)
+            .unwrap();
+        assert_eq!(
+            used.get("DATA_DIR"),
+            Some(&OriginValue::from("/data/pg").with_origin(MOD_EXPR_SOURCE))
+        );
+        assert_eq!(
+            used.get("MEM_LIMIT").unwrap().value(),
+            &ValueType::Int(1024)
+        );
+        // _used.* 中只保留引用, 明文在 _generated.yml
+        let pass = used.get("DB_PASS").unwrap().value().clone();
+        assert_eq!(pass, ValueType::from("${secret:gen:DB_PASS}"));
+        let generated = ValueDict::from_valconf(value_paths.generated_file()).unwrap();
+        assert_eq!(generated.get("DB_PASS").unwrap().to_string().len(), 16);
+
+        // 密码只生成一次; 用户指定的值优先于 expr
+        std::fs::write(
+            value_root.join(USER_VALUE_FILE),
+            "ROOT: /data\nDATA_DIR: /mnt",
+        )
+        .unwrap();
+        let used = spec
+            .build_used_value(LocalizeOptions::new(ValueDict::new(), false), &value_paths)
+            .unwrap();
+        assert_eq!(used.get("DB_PASS").unwrap().value(), &pass);
+        assert_eq!(
+            used.get("DATA_DIR"),
+            Some(&OriginValue::from("/mnt").with_origin("mod-cust"))
+        );
+    }
+
+    #[test]
+    fn test_build_used_value_with_shared_vars() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let shared = VarCollection::define(vec![
+            VarDefinition::from(("REPLICAS", 1)),
+            VarDefinition::from(("REPLICAS", 3)).with_when("spc == k8s"),
+            VarDefinition::from(("LOG_DIR", "/var/log")),
+            VarDefinition::from(("BIN_DIR", "/opt/homebrew/bin")).with_when("os =* 'mac*'"),
+        ]);
+        // target 自身的 vars.yml 优先于共享 vars
+        let spec = build_spec(VarCollection::define(vec![VarDefinition::from((
+            "LOG_DIR",
+            "/data/log",
+        ))]))
+        .with_shared_vars(shared);
+        let value_paths = TargetValuePaths::from(&temp_dir.path().to_path_buf());
+        let used = spec
+            .build_used_value(LocalizeOptions::new(ValueDict::new(), false), &value_paths)
+            .unwrap();
+        assert_eq!(used.get("REPLICAS").unwrap().value(), &ValueType::Int(3));
+        assert_eq!(
+            used.get("LOG_DIR").unwrap().value(),
+            &ValueType::from("/data/log")
+        );
+        assert!(used.get("BIN_DIR").is_none());
+    }
+
     #[test]
     fn test_build_used_value_with_user_value() {
         test_init();
@@ -575,4 +983,235 @@ pub mod test {
             Some(&OriginValue::from("default_only").with_origin("mod-default"))
         );
     }
+
+    #[test]
+    fn test_build_used_value_with_profile() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let value_root = temp_dir.path().to_path_buf();
+        std::fs::create_dir_all(value_root.join("profiles")).unwrap();
+        std::fs::write(
+            value_root.join("profiles/prod.yml"),
+            "GLOBAL_KEY: global_prod",
+        )
+        .unwrap();
+        let mod_root = value_root.join("mods/mysql");
+        std::fs::create_dir_all(mod_root.join("profiles")).unwrap();
+        std::fs::write(
+            mod_root.join("profiles/prod.yml"),
+            "MOD_KEY: mod_prod\nCUST_KEY: mod_prod",
+        )
+        .unwrap();
+        std::fs::write(mod_root.join(USER_VALUE_FILE), "CUST_KEY: user_value").unwrap();
+
+        let mut global_dict = ValueDict::new();
+        global_dict.insert("GLOBAL_KEY", ValueType::from("global_value"));
+        global_dict.insert("BASE_KEY", ValueType::from("base_value"));
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("MOD_KEY", "default_value")),
+            VarDefinition::from(("DEFAULT_ONLY", "default_only")),
+        ]);
+        let spec = build_spec(vars);
+        let options = LocalizeOptions::new(global_dict, false)
+            .with_profile(Some("prod".into()))
+            .with_global_profile(&value_root, temp_dir.path())
+            .unwrap();
+        let value_paths = TargetValuePaths::from(&mod_root);
+
+        let result = spec.build_used_value(options, &value_paths).unwrap();
+        assert_eq!(
+            result.get("GLOBAL_KEY"),
+            Some(&OriginValue::from("global_prod").with_origin("global-profile:prod"))
+        );
+        assert_eq!(
+            result.get("BASE_KEY"),
+            Some(&OriginValue::from("base_value").with_origin("global"))
+        );
+        assert_eq!(
+            result.get("MOD_KEY"),
+            Some(&OriginValue::from("mod_prod").with_origin("mod-profile:prod"))
+        );
+        assert_eq!(
+            result.get("CUST_KEY"),
+            Some(&OriginValue::from("mod_prod").with_origin("mod-profile:prod"))
+        );
+        assert_eq!(
+            result.get("DEFAULT_ONLY"),
+            Some(&OriginValue::from("default_only").with_origin("mod-default"))
+        );
+    }
+
+    #[test]
+    fn test_build_used_value_deep_merge() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let value_root = temp_dir.path().to_path_buf();
+        std::fs::write(
+            value_root.join(USER_VALUE_FILE),
+            "ARGS: [--b]\nCONF: { host: 10.0.0.1, debug: !reset }\nDROP: !reset",
+        )
+        .unwrap();
+        let vars: VarCollection = serde_yaml::from_str(
+            r#"
+vars:
+  - name: ARGS
+    value: [--a]
+    merge: append
+  - name: CONF
+    value: { host: localhost, port: 80, debug: true }
+  - name: DROP
+    value: default
+"#,
+        )
+        .unwrap();
+        let spec = build_spec(vars);
+        let options = LocalizeOptions::new(ValueDict::new(), false);
+        let value_paths = TargetValuePaths::from(&value_root);
+        let result = spec.build_used_value(options, &value_paths).unwrap();
+        assert_eq!(
+            result.get("ARGS").map(|x| x.value().to_string()),
+            Some(r#"["--a","--b"]"#.to_string())
+        );
+        assert_eq!(
+            result.get("CONF").map(|x| x.value().to_string()),
+            Some(r#"{"host":"10.0.0.1","port":80}"#.to_string())
+        );
+        assert_eq!(
+            result.get("CONF").and_then(|x| x.origin().clone()),
+            Some("mod-cust".to_string())
+        );
+        assert!(result.get("DROP").is_none());
+    }
+
+    #[test]
+    fn test_build_used_value_empty_keep() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let value_root = temp_dir.path().to_path_buf();
+        std::fs::write(value_root.join(USER_VALUE_FILE), "KEEP:\nNULL: ~").unwrap();
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("KEEP", "default")),
+            VarDefinition::from(("NULL", "default")),
+        ]);
+        let spec = build_spec(vars);
+        let options = LocalizeOptions::new(ValueDict::new(), false);
+        let value_paths = TargetValuePaths::from(&value_root);
+        let result = spec.build_used_value(options, &value_paths).unwrap();
+        // 空值不是删除标记, 变量仍保留
+        assert!(result.get("KEEP").is_some());
+        assert!(result.get("NULL").is_some());
+    }
+
+    #[test]
+    fn test_explain_match_used_value() {
+        test_init();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let target_root = temp_dir.path().join("x86-ubt22-k8s");
+        let value_root = temp_dir.path().join("values");
+        std::fs::create_dir_all(value_root.join("profiles")).unwrap();
+        std::fs::write(value_root.join("profiles/prod.yml"), "CUST_KEY: mod_prod").unwrap();
+        std::fs::write(
+            value_root.join(USER_VALUE_FILE),
+            "CUST_KEY: user\nMOD_KEY: user",
+        )
+        .unwrap();
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("MOD_KEY", "default_value")),
+            VarDefinition::from(("DEFAULT_ONLY", "${CUST_KEY}-only")),
+            VarDefinition::from(("DB_PASS", "")).with_expr("random_password()"),
+        ]);
+        let shared = VarCollection::define(vec![
+            VarDefinition::from(("REPLICAS", 1)),
+            VarDefinition::from(("REPLICAS", 3)).with_when("spc == k8s"),
+        ]);
+        let mut spec = build_spec(vars).with_shared_vars(shared);
+        std::fs::create_dir_all(&target_root).unwrap();
+        spec.vars.save_conf(&target_root.join(VARS_YML)).unwrap();
+        spec.local = Some(target_root);
+
+        let options =
+            LocalizeOptions::new(ValueDict::new(), false).with_profile(Some("prod".into()));
+        let value_paths = TargetValuePaths::from(&value_root);
+        let used = spec
+            .build_used_value(options.clone(), &value_paths)
+            .unwrap();
+        for key in ["CUST_KEY", "MOD_KEY", "DEFAULT_ONLY", "DB_PASS"] {
+            let explain = spec
+                .explain(
+                    key,
+                    Some(ValuePath::new(&value_root)),
+                    ValueExplainer::new(),
+                    &options,
+                )
+                .unwrap();
+            let winner = explain.winner().unwrap();
+            let used_v = used.get(key).unwrap();
+            assert_eq!(Some(winner.source()), used_v.origin().as_ref());
+            assert_eq!(winner.value(), used_v.value());
+        }
+        let explain = spec
+            .explain(
+                "CUST_KEY",
+                Some(ValuePath::new(&value_root)),
+                ValueExplainer::new(),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(explain.hits().len(), 2);
+        assert_eq!(explain.hits()[1].source(), "mod-cust");
+
+        // 共享 vars 与 expr 层
+        let explain = spec
+            .explain(
+                "REPLICAS",
+                Some(ValuePath::new(&value_root)),
+                ValueExplainer::new(),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(explain.winner().unwrap().source(), MOD_SHARED_SOURCE);
+        assert_eq!(explain.winner().unwrap().value(), &ValueType::Int(3));
+        let explain = spec
+            .explain(
+                "DB_PASS",
+                Some(ValuePath::new(&value_root)),
+                ValueExplainer::new(),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(explain.hits().len(), 2);
+        assert_eq!(
+            explain.winner().unwrap().raw(),
+            &ValueType::from("random_password()")
+        );
+        assert!(
+            !explain
+                .to_string()
+                .contains(generated_value(&value_root, "DB_PASS").as_str())
+        );
+    }
+
+    #[test]
+    fn test_global_profile_missing() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let options = LocalizeOptions::new(ValueDict::new(), false)
+            .with_profile(Some("prod".into()))
+            .with_global_profile(temp_dir.path(), temp_dir.path());
+        assert!(options.is_err());
+
+        // 全局缺少但模块层提供时, 跳过全局层
+        let mod_profiles = temp_dir.path().join("mods/redis/profiles");
+        std::fs::create_dir_all(&mod_profiles).unwrap();
+        std::fs::write(mod_profiles.join("prod.yml"), "KEY: prod").unwrap();
+        let mut global_dict = ValueDict::new();
+        global_dict.insert("TEST_KEY", ValueType::from("base"));
+        let options = LocalizeOptions::new(global_dict, false)
+            .with_profile(Some("prod".into()))
+            .with_global_profile(temp_dir.path(), temp_dir.path())
+            .unwrap();
+        assert_eq!(
+            options.global_value().get("TEST_KEY"),
+            Some(&OriginValue::from("base").with_origin("global"))
+        );
+    }
 }
@@ -0,0 +1,522 @@
+use crate::predule::*;
+
+use std::fmt::Display;
+
+use walkdir::WalkDir;
+
+use super::{ModelSTD, proj::ModProject};
+use crate::{
+    addr::{AddrType, LocalAddr, path_file_name},
+    const_vars::{EXTENDS_DIR, EXTENDS_YML, LAYERS_YML, LOCAL_DIR, MOD_DIR, VALUE_DIR},
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    tools::{get_sub_dirs, make_clean_path},
+    types::{AsyncUpdateable, Configable},
+    vars::EnvEvalable,
+};
+
+// 继承链的最大深度
+const MAX_EXTENDS_DEPTH: usize = 8;
+// 拉取的基础模块工程名, 位于 <target>/.extends/ 下
+const BASE_PRJ_NAME: &str = "base";
+// 合并结果目录, 位于 <target>/.extends/ 下, 不写回 target 目录
+const MERGED_NAME: &str = "merged";
+
+// target 目录下的 extends.yml
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct ModExtends {
+    // 基础模块工程地址, 本地相对路径基于 target 目录
+    extends: AddrType,
+    // 基础模块的 target, 缺省与当前 target 相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<ModelSTD>,
+    // 从基础层删除的文件, 相对 target 目录
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    delete: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LayerKind {
+    // 只在当前层
+    Own,
+    // 复制自基础层
+    Base,
+    // 当前层覆盖基础层
+    Override,
+}
+
+impl Display for LayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayerKind::Own => write!(f, "own"),
+            LayerKind::Base => write!(f, "base"),
+            LayerKind::Override => write!(f, "override"),
+        }
+    }
+}
+
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct FileLayer {
+    path: String,
+    kind: LayerKind,
+    // 来源层的地址, 多级继承时为最初提供该文件的层
+    layer: String,
+}
+
+impl Display for FileLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<40} {:<10} {}", self.path, self.kind, self.layer)
+    }
+}
+
+// 合并结果, 保存为合并目录下的 .layers.yml
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct TargetLayers {
+    files: Vec<FileLayer>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deleted: Vec<String>,
+}
+
+impl TargetLayers {
+    pub fn load(target_root: &Path) -> SpecResult<Option<Self>> {
+        let path = merged_root(target_root).join(LAYERS_YML);
+        if path.exists() {
+            Ok(Some(Self::from_conf(&path)?))
+        } else {
+            Ok(None)
+        }
+    }
+    fn find(&self, path: &str) -> Option<&FileLayer> {
+        self.files.iter().find(|x| x.path == path)
+    }
+}
+
+// 合并时不参与的目录与文件
+fn is_layer_skip(name: &str) -> bool {
+    [VALUE_DIR, LOCAL_DIR, EXTENDS_DIR, EXTENDS_YML, LAYERS_YML].contains(&name)
+}
+
+// 合并后的 target 目录
+pub fn merged_root(target_root: &Path) -> PathBuf {
+    target_root.join(EXTENDS_DIR).join(MERGED_NAME)
+}
+
+// 当前层 (含 extends.yml) 在上次合并之后有改动
+fn is_merge_stale(target_root: &Path, merged: &Path) -> SpecResult<bool> {
+    let Ok(merged_at) = merged
+        .join(LAYERS_YML)
+        .metadata()
+        .and_then(|x| x.modified())
+    else {
+        return Ok(true);
+    };
+    let mut own: Vec<PathBuf> = layer_files(target_root)?
+        .iter()
+        .map(|x| target_root.join(x))
+        .collect();
+    own.push(target_root.join(EXTENDS_YML));
+    Ok(own
+        .iter()
+        .filter_map(|x| x.metadata().and_then(|m| m.modified()).ok())
+        .any(|x| x > merged_at))
+}
+
+fn layer_files(root: &Path) -> SpecResult<Vec<String>> {
+    let mut files = Vec::new();
+    let walker = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|x| x.depth() != 1 || !is_layer_skip(&x.file_name().to_string_lossy()));
+    for entry in walker {
+        let entry = entry.owe_res().with(root)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(root).owe_sys()?;
+        files.push(
+            rel.components()
+                .map(|x| x.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+        );
+    }
+    files.sort();
+    Ok(files)
+}
+
+impl ModExtends {
+    pub fn new<A: Into<AddrType>>(extends: A) -> Self {
+        Self {
+            extends: extends.into(),
+            target: None,
+            delete: Vec::new(),
+        }
+    }
+    pub fn with_target(mut self, target: ModelSTD) -> Self {
+        self.target = Some(target);
+        self
+    }
+    pub fn with_delete(mut self, delete: Vec<String>) -> Self {
+        self.delete = delete;
+        self
+    }
+    pub fn load(target_root: &Path) -> SpecResult<Option<Self>> {
+        let path = target_root.join(EXTENDS_YML);
+        if path.exists() {
+            Ok(Some(Self::from_conf(&path)?))
+        } else {
+            Ok(None)
+        }
+    }
+    fn base_target(&self, target_root: &Path) -> SpecResult<String> {
+        match &self.target {
+            Some(target) => Ok(target.to_string()),
+            None => path_file_name(target_root),
+        }
+    }
+    // 基础层的 target 目录, 由 fetch 拉取到 .extends/base
+    pub fn base_root(&self, target_root: &Path) -> SpecResult<PathBuf> {
+        let target = self.base_target(target_root)?;
+        let fetched = target_root
+            .join(EXTENDS_DIR)
+            .join(BASE_PRJ_NAME)
+            .join(MOD_DIR)
+            .join(&target);
+        if fetched.exists() {
+            return Ok(fetched);
+        }
+        SpecReason::from(ElementReason::Miss(format!(
+            "extends base {} of {}, run update first",
+            target,
+            target_root.display()
+        )))
+        .err_result()
+    }
+
+    // 读取时使用的 target 目录: 有 extends 时为合并目录
+    pub fn spec_root(target_root: &Path) -> SpecResult<PathBuf> {
+        if !target_root.join(EXTENDS_YML).exists() {
+            return Ok(target_root.to_path_buf());
+        }
+        let merged = merged_root(target_root);
+        if merged.exists() {
+            if is_merge_stale(target_root, &merged)? {
+                return SpecReason::from(ElementReason::Version(format!(
+                    "merged extends of {} is older than its own layer, run update first",
+                    target_root.display()
+                )))
+                .err_result();
+            }
+            return Ok(merged);
+        }
+        SpecReason::from(ElementReason::Miss(format!(
+            "merged extends of {}, run update first",
+            target_root.display()
+        )))
+        .err_result()
+    }
+
+    // 拉取基础层到 <target>/.extends/base, 基础层自身的 extends 一并拉取
+    pub async fn fetch(target_root: &Path, options: &UpdateOptions) -> SpecResult<()> {
+        Self::fetch_from(target_root, target_root, options).await
+    }
+    // origin 为 target 的原始目录, 本地相对地址基于它解析;
+    // target 被复制或移动到别处后 (如 sys/mods), 仍能找到基础层
+    pub async fn fetch_from(
+        target_root: &Path,
+        origin: &Path,
+        options: &UpdateOptions,
+    ) -> SpecResult<()> {
+        let mut root = target_root.to_path_buf();
+        let mut origin = origin.to_path_buf();
+        for _ in 0..MAX_EXTENDS_DEPTH {
+            let Some(extends) = Self::load(&root)? else {
+                return Ok(());
+            };
+            let cache = root.join(EXTENDS_DIR);
+            make_clean_path(&cache)?;
+            std::fs::create_dir_all(&cache).owe_res().with(&cache)?;
+            let mut addr = extends.extends.clone().env_eval(&options.env_dict());
+            let mut local_prj = None;
+            if let AddrType::Local(local) = &addr {
+                let prj = origin.join(local.path());
+                let prj = prj.canonicalize().unwrap_or(prj);
+                addr = AddrType::from(LocalAddr::from(prj.display().to_string()));
+                local_prj = Some(prj);
+            }
+            addr.update_rename(&cache, BASE_PRJ_NAME, options).await?;
+            let base_root = extends.base_root(&root)?;
+            // 本地基础层的下一级仍按其原始目录解析
+            origin = match local_prj {
+                Some(prj) => prj.join(MOD_DIR).join(extends.base_target(&root)?),
+                None => base_root.clone(),
+            };
+            root = base_root;
+        }
+        SpecReason::from(ElementReason::Cycle(format!(
+            "extends of {} deeper than {}",
+            target_root.display(),
+            MAX_EXTENDS_DEPTH
+        )))
+        .err_result()
+    }
+    // 拉取 mod/ 下各 target 的基础层
+    pub async fn fetch_all(mod_root: &Path, options: &UpdateOptions) -> SpecResult<()> {
+        if !mod_root.exists() {
+            return Ok(());
+        }
+        for sub in get_sub_dirs(mod_root)? {
+            Self::fetch(&sub, options).await?;
+        }
+        Ok(())
+    }
+    // 合并 mod/ 下各 target
+    pub fn materialize_all(mod_root: &Path) -> SpecResult<()> {
+        if !mod_root.exists() {
+            return Ok(());
+        }
+        for sub in get_sub_dirs(mod_root)? {
+            Self::materialize(&sub)?;
+        }
+        Ok(())
+    }
+
+    // 将当前层与基础层逐文件合并到 .extends/merged: 当前层已有的文件覆盖基础层, delete 中的文件不复制
+    pub fn materialize(target_root: &Path) -> SpecResult<Option<TargetLayers>> {
+        Self::materialize_depth(target_root, 0)
+    }
+    fn materialize_depth(target_root: &Path, depth: usize) -> SpecResult<Option<TargetLayers>> {
+        let Some(extends) = Self::load(target_root)? else {
+            return Ok(None);
+        };
+        if depth >= MAX_EXTENDS_DEPTH {
+            return SpecReason::from(ElementReason::Cycle(format!(
+                "extends of {} deeper than {}",
+                target_root.display(),
+                MAX_EXTENDS_DEPTH
+            )))
+            .err_result();
+        }
+        let mut ctx = WithContext::want("materialize extends");
+        ctx.with_path("target", target_root);
+        let base_root = extends.base_root(target_root).with(&ctx)?;
+        let base_layers = Self::materialize_depth(&base_root, depth + 1)?;
+        // 基础层也有 extends 时, 使用其合并结果
+        let base_src = Self::spec_root(&base_root)?;
+        let merged = merged_root(target_root);
+        make_clean_path(&merged)?;
+        std::fs::create_dir_all(&merged).owe_res().with(&merged)?;
+
+        let layer_name = extends.extends.to_string();
+        let own = layer_files(target_root)?;
+        let base = layer_files(&base_src)?;
+        // 多级继承时记录最初提供文件的层
+        let origin = |rel: &str| {
+            base_layers
+                .as_ref()
+                .and_then(|x| x.find(rel))
+                .filter(|x| x.kind == LayerKind::Base)
+                .map(|x| x.layer.clone())
+                .unwrap_or(layer_name.clone())
+        };
+        let mut layers = TargetLayers::default();
+        for rel in &base {
+            if extends.delete.contains(rel) {
+                layers.deleted.push(rel.clone());
+                continue;
+            }
+            if own.contains(rel) {
+                continue;
+            }
+            copy_layer_file(&base_src, &merged, rel)?;
+            layers.files.push(FileLayer {
+                path: rel.clone(),
+                kind: LayerKind::Base,
+                layer: origin(rel),
+            });
+        }
+        for rel in &own {
+            let kind = if base.contains(rel) && !extends.delete.contains(rel) {
+                LayerKind::Override
+            } else {
+                LayerKind::Own
+            };
+            let layer = match kind {
+                LayerKind::Override => origin(rel),
+                _ => String::from("."),
+            };
+            copy_layer_file(target_root, &merged, rel)?;
+            layers.files.push(FileLayer {
+                path: rel.clone(),
+                kind,
+                layer,
+            });
+        }
+        layers.files.sort_by(|a, b| a.path.cmp(&b.path));
+        layers.save_conf(&merged.join(LAYERS_YML))?;
+        Ok(Some(layers))
+    }
+}
+
+fn copy_layer_file(src_root: &Path, dst_root: &Path, rel: &str) -> SpecResult<()> {
+    let src = src_root.join(rel);
+    let dst = dst_root.join(rel);
+    if let Some(parent) = dst.parent() {
+        std::fs::create_dir_all(parent).owe_res().with(parent)?;
+    }
+    std::fs::copy(&src, &dst).owe_res().with(&src)?;
+    Ok(())
+}
+
+impl ModProject {
+    // 各 target 的合并来源, 没有 extends 的 target 不返回
+    pub fn target_layers(&self) -> SpecResult<Vec<(ModelSTD, ModExtends, TargetLayers)>> {
+        let mut result = Vec::new();
+        let mut targets: Vec<&ModelSTD> = self.mod_spec().targets().keys().collect();
+        targets.sort_by_key(|x| x.to_string());
+        for target in targets {
+            let target_root = self.root_local().join(MOD_DIR).join(target.to_string());
+            if let (Some(extends), Some(layers)) = (
+                ModExtends::load(&target_root)?,
+                TargetLayers::load(&target_root)?,
+            ) {
+                result.push((target.clone(), extends, layers));
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        addr::LocalAddr,
+        module::{model::ModModelSpec, spec::ModuleSpec},
+        types::Persistable,
+    };
+
+    fn k8s_spec(name: &str) -> ModModelSpec {
+        ModuleSpec::make_new(name)
+            .assert()
+            .targets()
+            .get(&ModelSTD::x86_ubt22_k8s())
+            .cloned()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mod_extends() {
+        let temp = tempfile::tempdir().assert();
+        let target = ModelSTD::x86_ubt22_k8s().to_string();
+        let base_mod = temp.path().join("mysql").join(MOD_DIR);
+        k8s_spec("mysql").save_to(&base_mod, None).assert();
+        std::fs::write(base_mod.join(&target).join("spec/my.cnf"), "base").assert();
+
+        // mysql-ha 只提供覆盖的 my.cnf 与新增的 ha.cnf
+        let ha_root = temp.path().join("mysql-ha").join(MOD_DIR).join(&target);
+        std::fs::create_dir_all(ha_root.join("spec")).assert();
+        std::fs::write(ha_root.join("spec/my.cnf"), "ha").assert();
+        std::fs::write(ha_root.join("spec/ha.cnf"), "ha").assert();
+        ModExtends::new(LocalAddr::from("../../../mysql"))
+            .with_delete(vec![String::from("spec/logs.yml")])
+            .save_conf(&ha_root.join(EXTENDS_YML))
+            .assert();
+
+        // 未拉取时无法读取
+        assert!(ModExtends::spec_root(&ha_root).is_err());
+        let options = UpdateOptions::default();
+        ModExtends::fetch(&ha_root, &options).await.assert();
+        let layers = ModExtends::materialize(&ha_root).assert().unwrap();
+        let kind = |path: &str| layers.find(path).map(|x| x.kind.clone());
+        assert_eq!(kind("spec/my.cnf"), Some(LayerKind::Override));
+        assert_eq!(kind("spec/ha.cnf"), Some(LayerKind::Own));
+        assert_eq!(kind("spec/artifact.yml"), Some(LayerKind::Base));
+        assert_eq!(kind("spec/logs.yml"), None);
+        assert_eq!(layers.deleted(), &vec![String::from("spec/logs.yml")]);
+        let merged = ModExtends::spec_root(&ha_root).assert();
+        assert_eq!(merged, merged_root(&ha_root));
+        assert_eq!(
+            std::fs::read_to_string(merged.join("spec/my.cnf")).assert(),
+            "ha"
+        );
+        assert!(merged.join("spec/artifact.yml").exists());
+        assert!(!merged.join("spec/logs.yml").exists());
+        // 源 target 目录不被写入
+        assert!(!ha_root.join("spec/artifact.yml").exists());
+        assert!(!ha_root.join(LAYERS_YML).exists());
+
+        // 基础层删除的文件在重新合并时移除
+        std::fs::remove_file(base_mod.join(&target).join("spec/res.yml")).assert();
+        ModExtends::fetch(&ha_root, &options).await.assert();
+        let layers = ModExtends::materialize(&ha_root).assert().unwrap();
+        assert!(layers.find("spec/res.yml").is_none());
+        assert!(!merged.join("spec/res.yml").exists());
+
+        // 当前层在合并后修改, 须重新 update 才能读取
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(ha_root.join("spec/my.cnf"), "ha-2").assert();
+        assert!(ModExtends::spec_root(&ha_root).is_err());
+        ModExtends::materialize(&ha_root).assert();
+        let merged = ModExtends::spec_root(&ha_root).assert();
+        assert_eq!(
+            std::fs::read_to_string(merged.join("spec/my.cnf")).assert(),
+            "ha-2"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extends_relocated() {
+        let temp = tempfile::tempdir().assert();
+        let target = ModelSTD::x86_ubt22_k8s().to_string();
+        let base_mod = temp.path().join("mysql").join(MOD_DIR);
+        k8s_spec("mysql").save_to(&base_mod, None).assert();
+        let ha_root = temp.path().join("mysql-ha").join(MOD_DIR).join(&target);
+        std::fs::create_dir_all(ha_root.join("spec")).assert();
+        ModExtends::new(LocalAddr::from("../../../mysql"))
+            .save_conf(&ha_root.join(EXTENDS_YML))
+            .assert();
+
+        // 复制到 sys/mods 后, 相对地址仍按原目录解析
+        let mods_root = temp.path().join("sys").join("mods").join("mysql-ha");
+        std::fs::create_dir_all(&mods_root).assert();
+        let copy = fs_extra::dir::CopyOptions::new();
+        fs_extra::dir::copy(&ha_root, &mods_root, &copy).assert();
+        let moved = mods_root.join(&target);
+        ModExtends::fetch_from(&moved, &ha_root, &UpdateOptions::default())
+            .await
+            .assert();
+        ModExtends::materialize(&moved).assert();
+        assert!(merged_root(&moved).join("spec/artifact.yml").exists());
+    }
+
+    #[tokio::test]
+    async fn test_extends_load_target() {
+        let temp = tempfile::tempdir().assert();
+        let target = ModelSTD::x86_ubt22_k8s().to_string();
+        let base_mod = temp.path().join("mysql").join(MOD_DIR);
+        k8s_spec("mysql").save_to(&base_mod, None).assert();
+
+        let ha_root = temp.path().join("mysql-ha").join(MOD_DIR).join(&target);
+        std::fs::create_dir_all(ha_root.join("spec")).assert();
+        std::fs::write(
+            ha_root.join("vars.yml"),
+            "vars:\n  - name: HA_NODES\n    value: 3\n",
+        )
+        .assert();
+        ModExtends::new(LocalAddr::from("../../../mysql"))
+            .save_conf(&ha_root.join(EXTENDS_YML))
+            .assert();
+        // 读取时不合并
+        assert!(ModModelSpec::load_from(&ha_root).is_err());
+        ModExtends::fetch(&ha_root, &UpdateOptions::default())
+            .await
+            .assert();
+        ModExtends::materialize(&ha_root).assert();
+        let spec = ModModelSpec::load_from(&ha_root).assert();
+        assert!(spec.vars().vars().iter().any(|x| x.name() == "HA_NODES"));
+        assert!(!spec.artifact().is_empty());
+        assert_eq!(spec.local(), &Some(ha_root.clone()));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::predule::*;
+
+use std::{fs::File, io::Read, str::FromStr};
+
+use flate2::{Compression, write::GzEncoder};
+use orion_error::UvsConfFrom;
+use semver::Version;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use super::{ModelSTD, init::mod_ignore_names, proj::ModProject, require::parse_version};
+use crate::{
+    addr::{AddrType, path_file_name},
+    const_vars::{MANIFEST_YML, MOD_DIR, VALUE_DIR, VERSION_TXT},
+    error::{ElementReason, LocalizeReason, SpecReason, SpecResult, ToErr},
+    types::Configable,
+    vars::{ImportMode, RuntimeContext},
+};
+
+// 打包时升级 version.txt
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl FromStr for VersionBump {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "major" => Ok(VersionBump::Major),
+            "minor" => Ok(VersionBump::Minor),
+            "patch" => Ok(VersionBump::Patch),
+            _ => Err(format!("unknown version bump: {}", s)),
+        }
+    }
+}
+
+impl VersionBump {
+    pub fn apply(&self, version: &Version) -> Version {
+        match self {
+            VersionBump::Major => Version::new(version.major + 1, 0, 0),
+            VersionBump::Minor => Version::new(version.major, version.minor + 1, 0),
+            VersionBump::Patch => Version::new(version.major, version.minor, version.patch + 1),
+        }
+    }
+}
+
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct PackageFile {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+// 随包写入 manifest.yml
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct PackageManifest {
+    name: String,
+    version: String,
+    targets: Vec<ModelSTD>,
+    files: Vec<PackageFile>,
+}
+
+#[derive(Getters, Clone, Debug)]
+pub struct ModPackage {
+    file: PathBuf,
+    // tar.gz 文件的 sha256, 同时写入 <file>.sha256
+    sha256: String,
+    manifest: PackageManifest,
+}
+
+pub fn file_sha256(path: &Path) -> SpecResult<(u64, String)> {
+    let mut file = File::open(path).owe_res().with(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 8192];
+    let mut size = 0;
+    loop {
+        let n = file.read(&mut buf).owe_res().with(path)?;
+        if n == 0 {
+            break;
+        }
+        size += n as u64;
+        hasher.update(&buf[..n]);
+    }
+    Ok((size, format!("{:x}", hasher.finalize())))
+}
+
+// .gitignore 中的本地产物只在其所在层级忽略:
+// target 下的 local/.extends 等, target 值目录中的 _generated.yml;
+// 其他位置的同名文件 (如 spec/dist/) 照常打包, 工程根下的 dist 不在打包目录内
+fn is_local_artifact(rel: &Path, ignores: &[&str]) -> bool {
+    let parts: Vec<String> = rel
+        .components()
+        .map(|x| x.as_os_str().to_string_lossy().to_string())
+        .collect();
+    match parts.as_slice() {
+        [dir, _, name] => dir == MOD_DIR && ignores.contains(&name.as_str()),
+        [dir, _, value, name] => {
+            dir == MOD_DIR && value == VALUE_DIR && ignores.contains(&name.as_str())
+        }
+        _ => false,
+    }
+}
+
+impl ModProject {
+    pub fn version(&self) -> SpecResult<Version> {
+        let path = self.root_local().join(VERSION_TXT);
+        if !path.exists() {
+            return SpecReason::from(ElementReason::Miss(format!("{}", path.display())))
+                .err_result();
+        }
+        let text = std::fs::read_to_string(&path).owe_res().with(&path)?;
+        parse_version(self.mod_spec().name(), &text)
+    }
+    pub fn bump_version(&self, bump: VersionBump) -> SpecResult<Version> {
+        let version = bump.apply(&self.version()?);
+        let path = self.root_local().join(VERSION_TXT);
+        std::fs::write(&path, version.to_string())
+            .owe_res()
+            .with(&path)?;
+        Ok(version)
+    }
+
+    // 打包前检查: 有 target, 版本可解析, 模板引用都已定义
+    pub fn check_package(&self, runtime: &RuntimeContext) -> SpecResult<Version> {
+        if self.mod_spec().targets().is_empty() {
+            return SpecReason::from(ElementReason::Miss(format!(
+                "module {} targets",
+                self.mod_spec().name()
+            )))
+            .err_result();
+        }
+        let version = self.version()?;
+        let errors: usize = self
+            .lint(&[], &[], ImportMode::default(), runtime)?
+            .iter()
+            .map(|(_, issues)| issues.iter().filter(|x| x.is_error()).count())
+            .sum();
+        if errors > 0 {
+            return SpecReason::from(LocalizeReason::Validate(format!(
+                "{} undefined variable refs, run ds-mod lint",
+                errors
+            )))
+            .err_result();
+        }
+        let issues = self.validate_values(&None, runtime)?;
+        if !issues.is_empty() {
+            return SpecReason::from(LocalizeReason::Validate(format!(
+                "{} value issues, run ds-mod values validate",
+                issues.len()
+            )))
+            .err_result();
+        }
+        Ok(version)
+    }
+
+    // 打包文件: mod/, _gal/ 与 version.txt, 跳过 .gitignore 中的本地产物
+    fn package_files(&self) -> SpecResult<Vec<(String, PathBuf)>> {
+        let root = self.root_local();
+        let ignores = mod_ignore_names();
+        let mut files = vec![(VERSION_TXT.to_string(), root.join(VERSION_TXT))];
+        for dir in [MOD_DIR, "_gal"] {
+            let walker = WalkDir::new(root.join(dir)).into_iter().filter_entry(|x| {
+                x.path()
+                    .strip_prefix(root)
+                    .map(|rel| !is_local_artifact(rel, &ignores))
+                    .unwrap_or(true)
+            });
+            for entry in walker {
+                let entry = entry.owe_res()?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let rel = entry.path().strip_prefix(root).owe_sys()?;
+                let rel = rel
+                    .components()
+                    .map(|x| x.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((rel, entry.path().to_path_buf()));
+            }
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    }
+
+    // 生成 <out_dir>/<name>-<version>.tar.gz, 调用前先 check_package
+    pub fn package(&self, out_dir: &Path) -> SpecResult<ModPackage> {
+        let version = self.version()?;
+        let name = self.mod_spec().name().clone();
+        let mut ctx = WithContext::want("package module");
+        ctx.with_path("out", out_dir);
+
+        let mut manifest = PackageManifest {
+            name: name.clone(),
+            version: version.to_string(),
+            targets: self.mod_spec().targets().keys().cloned().collect(),
+            files: Vec::new(),
+        };
+        manifest.targets.sort_by_key(|x| x.to_string());
+        let files = self.package_files()?;
+        for (rel, path) in &files {
+            let (size, sha256) = file_sha256(path)?;
+            manifest.files.push(PackageFile {
+                path: rel.clone(),
+                size,
+                sha256,
+            });
+        }
+
+        std::fs::create_dir_all(out_dir).owe_res().with(&ctx)?;
+        let manifest_path = out_dir.join(MANIFEST_YML);
+        manifest.save_conf(&manifest_path)?;
+        let file = out_dir.join(format!("{}-{}.tar.gz", name, version));
+        let tar_gz = File::create(&file).owe_res().with(&ctx)?;
+        let mut builder = tar::Builder::new(GzEncoder::new(tar_gz, Compression::default()));
+        builder
+            .append_path_with_name(&manifest_path, MANIFEST_YML)
+            .owe_res()
+            .with(&ctx)?;
+        for (rel, path) in &files {
+            builder
+                .append_path_with_name(path, rel)
+                .owe_res()
+                .with(&ctx)?;
+        }
+        builder
+            .into_inner()
+            .and_then(|x| x.finish())
+            .owe_res()
+            .with(&ctx)?;
+        std::fs::remove_file(&manifest_path).owe_res().with(&ctx)?;
+
+        let (_, sha256) = file_sha256(&file)?;
+        let sum_file = PathBuf::from(format!("{}.sha256", file.display()));
+        std::fs::write(
+            &sum_file,
+            format!("{}  {}\n", sha256, path_file_name(&file)?),
+        )
+        .owe_res()
+        .with(&sum_file)?;
+        info!(target: "mod/package", "package {} to {}", name, file.display());
+        Ok(ModPackage {
+            file,
+            sha256,
+            manifest,
+        })
+    }
+}
+
+impl ModPackage {
+    // http 地址以 / 结尾时追加包文件名; 本地地址复制到目录
+    pub async fn upload(&self, addr: &AddrType, method: &str) -> SpecResult<()> {
+        let file_name = path_file_name(&self.file)?;
+        match addr {
+            AddrType::Http(http) => {
+                let http = if http.url().ends_with('/') {
+                    http.clone()
+                        .with_url(format!("{}{}", http.url(), file_name))
+                } else {
+                    http.clone()
+                };
+                http.upload(&self.file, method).await
+            }
+            AddrType::Local(local) => {
+                let dst = PathBuf::from(local.path());
+                std::fs::create_dir_all(&dst).owe_res().with(&dst)?;
+                std::fs::copy(&self.file, dst.join(&file_name))
+                    .owe_res()
+                    .with(&dst)?;
+                Ok(())
+            }
+            AddrType::Git(_) => Err(StructError::from_conf(
+                "upload package to git address not supported".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use flate2::read::GzDecoder;
+    use httpmock::{Method::PUT, MockServer};
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::addr::{HttpAddr, LocalAddr};
+
+    fn make_prj(root: &Path) -> ModProject {
+        let prj_path = root.join("pkg_mod");
+        std::fs::create_dir_all(&prj_path).assert();
+        ModProject::make_new(&prj_path, "pkg_mod")
+            .assert()
+            .save()
+            .assert();
+        ModProject::load(&prj_path).assert()
+    }
+
+    #[test]
+    fn test_version_bump() {
+        let v = Version::new(1, 2, 3);
+        assert_eq!(VersionBump::Patch.apply(&v), Version::new(1, 2, 4));
+        assert_eq!(VersionBump::Minor.apply(&v), Version::new(1, 3, 0));
+        assert_eq!(VersionBump::Major.apply(&v), Version::new(2, 0, 0));
+        assert!(VersionBump::from_str("build").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mod_package() -> SpecResult<()> {
+        let temp = tempfile::tempdir().owe_res()?;
+        let prj = make_prj(temp.path());
+        assert_eq!(prj.bump_version(VersionBump::Minor)?, Version::new(0, 2, 0));
+        let target = prj
+            .root_local()
+            .join(MOD_DIR)
+            .join(ModelSTD::x86_ubt22_k8s().to_string());
+        std::fs::create_dir_all(target.join("local")).owe_res()?;
+        std::fs::write(target.join("local/tmp.txt"), "tmp").owe_res()?;
+        // 非本地产物位置的同名目录照常打包
+        std::fs::create_dir_all(target.join("spec/dist")).owe_res()?;
+        std::fs::write(target.join("spec/dist/app.js"), "js").owe_res()?;
+
+        prj.check_package(&RuntimeContext::default())?;
+        let out = temp.path().join("dist");
+        let package = prj.package(&out)?;
+        assert_eq!(package.file(), &out.join("pkg_mod-0.2.0.tar.gz"));
+        assert!(out.join("pkg_mod-0.2.0.tar.gz.sha256").exists());
+        assert_eq!(package.manifest().version(), "0.2.0");
+        assert!(!package.manifest().targets().is_empty());
+        let paths: HashSet<&str> = package
+            .manifest()
+            .files()
+            .iter()
+            .map(|x| x.path().as_str())
+            .collect();
+        assert!(paths.contains(VERSION_TXT));
+        assert!(paths.contains("_gal/work.gxl"));
+        assert!(paths.iter().all(|x| !x.contains("/local/")));
+        assert!(paths.contains("mod/x86-ubt22-k8s/spec/dist/app.js"));
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(package.file()).owe_res()?));
+        let entries: HashSet<String> = archive
+            .entries()
+            .owe_res()?
+            .map(|x| x.unwrap().path().unwrap().display().to_string())
+            .collect();
+        assert!(entries.contains(MANIFEST_YML));
+        assert_eq!(entries.len(), package.manifest().files().len() + 1);
+
+        let repo = temp.path().join("repo");
+        package
+            .upload(
+                &AddrType::from(LocalAddr::from(repo.display().to_string())),
+                "PUT",
+            )
+            .await?;
+        assert!(repo.join("pkg_mod-0.2.0.tar.gz").exists());
+
+        let server = MockServer::start();
+        let mock = server.mock(|when, then| {
+            when.method(PUT).path("/mods/pkg_mod-0.2.0.tar.gz");
+            then.status(200);
+        });
+        package
+            .upload(&AddrType::from(HttpAddr::from(server.url("/mods/"))), "PUT")
+            .await?;
+        mock.assert();
+        Ok(())
+    }
+}
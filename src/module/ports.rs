@@ -0,0 +1,107 @@
+use crate::predule::*;
+use std::fmt::Display;
+
+use derive_more::Deref;
+
+use crate::vars::{ValueDict, ValueType};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortProto {
+    #[default]
+    Tcp,
+    Udp,
+}
+impl Display for PortProto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PortProto::Tcp => write!(f, "tcp"),
+            PortProto::Udp => write!(f, "udp"),
+        }
+    }
+}
+
+// 模块监听的端口, 值注入为 PORT_<NAME>
+#[derive(Getters, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PortSpec {
+    name: String,
+    port: u16,
+    #[serde(default)]
+    protocol: PortProto,
+}
+impl PortSpec {
+    pub fn new<S: Into<String>>(name: S, port: u16) -> Self {
+        Self {
+            name: name.into(),
+            port,
+            protocol: PortProto::Tcp,
+        }
+    }
+    pub fn with_protocol(mut self, protocol: PortProto) -> Self {
+        self.protocol = protocol;
+        self
+    }
+    pub fn var_name(&self) -> String {
+        port_var_name(&self.name)
+    }
+}
+
+pub fn port_var_name(name: &str) -> String {
+    format!("PORT_{}", name.to_uppercase().replace('-', "_"))
+}
+
+// spec/ports.yml
+#[derive(Getters, Clone, Debug, Default, PartialEq, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct ModPorts {
+    ports: Vec<PortSpec>,
+}
+impl ModPorts {
+    pub fn new(ports: Vec<PortSpec>) -> Self {
+        Self { ports }
+    }
+    pub fn find(&self, name: &str) -> Option<&PortSpec> {
+        self.ports.iter().find(|x| x.name() == name)
+    }
+    // 同名端口只保留第一个
+    pub fn merge(mut self, other: &ModPorts) -> Self {
+        for port in other.iter() {
+            if self.find(port.name()).is_none() {
+                self.ports.push(port.clone());
+            }
+        }
+        self
+    }
+    // 未经系统分配时使用声明的默认端口
+    pub fn value_dict(&self) -> ValueDict {
+        let mut dict = ValueDict::new();
+        for port in &self.ports {
+            dict.insert(port.var_name(), ValueType::Int(*port.port() as u64));
+        }
+        dict
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mod_ports() {
+        let ports: ModPorts = serde_yaml::from_str(
+            "- { name: http, port: 8080 }\n- { name: dns-udp, port: 53, protocol: udp }\n",
+        )
+        .unwrap();
+        assert_eq!(ports[1].protocol(), &PortProto::Udp);
+        assert_eq!(ports[1].var_name(), "PORT_DNS_UDP");
+        let dict = ports.value_dict();
+        assert_eq!(dict.get("PORT_HTTP"), Some(&ValueType::Int(8080)));
+
+        let merged = ports.merge(&ModPorts::new(vec![
+            PortSpec::new("http", 9090),
+            PortSpec::new("admin", 9000),
+        ]));
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.find("http").map(|x| *x.port()), Some(8080));
+    }
+}
@@ -1,7 +1,10 @@
-use crate::const_vars::{VALUE_DIR, VALUE_FILE};
+use crate::const_vars::{USER_VALUE_FILE, VALUE_DIR, VALUE_FILE, VARS_SCHEMA_JSON};
 use crate::predule::*;
 use crate::tools::ensure_path;
-use crate::vars::EnvDict;
+use crate::vars::{
+    GLOBAL_SOURCE, ImportMode, RuntimeContext, ValueExplainer, ValueIssue, VarExplain,
+    profile_source, profile_value_file,
+};
 
 use super::init::{MOD_PRJ_ADM_GXL, MOD_PRJ_WORK_GXL, mod_init_gitignore};
 use crate::types::{LocalizeOptions, ValueConfable};
@@ -9,7 +12,10 @@ use crate::{
     addr::{AddrType, GitAddr, types::EnvVarPath},
     const_vars::MODULES_SPC_ROOT,
     module::{
+        ModelSTD,
         depend::{Dependency, DependencySet},
+        lint::LintIssue,
+        model::ModModelSpec,
         spec::ModuleSpec,
     },
     tools::make_clean_path,
@@ -94,28 +100,163 @@ impl ModProject {
         flag.flag_suc();
         Ok(())
     }
-    pub fn load_global_value(&self, value: &Option<String>) -> SpecResult<ValueDict> {
-        load_project_global_value(self.root_local(), value)
+    pub fn load_global_value(
+        &self,
+        values: &[String],
+        layers: &[String],
+        mode: ImportMode,
+        runtime: &RuntimeContext,
+    ) -> SpecResult<ValueDict> {
+        load_project_global_value(self.root_local(), values, layers, mode, runtime)
+    }
+    pub fn explain(
+        &self,
+        key: &str,
+        values: &[String],
+        layers: &[String],
+        mode: ImportMode,
+        options: &LocalizeOptions,
+    ) -> SpecResult<Vec<(ModelSTD, VarExplain)>> {
+        let value_files =
+            project_value_files(self.root_local(), values, layers, options.runtime())?;
+        let global = global_value_explainer(
+            &self.root_local().join(VALUE_DIR),
+            &value_files,
+            mode,
+            options,
+        );
+        let mut result = Vec::new();
+        for target in self.sorted_targets() {
+            let explain = target.explain(key, None, global.clone(), options)?;
+            result.push((target.model().clone(), explain));
+        }
+        Ok(result)
+    }
+    // 每个 target 生成 <target>/vars.schema.json
+    pub fn export_schema(&self) -> SpecResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for target in self.sorted_targets() {
+            if let Some(local) = target.local() {
+                let schema_file = local.join(VARS_SCHEMA_JSON);
+                let data = serde_json::to_string_pretty(&target.value_schema()?).owe_data()?;
+                std::fs::write(&schema_file, data)
+                    .owe_res()
+                    .with(&schema_file)?;
+                files.push(schema_file);
+            }
+        }
+        Ok(files)
+    }
+    // 未指定 value 文件时校验各 target 下的 values/value.yml
+    pub fn validate_values(
+        &self,
+        value: &Option<String>,
+        runtime: &RuntimeContext,
+    ) -> SpecResult<Vec<ValueIssue>> {
+        let mut issues = Vec::new();
+        for target in self.sorted_targets() {
+            let value_file = match (value, target.local()) {
+                (Some(v_file), _) => runtime.resolve(v_file),
+                (None, Some(local)) => local.join(VALUE_DIR).join(USER_VALUE_FILE),
+                (None, None) => continue,
+            };
+            if value_file.exists() {
+                issues.extend(target.validate_value_file(&value_file)?);
+            }
+        }
+        Ok(issues)
+    }
+    pub fn lint(
+        &self,
+        values: &[String],
+        layers: &[String],
+        mode: ImportMode,
+        runtime: &RuntimeContext,
+    ) -> SpecResult<Vec<(ModelSTD, Vec<LintIssue>)>> {
+        let global = self.load_global_value(values, layers, mode, runtime)?;
+        let mut result = Vec::new();
+        for target in self.sorted_targets() {
+            result.push((target.model().clone(), target.lint(&global)?));
+        }
+        Ok(result)
+    }
+    fn sorted_targets(&self) -> Vec<&ModModelSpec> {
+        let mut targets: Vec<_> = self.mod_spec().targets().values().collect();
+        targets.sort_by_key(|x| x.model().to_string());
+        targets
     }
 }
 
-pub fn load_project_global_value(root: &Path, options: &Option<String>) -> SpecResult<ValueDict> {
-    let value_root = ensure_path(root.join(VALUE_DIR))?;
-    let value_file = if let Some(v_file) = options {
-        PathBuf::from(v_file)
-    } else {
-        let v_file = value_root.join(VALUE_FILE);
-        if !v_file.exists() {
-            let mut dict = ValueDict::new();
-            dict.insert("SAMPLE_KEY", ValueType::from("SAMPLE_VAL"));
-            dict.save_valconf(&v_file)?;
-        }
-        v_file
-    };
-    let dict = ValueDict::eval_from_file(&EnvDict::default(), &value_file)?;
+// 全局 value: --value 替换 values/value.yml, --value-layer 按顺序叠加其上, 靠后的优先
+pub fn load_project_global_value(
+    root: &Path,
+    values: &[String],
+    layers: &[String],
+    mode: ImportMode,
+    runtime: &RuntimeContext,
+) -> SpecResult<ValueDict> {
+    let mut dict = ValueDict::new();
+    for value_file in project_value_files(root, values, layers, runtime)? {
+        let mut layer = ValueDict::eval_import_file(&runtime.eval_dict(&dict), &value_file, mode)?;
+        layer.merge(&dict);
+        dict = layer;
+    }
     Ok(dict)
 }
 
+// 按优先级从低到高; 只读, 缺少 value.yml 时跳过
+// 指定 --value 时不再读取 values/value.yml, --value-layer 总是叠加在最上层
+pub fn project_value_files(
+    root: &Path,
+    values: &[String],
+    layers: &[String],
+    runtime: &RuntimeContext,
+) -> SpecResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if values.is_empty() {
+        let v_file = root.join(VALUE_DIR).join(VALUE_FILE);
+        if v_file.exists() {
+            files.push(v_file);
+        }
+    } else {
+        files.extend(values.iter().map(|x| runtime.resolve(x)));
+    }
+    files.extend(layers.iter().map(|x| runtime.resolve(x)));
+    Ok(files)
+}
+
+// localize 时生成示例 value.yml
+pub fn ensure_sample_value(root: &Path) -> SpecResult<PathBuf> {
+    let value_root = ensure_path(root.join(VALUE_DIR))?;
+    let v_file = value_root.join(VALUE_FILE);
+    if !v_file.exists() {
+        let mut dict = ValueDict::new();
+        dict.insert("SAMPLE_KEY", ValueType::from("SAMPLE_VAL"));
+        dict.save_valconf(&v_file)?;
+    }
+    Ok(v_file)
+}
+
+// 全局层: global-profile > --value-layer(靠后优先) > --value 或 value.yml
+pub fn global_value_explainer(
+    value_root: &Path,
+    value_files: &[PathBuf],
+    mode: ImportMode,
+    options: &LocalizeOptions,
+) -> ValueExplainer {
+    let mut explainer = ValueExplainer::new().with_runtime(options.runtime().clone());
+    if let Some(profile) = options.profile() {
+        explainer = explainer.with_value_file(
+            profile_source(GLOBAL_SOURCE, profile),
+            profile_value_file(value_root, profile),
+        );
+    }
+    for value_file in value_files.iter().rev() {
+        explainer = explainer.with_import_file(GLOBAL_SOURCE, value_file.clone(), mode);
+    }
+    explainer
+}
+
 impl ModConf {
     pub async fn update(&self, options: &UpdateOptions) -> SpecResult<()> {
         self.test_envs.update(options).await
@@ -151,6 +292,8 @@ impl Localizable for ModProject {
         options: LocalizeOptions,
     ) -> SpecResult<()> {
         //let local_path = LocalizePath::from_root(self.root_local());
+        let options =
+            options.with_global_profile(&self.root_local().join(VALUE_DIR), self.root_local())?;
         self.conf
             .localize(dst_path.clone(), options.clone())
             .await?;
@@ -234,4 +377,60 @@ pub mod tests {
             .assert("spec.localize");
         Ok(())
     }
+
+    #[test]
+    fn test_load_layered_value() -> SpecResult<()> {
+        use crate::{
+            module::proj::load_project_global_value,
+            vars::{ImportMode, RuntimeContext, ValueType},
+        };
+        use std::fs::write;
+        let dir = tempfile::tempdir().assert("tempdir");
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("values")).assert("values");
+        write(root.join("values/value.yml"), "HOST: 127.0.0.1\nPORT: 80\n").assert("write");
+        write(
+            root.join("db.toml"),
+            "PORT = 5432\n[db]\nuser = \"admin\"\n",
+        )
+        .assert("write");
+        write(root.join(".env"), "PORT=6432\nDB_URL=${HOST}:${PORT}\n").assert("write");
+        let runtime = RuntimeContext::from_root(root);
+        let layers = vec!["db.toml".to_string(), ".env".to_string()];
+
+        // --value-layer 叠加在 value.yml 之上
+        let dict = load_project_global_value(root, &[], &layers, ImportMode::Flatten, &runtime)?;
+        assert_eq!(dict.get("HOST"), Some(&ValueType::from("127.0.0.1")));
+        assert_eq!(dict.get("PORT"), Some(&ValueType::from("6432")));
+        assert_eq!(dict.get("db.user"), Some(&ValueType::from("admin")));
+        assert_eq!(dict.get("DB_URL"), Some(&ValueType::from("127.0.0.1:6432")));
+
+        let dict =
+            load_project_global_value(root, &[], &layers[..1], ImportMode::Structured, &runtime)?;
+        assert_eq!(dict.get("PORT"), Some(&ValueType::Int(5432)));
+        assert!(dict.get("db.user").is_none());
+
+        // --value 替换 value.yml
+        let dict =
+            load_project_global_value(root, &layers[..1], &[], ImportMode::Structured, &runtime)?;
+        assert_eq!(dict.get("PORT"), Some(&ValueType::Int(5432)));
+        assert!(dict.get("HOST").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_no_sample_value() -> SpecResult<()> {
+        use crate::vars::{ImportMode, ValueDict};
+        let dir = tempfile::tempdir().assert("tempdir");
+        let prj_path = dir.path().join("explain_mod");
+        std::fs::create_dir_all(&prj_path).assert("prj");
+        ModProject::make_new(&prj_path, "explain_mod")?.save()?;
+        let proj = ModProject::load(&prj_path)?;
+        let options = LocalizeOptions::new(ValueDict::default(), false);
+        proj.explain("NOT_DEFINED", &[], &[], ImportMode::default(), &options)?;
+        proj.lint(&[], &[], ImportMode::default(), options.runtime())?;
+        // explain / lint 只读, 不生成示例 value.yml
+        assert!(!prj_path.join("values").join("value.yml").exists());
+        Ok(())
+    }
 }
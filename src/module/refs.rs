@@ -1,26 +1,46 @@
 use crate::predule::*;
 
 use async_trait::async_trait;
+use fs_extra::dir::CopyOptions;
 
-use super::ModelSTD;
+use super::{
+    ModelSTD,
+    require::{ModRequires, parse_version},
+    target::{TargetChoice, TargetModel, detect_host_model},
+};
 use crate::types::LocalizeOptions;
 use crate::{
-    addr::AddrType,
-    const_vars::MOD_DIR,
-    error::SpecResult,
-    module::model::ModModelSpec,
-    tools::make_clean_path,
-    types::{AsyncUpdateable, Localizable, Persistable, ValuePath},
+    addr::version::{
+        OutdatedItem, ResolvedVersion, latest_tag, parse_version_req, select_tag, tag_version,
+    },
+    addr::{AddrType, LocalAddr},
+    const_vars::{INLINE_MODS_DIR, MOD_DIR, MOD_REQUIRES_YML, RESOLVED_YML, VERSION_TXT},
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::{model::ModModelSpec, overlay::ModExtends, spec::ModuleSpec},
+    tools::{get_sub_dirs, make_clean_path},
+    types::{AsyncUpdateable, Configable, Localizable, Persistable, ValuePath},
+    vars::{EnvEvalable, RuntimeContext},
 };
+use semver::Version;
 
 #[derive(Getters, Clone, Debug, Serialize, Deserialize)]
 pub struct ModuleSpecRef {
     name: String,
     addr: AddrType,
-    #[serde(alias = "node")]
-    model: ModelSTD,
+    // 指定 target 或 auto; 与 models 都未配置时为 auto
+    #[serde(alias = "node", default, skip_serializing_if = "Option::is_none")]
+    model: Option<TargetModel>,
+    // 同时保留多个 target, 各自 localize 到 <mod>/<model>/
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    models: Vec<TargetModel>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     enable: Option<bool>,
+    // 系统侧补充的依赖, 与模块自带的 requires.yml 合并
+    #[serde(flatten)]
+    requires: ModRequires,
+    // 部署位置: 资源节点名或 k8s namespace, 同一位置的端口不能冲突
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    place: Option<String>,
     #[serde(skip)]
     local: Option<PathBuf>,
 }
@@ -29,16 +49,138 @@ impl ModuleSpecRef {
     pub fn from<S: Into<String>, A: Into<AddrType>>(
         name: S,
         addr: A,
-        node: ModelSTD,
+        node: impl Into<TargetModel>,
     ) -> ModuleSpecRef {
         Self {
             name: name.into(),
             addr: addr.into(),
-            model: node,
+            model: Some(node.into()),
+            models: Vec::new(),
             enable: None,
+            requires: ModRequires::default(),
+            place: None,
             local: None,
         }
     }
+    // 内联模块: 源文件保存在 inline_mods/<name>, update 时复制到 mods/<name>
+    pub fn inline(spec: &ModuleSpec) -> Self {
+        let mut targets: Vec<ModelSTD> = spec.targets().keys().cloned().collect();
+        targets.sort_by_key(|x| x.to_string());
+        let model = match targets.as_slice() {
+            [one] => TargetModel::from(one.clone()),
+            _ => TargetModel::Auto,
+        };
+        Self::from(
+            spec.name(),
+            LocalAddr::from(inline_path(spec.name())),
+            model,
+        )
+    }
+    pub fn is_inline(&self) -> bool {
+        matches!(&self.addr, AddrType::Local(x) if *x.path() == inline_path(&self.name))
+    }
+    pub fn inline_spec(&self) -> SpecResult<Option<ModuleSpec>> {
+        match self.inline_src_path() {
+            Some(path) if self.is_inline() && path.exists() => {
+                Ok(Some(ModuleSpec::load_src(self.name.clone(), &path)?))
+            }
+            _ => Ok(None),
+        }
+    }
+    pub fn with_models(mut self, models: Vec<TargetModel>) -> Self {
+        self.model = None;
+        self.models = models;
+        self
+    }
+    pub fn target_models(&self) -> Vec<TargetModel> {
+        let mut result: Vec<TargetModel> = Vec::new();
+        for model in self.model.iter().chain(self.models.iter()) {
+            if !result.contains(model) {
+                result.push(model.clone());
+            }
+        }
+        if result.is_empty() {
+            result.push(TargetModel::Auto);
+        }
+        result
+    }
+    pub fn is_multi_target(&self) -> bool {
+        self.target_models().len() > 1
+    }
+    pub fn with_place<S: Into<String>>(mut self, place: S) -> Self {
+        self.place = Some(place.into());
+        self
+    }
+    pub fn with_requires(mut self, requires: ModRequires) -> Self {
+        self.requires = requires;
+        self
+    }
+    // update 后本地模块目录: <sys>/mods/<name>
+    fn local_mod_path(&self) -> Option<PathBuf> {
+        self.local.as_ref().map(|x| x.join(self.name()))
+    }
+    // 内联模块源文件目录: <sys>/inline_mods/<name>, 只读
+    fn inline_src_path(&self) -> Option<PathBuf> {
+        self.local
+            .as_ref()
+            .and_then(|x| x.parent())
+            .map(|x| x.join(INLINE_MODS_DIR).join(self.name()))
+    }
+    pub fn mod_requires(&self) -> SpecResult<ModRequires> {
+        let mut requires = self.requires.clone();
+        if let Some(path) = self.local_mod_path().map(|x| x.join(MOD_REQUIRES_YML))
+            && path.exists()
+        {
+            requires = requires.merge(&ModRequires::from_conf(&path)?);
+        }
+        Ok(requires)
+    }
+    // 模块 version.txt 中的版本, update 时从模块工程复制; 缺失时取解析出的 tag
+    pub fn local_version(&self) -> SpecResult<Option<Version>> {
+        let Some(path) = self.local_mod_path().map(|x| x.join(VERSION_TXT)) else {
+            return Ok(None);
+        };
+        if !path.exists() {
+            return Ok(self.resolved_version()?.and_then(|x| tag_version(x.tag())));
+        }
+        let text = std::fs::read_to_string(&path).owe_res().with(&path)?;
+        Ok(Some(parse_version(self.name(), &text)?))
+    }
+    pub fn resolved_version(&self) -> SpecResult<Option<ResolvedVersion>> {
+        match self.local_mod_path().map(|x| x.join(RESOLVED_YML)) {
+            Some(path) if path.exists() => Ok(Some(ResolvedVersion::from_conf(&path)?)),
+            _ => Ok(None),
+        }
+    }
+    // 对比远程 tag, 只处理以 tag 或 version 引用的 git 模块
+    pub fn outdated(&self, options: &UpdateOptions) -> SpecResult<Option<OutdatedItem>> {
+        let AddrType::Git(addr) = self.addr.clone().env_eval(&options.env_dict()) else {
+            return Ok(None);
+        };
+        if addr.version_req().is_none() && addr.tag_name().is_none() {
+            return Ok(None);
+        }
+        let current = match self.resolved_version()? {
+            Some(resolved) => tag_version(resolved.tag()),
+            None => addr.tag_name().and_then(|x| tag_version(x)),
+        };
+        let tags = addr.remote_tags()?;
+        // 未写 version 时按当前版本的兼容范围
+        let req = match (addr.version_req(), &current) {
+            (Some(req), _) => Some(parse_version_req(req)?),
+            (None, Some(cur)) => Some(parse_version_req(&format!("^{}", cur))?),
+            (None, None) => None,
+        };
+        let wanted = req.and_then(|x| select_tag(&x, &tags).map(|x| x.1));
+        let with_pre = current.as_ref().is_some_and(|x| !x.pre.is_empty());
+        let latest = latest_tag(&tags, with_pre).map(|x| x.1);
+        Ok(Some(OutdatedItem::new(
+            self.name(),
+            current,
+            wanted,
+            latest,
+        )))
+    }
     pub fn with_enable(mut self, effective: bool) -> Self {
         self.enable = Some(effective);
         self
@@ -53,56 +195,230 @@ impl ModuleSpecRef {
     pub fn set_local(&mut self, local: PathBuf) {
         self.local = Some(local);
     }
-    pub fn get_target_spec(&self) -> SpecResult<Option<ModModelSpec>> {
-        if self.is_enable() {
-            if let Some(local) = &self.local {
-                let target_root = local.join(self.name());
-                let target_path = target_root.join(self.model().to_string());
-                if target_path.exists() {
-                    let spec = ModModelSpec::load_from(&target_path).with(&target_root)?;
-                    return Ok(Some(spec));
+    // 确定使用的 target: auto 先探测主机;
+    // 模块中没有对应 target 时按 TargetRegistry 的规则回退
+    pub fn resolve_models(
+        &self,
+        target_root: &Path,
+        runtime: &RuntimeContext,
+    ) -> SpecResult<Vec<TargetChoice>> {
+        let available: Vec<ModelSTD> = if target_root.exists() {
+            get_sub_dirs(target_root)?
+                .iter()
+                .filter_map(|x| x.file_name())
+                .filter_map(|x| ModelSTD::parse_with(runtime.targets(), &x.to_string_lossy()).ok())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut choices: Vec<TargetChoice> = Vec::new();
+        for model in self.target_models() {
+            let choice = self.resolve_model(&model, &available, runtime)?;
+            // 多个 model 回退到同一 target 时只保留一份
+            if !choices.iter().any(|x| x.chosen() == choice.chosen()) {
+                choices.push(choice);
+            }
+        }
+        Ok(choices)
+    }
+    fn resolve_model(
+        &self,
+        model: &TargetModel,
+        available: &[ModelSTD],
+        runtime: &RuntimeContext,
+    ) -> SpecResult<TargetChoice> {
+        let choice = |chosen: &ModelSTD, reason: String| {
+            TargetChoice::new(&self.name, model.clone(), chosen.clone(), reason)
+        };
+        let (want, why) = match model {
+            TargetModel::Fixed(model) => (model.clone(), "fixed".to_string()),
+            TargetModel::Auto => match detect_host_model(runtime) {
+                Ok(found) => found,
+                Err(e) if available.len() == 1 => {
+                    return Ok(choice(
+                        &available[0],
+                        format!("host detect failed: {}, use the only target", e),
+                    ));
+                }
+                Err(e) => {
+                    return SpecReason::from(ElementReason::Miss(format!(
+                        "mod {} host target: {}",
+                        self.name, e
+                    )))
+                    .err_result();
+                }
+            },
+        };
+        if available.is_empty() || available.contains(&want) {
+            return Ok(choice(&want, why));
+        }
+        if let Some(found) = runtime.targets().select(&want, available) {
+            info!(target: "mod/ref", "mod {} : {} fallback to {}", self.name, want, found);
+            return Ok(choice(found, format!("{}, fallback from {}", why, want)));
+        }
+        // 仅剩的 target 须与主机同架构/同系统系列/同运行方式, 否则视为缺失, 由 update 重新获取
+        if *model == TargetModel::Auto
+            && let [only] = available
+            && only.arch() == want.arch()
+            && only.os().family() == want.os().family()
+            && only.spc() == want.spc()
+        {
+            return Ok(choice(
+                only,
+                format!("{}, {} not provided, use the only target", why, want),
+            ));
+        }
+        Ok(choice(&want, format!("{}, not provided by module", why)))
+    }
+    // 选用的各 target, 需先 update 到本地
+    pub fn target_specs(&self, runtime: &RuntimeContext) -> SpecResult<Vec<ModModelSpec>> {
+        let mut specs = Vec::new();
+        if self.is_enable()
+            && let Some(local) = &self.local
+        {
+            let target_root = local.join(self.name());
+            for choice in self.resolve_models(&target_root, runtime)? {
+                let target_path = target_root.join(choice.chosen().to_string());
+                if !target_path.exists() {
+                    return SpecReason::from(ElementReason::Miss(format!(
+                        "mod {} target {}, run update first",
+                        self.name,
+                        choice.chosen()
+                    )))
+                    .err_result();
                 }
+                specs.push(ModModelSpec::load_from(&target_path).with(&target_root)?);
+            }
+        }
+        Ok(specs)
+    }
+    // 第一个选用的 target; auto 时按 runtime 中的 --model 或主机探测结果
+    pub fn get_target_spec(&self, runtime: &RuntimeContext) -> SpecResult<Option<ModModelSpec>> {
+        if self.is_enable()
+            && let Some(local) = &self.local
+        {
+            let target_root = local.join(self.name());
+            let choices = self.resolve_models(&target_root, runtime)?;
+            let Some(choice) = choices.first() else {
+                return Ok(None);
+            };
+            let target_path = target_root.join(choice.chosen().to_string());
+            if target_path.exists() {
+                let spec = ModModelSpec::load_from(&target_path).with(&target_root)?;
+                return Ok(Some(spec));
             }
         }
         Ok(None)
     }
 }
+fn inline_path(name: &str) -> String {
+    format!("./{}/{}", INLINE_MODS_DIR, name)
+}
+
 impl ModuleSpecRef {
-    pub async fn update(&self, _sys_root: &Path, options: &UpdateOptions) -> SpecResult<()> {
+    pub async fn update(
+        &self,
+        _sys_root: &Path,
+        options: &UpdateOptions,
+    ) -> SpecResult<Vec<TargetChoice>> {
         //trace!(target: "spec/mod/",  "{:?}",self );
-        if self.is_enable() {
-            if let Some(local) = &self.local {
-                let mut flag = log_guard!(
-                    info!(target: "/mod/ref",  "update mod ref {} success!", self.name ),
-                    error!(target: "/mod/ref", "update mod ref {} fail!", self.name )
-                );
-                std::fs::create_dir_all(local).owe_res().with(local)?;
-                let target_root = local.join(self.name());
-                let target_path = target_root.join(self.model().to_string());
-                if !target_path.exists() || options.clean_exist_ref_mod() {
-                    let tmp_name = "__mod";
-                    let prj_path = self.addr.update_rename(local, tmp_name, options).await?;
-                    let mod_path = prj_path.join(MOD_DIR);
-                    let tmp_path = local.join(tmp_name);
-                    make_clean_path(&target_root)?;
-
-                    std::fs::rename(&mod_path, &target_root)
-                        .owe_logic()
-                        .with(("from", &mod_path))
-                        .with(("to", &target_root))?;
-                    if tmp_path.exists() {
-                        std::fs::remove_dir_all(tmp_path).owe_sys()?;
-                    }
+        if self.is_enable()
+            && let Some(local) = &self.local
+        {
+            let mut flag = log_guard!(
+                info!(target: "/mod/ref",  "update mod ref {} success!", self.name ),
+                error!(target: "/mod/ref", "update mod ref {} fail!", self.name )
+            );
+            std::fs::create_dir_all(local).owe_res().with(local)?;
+            let target_root = local.join(self.name());
+            if self.is_inline() {
+                self.copy_inline(&target_root)?;
+            }
+            let choices = self.resolve_models(&target_root, options.runtime())?;
+            let missing = choices
+                .iter()
+                .any(|x| !target_root.join(x.chosen().to_string()).exists());
+            if self.is_inline() && missing {
+                return SpecReason::from(ElementReason::Miss(format!(
+                    "inline mod {} target in {}",
+                    self.name,
+                    target_root.display()
+                )))
+                .err_result();
+            }
+            if !self.is_inline() && (missing || options.clean_exist_ref_mod()) {
+                let tmp_name = "__mod";
+                let (addr, resolved) = self
+                    .addr
+                    .clone()
+                    .env_eval(&options.env_dict())
+                    .resolve_version()?;
+                let prj_path = addr.update_rename(local, tmp_name, options).await?;
+                let mod_path = prj_path.join(MOD_DIR);
+                // 本地相对 extends 基于原工程目录, 需在移动前拉取
+                ModExtends::fetch_all(&mod_path, options).await?;
+                let tmp_path = local.join(tmp_name);
+                make_clean_path(&target_root)?;
+
+                std::fs::rename(&mod_path, &target_root)
+                    .owe_logic()
+                    .with(("from", &mod_path))
+                    .with(("to", &target_root))?;
+                let version_file = prj_path.join(VERSION_TXT);
+                if version_file.exists() {
+                    std::fs::copy(&version_file, target_root.join(VERSION_TXT))
+                        .owe_res()
+                        .with(&version_file)?;
+                }
+                if let Some(resolved) = resolved {
+                    resolved.save_conf(&target_root.join(RESOLVED_YML))?;
                 }
+                if tmp_path.exists() {
+                    std::fs::remove_dir_all(tmp_path).owe_sys()?;
+                }
+            }
 
-                debug!(target: "mod/ref",  "update target success!" );
-                //let target_path = target_root.join(self.node().to_string());
+            debug!(target: "mod/ref",  "update target success!" );
+            let choices = self.resolve_models(&target_root, options.runtime())?;
+            for choice in &choices {
+                let target_path = target_root.join(choice.chosen().to_string());
+                if let Some(src) = self.inline_src_path().filter(|_| self.is_inline()) {
+                    let origin = src.join(choice.chosen().to_string());
+                    ModExtends::fetch_from(&target_path, &origin, options).await?;
+                }
+                ModExtends::materialize(&target_path)?;
                 let spec = ModModelSpec::load_from(&target_path).with(&target_root)?;
                 let _x = spec.update_local(&target_path, options).await?;
-                ModModelSpec::clean_other(&target_root, self.model())?;
-                flag.flag_suc();
             }
+            let keep: Vec<ModelSTD> = choices.iter().map(|x| x.chosen().clone()).collect();
+            ModModelSpec::clean_others(&target_root, &keep)?;
+            flag.flag_suc();
+            return Ok(choices);
         }
+        Ok(Vec::new())
+    }
+    // 每次 update 用源文件覆盖 mods/<name>, localize 的输出不写回源目录
+    fn copy_inline(&self, target_root: &Path) -> SpecResult<()> {
+        let src = self.inline_src_path().ok_or(
+            SpecReason::from(ElementReason::Miss(format!(
+                "inline mod {} local",
+                self.name
+            )))
+            .to_err(),
+        )?;
+        if !src.exists() {
+            return SpecReason::from(ElementReason::Miss(format!(
+                "inline mod {} source in {}",
+                self.name,
+                src.display()
+            )))
+            .err_result();
+        }
+        make_clean_path(target_root)?;
+        fs_extra::dir::copy(&src, target_root, &CopyOptions::new().content_only(true))
+            .owe_res()
+            .with(("from", &src))
+            .with(("to", target_root))?;
         Ok(())
     }
 
@@ -126,15 +442,22 @@ impl Localizable for ModuleSpecRef {
                     error!(target: "spec/mod/", "localize mod {} fail!", self.name )
                 );
                 let mod_path = local.join(self.name.as_str());
-                let target_path = mod_path.join(self.model().to_string());
-                let spec = ModModelSpec::load_from(&target_path)?;
-                //if let Some(dst) = &dst_path {
-                //    spec.save_main(dst.local(), None)?;
-                //}
-                let value = PathBuf::from(self.name());
-                //let local = PathBuf::from(self.name()).join("local");
-                let cur_dst_path = dst_path.map(|x| x.join(value));
-                spec.localize(cur_dst_path, options).await?;
+                let multi = self.is_multi_target();
+                for choice in self.resolve_models(&mod_path, options.runtime())? {
+                    let target_path = mod_path.join(choice.chosen().to_string());
+                    let spec = ModModelSpec::load_from(&target_path)?;
+                    let value = PathBuf::from(self.name());
+                    // 多 target 时 value 按 model 分目录
+                    let cur_dst_path = dst_path.as_ref().map(|x| {
+                        let dst = x.join(&value);
+                        if multi {
+                            dst.join(choice.chosen().to_string())
+                        } else {
+                            dst
+                        }
+                    });
+                    spec.localize(cur_dst_path, options.clone()).await?;
+                }
                 flag.flag_suc();
             }
             Ok(())
@@ -143,3 +466,108 @@ impl Localizable for ModuleSpecRef {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use crate::{addr::LocalAddr, module::target::TargetRegistry};
+
+    use super::*;
+
+    // 测试只用内置登记表, 不读取 home 下的配置
+    fn model(value: &str) -> ModelSTD {
+        ModelSTD::parse_with(&TargetRegistry::builtin(), value).assert()
+    }
+
+    #[test]
+    fn test_resolve_model() {
+        let dir = tempfile::tempdir().assert();
+        let root = dir.path().join("redis");
+        for target in ["x86-ubt22-host", "x86-ubt22-k8s"] {
+            std::fs::create_dir_all(root.join(target)).assert();
+        }
+        let addr = LocalAddr::from("./redis");
+        let runtime = RuntimeContext::default();
+
+        let fixed = ModuleSpecRef::from("redis", addr.clone(), ModelSTD::x86_ubt22_k8s());
+        let choice = fixed.resolve_models(&root, &runtime).assert().remove(0);
+        assert_eq!(choice.chosen(), &ModelSTD::x86_ubt22_k8s());
+        assert_eq!(choice.reason(), "fixed");
+
+        let fallback = ModuleSpecRef::from("redis", addr.clone(), model("x86-ubt24-host"));
+        let choice = fallback.resolve_models(&root, &runtime).assert().remove(0);
+        assert_eq!(choice.chosen(), &ModelSTD::x86_ubt22_host());
+        assert!(choice.reason().contains("fallback from x86-ubt24-host"));
+
+        let auto = ModuleSpecRef::from("redis", addr.clone(), TargetModel::Auto);
+        let runtime = runtime.with_host_model(Some(model("amd64-noble-k8s")));
+        let choice = auto.resolve_models(&root, &runtime).assert().remove(0);
+        assert_eq!(choice.chosen(), &ModelSTD::x86_ubt22_k8s());
+        assert_eq!(
+            choice.to_string(),
+            "redis: auto -> x86-ubt22-k8s (--model override, fallback from x86-ubt24-k8s)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_model_other_host() {
+        let dir = tempfile::tempdir().assert();
+        let root = dir.path().join("redis");
+        let auto = ModuleSpecRef::from("redis", LocalAddr::from("./redis"), TargetModel::Auto);
+        // 在 mac 上 update 后, 只留下 arm-mac14-host
+        std::fs::create_dir_all(root.join("arm-mac14-host")).assert();
+        let runtime = RuntimeContext::default().with_host_model(Some(ModelSTD::x86_ubt22_k8s()));
+        let choice = auto.resolve_models(&root, &runtime).assert().remove(0);
+        assert_eq!(choice.chosen(), &ModelSTD::x86_ubt22_k8s());
+        assert!(choice.reason().contains("not provided by module"));
+
+        // 同系列更高版本的唯一 target 仍可使用
+        std::fs::remove_dir_all(root.join("arm-mac14-host")).assert();
+        std::fs::create_dir_all(root.join("x86-ubt24-k8s")).assert();
+        let choice = auto.resolve_models(&root, &runtime).assert().remove(0);
+        assert_eq!(choice.chosen(), &model("x86-ubt24-k8s"));
+    }
+
+    #[test]
+    fn test_multi_models() {
+        let models = ["arm-mac14-host", "x86-ubt22-host", "x86-ubt24-host"]
+            .iter()
+            .map(|x| TargetModel::Fixed(model(x)))
+            .collect();
+        let spec_ref = ModuleSpecRef::from("redis", LocalAddr::from("./redis"), TargetModel::Auto)
+            .with_models(models);
+        // 序列化后仍可读回
+        let yml = serde_yaml::to_string(&spec_ref).assert();
+        assert!(!yml.contains("model:"));
+        let spec_ref: ModuleSpecRef = serde_yaml::from_str(&yml).assert();
+        assert!(spec_ref.is_multi_target());
+        let dir = tempfile::tempdir().assert();
+        let root = dir.path().join("redis");
+        for target in ["arm-mac14-host", "x86-ubt22-host", "x86-ubt22-k8s"] {
+            std::fs::create_dir_all(root.join(target)).assert();
+        }
+        let choices = spec_ref
+            .resolve_models(&root, &RuntimeContext::default())
+            .assert();
+        let chosen: Vec<String> = choices.iter().map(|x| x.chosen().to_string()).collect();
+        assert_eq!(chosen, vec!["arm-mac14-host", "x86-ubt22-host"]);
+
+        let keep: Vec<ModelSTD> = choices.iter().map(|x| x.chosen().clone()).collect();
+        ModModelSpec::clean_others(&root, &keep).assert();
+        assert!(root.join("arm-mac14-host").exists());
+        assert!(!root.join("x86-ubt22-k8s").exists());
+
+        let single = ModuleSpecRef::from(
+            "redis",
+            LocalAddr::from("./redis"),
+            ModelSTD::x86_ubt22_host(),
+        );
+        assert!(!single.is_multi_target());
+        assert!(
+            serde_yaml::to_string(&single)
+                .assert()
+                .contains("model: x86-ubt22-host")
+        );
+    }
+}
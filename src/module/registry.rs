@@ -0,0 +1,316 @@
+use crate::predule::*;
+
+use std::fmt::Display;
+
+use home::home_dir;
+use orion_error::UvsResFrom;
+use semver::Version;
+use sha2::{Digest, Sha256};
+
+use super::{ModelSTD, refs::ModuleSpecRef, target::TargetModel};
+use crate::{
+    addr::{
+        AddrType,
+        version::{parse_version_req, tag_version},
+    },
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    tools::make_clean_path,
+    types::{AsyncUpdateable, Yamlable},
+};
+
+// 用户配置的索引地址列表
+pub const REGISTRY_YML: &str = ".galaxy/registry.yml";
+// 索引文件名, 索引地址为目录或 git 仓库时使用
+pub const REGISTRY_INDEX_YML: &str = "index.yml";
+
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct RegistryVersion {
+    version: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    targets: Vec<ModelSTD>,
+    addr: AddrType,
+}
+
+impl RegistryVersion {
+    pub fn new<S: Into<String>, A: Into<AddrType>>(
+        version: S,
+        targets: Vec<ModelSTD>,
+        addr: A,
+    ) -> Self {
+        Self {
+            version: version.into(),
+            targets,
+            addr: addr.into(),
+        }
+    }
+    pub fn semver(&self) -> Option<Version> {
+        tag_version(&self.version)
+    }
+    // 只支持一个 target 时固定, 否则 auto 由 update 按主机选择
+    pub fn to_mod_ref(&self, name: &str) -> ModuleSpecRef {
+        let model = match self.targets.as_slice() {
+            [one] => TargetModel::from(one.clone()),
+            _ => TargetModel::Auto,
+        };
+        ModuleSpecRef::from(name, self.addr.clone(), model)
+    }
+}
+
+#[derive(Getters, Clone, Debug, Serialize, Deserialize)]
+pub struct RegistryModule {
+    name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    desc: String,
+    versions: Vec<RegistryVersion>,
+}
+
+impl RegistryModule {
+    pub fn new<S: Into<String>>(name: S, desc: S, versions: Vec<RegistryVersion>) -> Self {
+        Self {
+            name: name.into(),
+            desc: desc.into(),
+            versions,
+        }
+    }
+    // 最新的正式版本, 没有正式版本时取预发布
+    pub fn latest(&self) -> Option<&RegistryVersion> {
+        let versions = || {
+            self.versions
+                .iter()
+                .filter_map(|x| x.semver().map(|v| (x, v)))
+        };
+        versions()
+            .filter(|(_, v)| v.pre.is_empty())
+            .max_by(|a, b| a.1.cmp(&b.1))
+            .or_else(|| versions().max_by(|a, b| a.1.cmp(&b.1)))
+            .map(|x| x.0)
+    }
+    // 精确版本优先, 其次按 semver 范围取最高版本; 未指定取最新
+    pub fn select(&self, version: Option<&str>) -> SpecResult<&RegistryVersion> {
+        let found = match version {
+            None => self.latest(),
+            Some(text) => match self.versions.iter().find(|x| x.version == text) {
+                Some(exact) => Some(exact),
+                None => {
+                    let req = parse_version_req(text)?;
+                    self.versions
+                        .iter()
+                        .filter_map(|x| x.semver().map(|v| (x, v)))
+                        .filter(|(_, v)| req.matches(v))
+                        .max_by(|a, b| a.1.cmp(&b.1))
+                        .map(|x| x.0)
+                }
+            },
+        };
+        found.ok_or(
+            SpecReason::from(ElementReason::Version(format!(
+                "registry module {}@{}",
+                self.name,
+                version.unwrap_or("latest")
+            )))
+            .to_err(),
+        )
+    }
+}
+
+impl Display for RegistryModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let latest = self.latest().map(|x| x.version.as_str()).unwrap_or("-");
+        write!(f, "{:<20} {:<12} {}", self.name, latest, self.desc)
+    }
+}
+
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    #[serde(default)]
+    modules: Vec<RegistryModule>,
+}
+
+impl RegistryIndex {
+    pub fn new(modules: Vec<RegistryModule>) -> Self {
+        Self { modules }
+    }
+    // 同名模块合并版本, 先加载的索引优先
+    pub fn merge(mut self, other: RegistryIndex) -> Self {
+        for module in other.modules {
+            match self.modules.iter_mut().find(|x| x.name == module.name) {
+                Some(exist) => {
+                    for ver in module.versions {
+                        if !exist.versions.iter().any(|x| x.version == ver.version) {
+                            exist.versions.push(ver);
+                        }
+                    }
+                }
+                None => self.modules.push(module),
+            }
+        }
+        self
+    }
+    pub fn find(&self, name: &str) -> SpecResult<&RegistryModule> {
+        self.modules.iter().find(|x| x.name == name).ok_or(
+            SpecReason::from(ElementReason::Miss(format!("registry module: {}", name))).to_err(),
+        )
+    }
+    // 名称或描述包含关键字, 忽略大小写
+    pub fn search(&self, keyword: &str) -> Vec<&RegistryModule> {
+        let keyword = keyword.to_lowercase();
+        self.modules
+            .iter()
+            .filter(|x| {
+                x.name.to_lowercase().contains(&keyword) || x.desc.to_lowercase().contains(&keyword)
+            })
+            .collect()
+    }
+}
+
+// 索引地址: http 文件, git 仓库或本地路径
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Registry {
+    indexes: Vec<AddrType>,
+}
+
+impl Registry {
+    pub fn new(indexes: Vec<AddrType>) -> Self {
+        Self { indexes }
+    }
+    pub fn user_path() -> Option<PathBuf> {
+        home_dir().map(|x| x.join(REGISTRY_YML))
+    }
+    // 命令行指定优先, 否则读取 ~/.galaxy/registry.yml
+    pub fn load(indexes: Vec<AddrType>) -> SpecResult<Self> {
+        if !indexes.is_empty() {
+            return Ok(Self::new(indexes));
+        }
+        match Self::user_path().filter(|x| x.exists()) {
+            Some(path) => Self::from_yml(&path),
+            None => SpecReason::from(ElementReason::Miss(format!(
+                "registry, use --registry or ~/{}",
+                REGISTRY_YML
+            )))
+            .err_result(),
+        }
+    }
+
+    // 默认缓存目录: ~/.cache/galaxy/registry
+    pub fn cache_root() -> SpecResult<PathBuf> {
+        Ok(home_dir()
+            .ok_or(StructError::from_res("unget home".into()))?
+            .join(".cache/galaxy/registry"))
+    }
+    pub async fn fetch(&self, options: &UpdateOptions) -> SpecResult<RegistryIndex> {
+        self.fetch_in(&Self::cache_root()?, options).await
+    }
+    // 每个索引地址缓存在 <cache>/<地址的 sha256 前缀>, 不同地址互不覆盖
+    pub async fn fetch_in(
+        &self,
+        cache: &Path,
+        options: &UpdateOptions,
+    ) -> SpecResult<RegistryIndex> {
+        let mut index = RegistryIndex::default();
+        for addr in self.indexes.iter() {
+            let local = cache.join(addr_cache_key(addr)?);
+            make_clean_path(&local)?;
+            std::fs::create_dir_all(&local).owe_res().with(&local)?;
+            let path = addr.update_local(&local, options).await?;
+            let path = if path.is_dir() {
+                path.join(REGISTRY_INDEX_YML)
+            } else {
+                path
+            };
+            index = index.merge(RegistryIndex::from_yml(&path)?);
+        }
+        Ok(index)
+    }
+}
+
+pub(crate) fn addr_cache_key(addr: &AddrType) -> SpecResult<String> {
+    let text = serde_json::to_string(addr).owe_data()?;
+    let digest = format!("{:x}", Sha256::digest(text.as_bytes()));
+    Ok(digest[..16].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{addr::LocalAddr, tools::get_sub_dirs, types::Configable};
+
+    fn index() -> RegistryIndex {
+        let git = |tag: &str| {
+            crate::addr::GitAddr::from("https://github.com/galaxy-sec/mysql.git").tag(tag)
+        };
+        RegistryIndex::new(vec![
+            RegistryModule::new(
+                "mysql",
+                "MySQL database server",
+                vec![
+                    RegistryVersion::new("8.0.3", vec![ModelSTD::x86_ubt22_k8s()], git("v8.0.3")),
+                    RegistryVersion::new(
+                        "8.1.0",
+                        vec![ModelSTD::x86_ubt22_k8s(), ModelSTD::x86_ubt22_host()],
+                        git("v8.1.0"),
+                    ),
+                    RegistryVersion::new("9.0.0-rc.1", vec![], git("v9.0.0-rc.1")),
+                ],
+            ),
+            RegistryModule::new("redis", "in-memory cache", vec![]),
+        ])
+    }
+
+    #[test]
+    fn test_registry_index() {
+        let index = index();
+        assert_eq!(index.search("SQL").len(), 1);
+        assert_eq!(index.search("cache")[0].name(), "redis");
+        assert_eq!(index.search("").len(), 2);
+
+        let mysql = index.find("mysql").assert();
+        assert_eq!(mysql.latest().assert().version(), "8.1.0");
+        assert_eq!(mysql.select(Some("8.0.3")).assert().version(), "8.0.3");
+        assert_eq!(mysql.select(Some("^8")).assert().version(), "8.1.0");
+        assert_eq!(
+            mysql.select(Some("9.0.0-rc.1")).assert().version(),
+            "9.0.0-rc.1"
+        );
+        assert!(mysql.select(Some("^7")).is_err());
+        assert!(index.find("nginx").is_err());
+
+        let one = mysql.select(Some("8.0.3")).assert().to_mod_ref("mysql");
+        assert_eq!(
+            one.model().clone(),
+            Some(TargetModel::from(ModelSTD::x86_ubt22_k8s()))
+        );
+        let multi = mysql.select(None).assert().to_mod_ref("mysql");
+        assert_eq!(multi.model().clone(), Some(TargetModel::Auto));
+    }
+
+    #[tokio::test]
+    async fn test_registry_fetch() -> SpecResult<()> {
+        let temp = tempfile::tempdir().owe_res()?;
+        let index_file = temp.path().join(REGISTRY_INDEX_YML);
+        index().save_conf(&index_file)?;
+        let other = RegistryIndex::new(vec![RegistryModule::new("nginx", "web", vec![])]);
+        let other_dir = temp.path().join("other");
+        std::fs::create_dir_all(&other_dir).owe_res()?;
+        other.save_conf(&other_dir.join(REGISTRY_INDEX_YML))?;
+
+        let registry = Registry::load(vec![
+            AddrType::from(LocalAddr::from(index_file.display().to_string())),
+            AddrType::from(LocalAddr::from(other_dir.display().to_string())),
+        ])?;
+        let cache = temp.path().join("cache");
+        let fetched = registry
+            .fetch_in(&cache, &UpdateOptions::for_test())
+            .await?;
+        assert_eq!(fetched.modules().len(), 3);
+        assert_eq!(get_sub_dirs(&cache)?.len(), 2);
+        let key = addr_cache_key(&registry.indexes()[0])?;
+        assert_eq!(key.len(), 16);
+        assert_ne!(key, addr_cache_key(&registry.indexes()[1])?);
+        assert_eq!(fetched.find("mysql")?.versions().len(), 3);
+        assert_eq!(fetched.find("nginx")?.desc(), "web");
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::{fmt::Display, str::FromStr};
+
+use derive_getters::Getters;
+use semver::{Version, VersionReq};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::error::{ElementReason, SpecReason, SpecResult, ToErr};
+
+// `mysql` 或 `mysql@>=8.0, <9`
+#[derive(Getters, Clone, Debug, PartialEq)]
+pub struct ModRequire {
+    name: String,
+    version: Option<VersionReq>,
+}
+
+impl ModRequire {
+    pub fn new<S: Into<String>>(name: S, version: Option<VersionReq>) -> Self {
+        Self {
+            name: name.into(),
+            version,
+        }
+    }
+    pub fn is_match(&self, version: &Version) -> bool {
+        self.version.as_ref().is_none_or(|x| x.matches(version))
+    }
+}
+
+impl Display for ModRequire {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{}", self.name, version),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for ModRequire {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, version) = match s.split_once('@') {
+            Some((name, version)) => {
+                let req = VersionReq::parse(version.trim()).map_err(|e| format!("{}: {}", s, e))?;
+                (name.trim(), Some(req))
+            }
+            None => (s.trim(), None),
+        };
+        if name.is_empty() {
+            return Err(format!("{}: empty module name", s));
+        }
+        Ok(Self::new(name, version))
+    }
+}
+
+impl serde::Serialize for ModRequire {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ModRequire {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// 模块间的顺序约束
+#[derive(Getters, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModRequires {
+    // 必须存在, 且先于本模块
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    requires: Vec<ModRequire>,
+    // 存在时先于本模块
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    after: Vec<String>,
+}
+
+impl ModRequires {
+    pub fn new(requires: Vec<ModRequire>, after: Vec<String>) -> Self {
+        Self { requires, after }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.requires.is_empty() && self.after.is_empty()
+    }
+    pub fn merge(mut self, other: &ModRequires) -> Self {
+        self.requires.extend(other.requires.iter().cloned());
+        self.after.extend(other.after.iter().cloned());
+        self
+    }
+}
+
+pub fn parse_version(name: &str, text: &str) -> SpecResult<Version> {
+    Version::parse(text.trim().trim_start_matches('v')).map_err(|e| {
+        SpecReason::from(ElementReason::Version(format!(
+            "{}: {} ({})",
+            name, text, e
+        )))
+        .to_err()
+    })
+}
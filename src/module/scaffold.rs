@@ -0,0 +1,528 @@
+use crate::predule::*;
+
+use home::home_dir;
+use orion_error::UvsResFrom;
+use walkdir::WalkDir;
+
+use super::{
+    ModelSTD,
+    init::{ModActIniter, ModPrjIniter},
+    model::ModModelSpec,
+    proj::ModProject,
+    setting::Setting,
+    spec::ModuleSpec,
+};
+use crate::{
+    addr::{GitAddr, HttpAddr, path_file_name},
+    artifact::{Artifact, ArtifactPackage},
+    const_vars::{MOD_DIR, SPEC_DIR, VARS_YML, WORKFLOWS_DIR},
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::depend::DependencySet,
+    resource::CaculateResSpec,
+    task::OperationType,
+    tools::get_sub_dirs,
+    types::{AsyncUpdateable, Configable, Persistable},
+    vars::{ValueDict, VarCollection, VarDefinition},
+    workflow::{
+        act::{ModWorkflows, Workflow},
+        gxl::GxlAction,
+        prj::GxlProject,
+    },
+};
+
+// 模板文件名与内容中的模块名占位符
+pub const SCAFFOLD_NAME_HOLDER: &str = "__MOD_NAME__";
+// 用户模板目录下的默认 target 列表
+pub const SCAFFOLD_TARGETS_YML: &str = "targets.yml";
+
+const SCAFFOLD_WORK_GXL: &str = include_str!("init/scaffold/_gal/work.gxl");
+const SYSTEMD_SETUP_GXL: &str = include_str!("init/systemd/workflows/setup.gxl");
+const SYSTEMD_UPDATE_GXL: &str = include_str!("init/systemd/workflows/update.gxl");
+const SYSTEMD_UNINSTALL_GXL: &str = include_str!("init/systemd/workflows/uninstall.gxl");
+const SYSTEMD_SERVICE: &str = include_str!("init/systemd/spec/__MOD_NAME__.service");
+const SYSTEMD_VARS_YML: &str = include_str!("init/systemd/vars.yml");
+const COMPOSE_SETUP_GXL: &str = include_str!("init/compose/workflows/setup.gxl");
+const COMPOSE_UPDATE_GXL: &str = include_str!("init/compose/workflows/update.gxl");
+const COMPOSE_UNINSTALL_GXL: &str = include_str!("init/compose/workflows/uninstall.gxl");
+const COMPOSE_YML: &str = include_str!("init/compose/spec/docker-compose.yml");
+const COMPOSE_VARS_YML: &str = include_str!("init/compose/vars.yml");
+const HELM_SETUP_GXL: &str = include_str!("init/helm/workflows/setup.gxl");
+const HELM_UPDATE_GXL: &str = include_str!("init/helm/workflows/update.gxl");
+const HELM_UNINSTALL_GXL: &str = include_str!("init/helm/workflows/uninstall.gxl");
+const HELM_VALUES_YAML: &str = include_str!("init/helm/spec/values.yaml");
+const HELM_VARS_YML: &str = include_str!("init/helm/vars.yml");
+const STATIC_SETUP_GXL: &str = include_str!("init/static/workflows/setup.gxl");
+const STATIC_UPDATE_GXL: &str = include_str!("init/static/workflows/update.gxl");
+const STATIC_UNINSTALL_GXL: &str = include_str!("init/static/workflows/uninstall.gxl");
+const STATIC_VARS_YML: &str = include_str!("init/static/vars.yml");
+
+// 一种模块骨架: 工作流, gxl 工程, spec 下的模板文件与变量
+#[derive(Getters, Clone, Debug)]
+pub struct Scaffold {
+    kind: String,
+    desc: String,
+    targets: Vec<ModelSTD>,
+    workflows: Vec<GxlAction>,
+    work_gxl: String,
+    // spec/ 下的相对路径与内容
+    files: Vec<(String, String)>,
+    vars: VarCollection,
+    setting: Option<Setting>,
+}
+
+fn gxl(task: OperationType, code: &str) -> GxlAction {
+    GxlAction::new(task.clone(), format!("{}.gxl", task), code.to_string())
+}
+// 内置模板的 vars.yml, 工作流中引用的变量都在其中声明
+fn builtin_vars(text: &str) -> VarCollection {
+    serde_yaml::from_str(text).expect("builtin scaffold vars.yml")
+}
+
+impl Scaffold {
+    pub fn new<S: Into<String>>(kind: S, desc: S, targets: Vec<ModelSTD>) -> Self {
+        Self {
+            kind: kind.into(),
+            desc: desc.into(),
+            targets,
+            workflows: Vec::new(),
+            work_gxl: SCAFFOLD_WORK_GXL.to_string(),
+            files: Vec::new(),
+            vars: VarCollection::default(),
+            setting: None,
+        }
+    }
+    pub fn with_workflows(mut self, workflows: Vec<GxlAction>) -> Self {
+        self.workflows = workflows;
+        self
+    }
+    pub fn with_work_gxl<S: Into<String>>(mut self, work_gxl: S) -> Self {
+        self.work_gxl = work_gxl.into();
+        self
+    }
+    pub fn with_file<S: Into<String>>(mut self, path: S, content: S) -> Self {
+        self.files.push((path.into(), content.into()));
+        self
+    }
+    pub fn with_files(mut self, files: Vec<(String, String)>) -> Self {
+        self.files = files;
+        self
+    }
+    pub fn with_vars(mut self, vars: VarCollection) -> Self {
+        self.vars = vars;
+        self
+    }
+    pub fn with_setting(mut self, setting: Setting) -> Self {
+        self.setting = Some(setting);
+        self
+    }
+
+    fn host() -> Self {
+        Self::new(
+            "host",
+            "run on host by gxl",
+            vec![ModelSTD::x86_ubt22_host(), ModelSTD::arm_mac14_host()],
+        )
+        .with_workflows(vec![
+            GxlAction::host_setup_tpl(),
+            GxlAction::host_update_tpl(),
+        ])
+        .with_work_gxl(GxlProject::spec_host_tpl().work().as_str())
+        .with_vars(VarCollection::define(vec![VarDefinition::from((
+            "EXAMPLE_SIZE",
+            1000,
+        ))]))
+    }
+    fn k8s() -> Self {
+        Self::new(
+            "k8s",
+            "run on kubernetes by gxl",
+            vec![ModelSTD::x86_ubt22_k8s()],
+        )
+        .with_workflows(vec![
+            GxlAction::k8s_setup_tpl(),
+            GxlAction::k8s_update_tpl(),
+        ])
+        .with_work_gxl(GxlProject::spec_k8s_tpl().work().as_str())
+        .with_vars(VarCollection::define(vec![VarDefinition::from((
+            "EXAMPLE_SIZE",
+            1000,
+        ))]))
+    }
+    fn systemd() -> Self {
+        Self::new(
+            "systemd",
+            "systemd service unit",
+            vec![ModelSTD::x86_ubt22_host()],
+        )
+        .with_workflows(vec![
+            gxl(OperationType::Setup, SYSTEMD_SETUP_GXL),
+            gxl(OperationType::Update, SYSTEMD_UPDATE_GXL),
+            gxl(OperationType::UnInstall, SYSTEMD_UNINSTALL_GXL),
+        ])
+        .with_file("__MOD_NAME__.service", SYSTEMD_SERVICE)
+        .with_vars(builtin_vars(SYSTEMD_VARS_YML))
+    }
+    fn compose() -> Self {
+        Self::new(
+            "compose",
+            "docker-compose project",
+            vec![ModelSTD::x86_ubt22_host()],
+        )
+        .with_workflows(vec![
+            gxl(OperationType::Setup, COMPOSE_SETUP_GXL),
+            gxl(OperationType::Update, COMPOSE_UPDATE_GXL),
+            gxl(OperationType::UnInstall, COMPOSE_UNINSTALL_GXL),
+        ])
+        .with_file("docker-compose.yml", COMPOSE_YML)
+        .with_vars(builtin_vars(COMPOSE_VARS_YML))
+    }
+    fn helm() -> Self {
+        Self::new(
+            "helm",
+            "wrapper of a helm chart",
+            vec![ModelSTD::x86_ubt22_k8s()],
+        )
+        .with_workflows(vec![
+            gxl(OperationType::Setup, HELM_SETUP_GXL),
+            gxl(OperationType::Update, HELM_UPDATE_GXL),
+            gxl(OperationType::UnInstall, HELM_UNINSTALL_GXL),
+        ])
+        .with_file("values.yaml", HELM_VALUES_YAML)
+        .with_vars(builtin_vars(HELM_VARS_YML))
+    }
+    fn static_binary() -> Self {
+        Self::new(
+            "static",
+            "single static binary",
+            vec![ModelSTD::x86_ubt22_host(), ModelSTD::arm_mac14_host()],
+        )
+        .with_workflows(vec![
+            gxl(OperationType::Setup, STATIC_SETUP_GXL),
+            gxl(OperationType::Update, STATIC_UPDATE_GXL),
+            gxl(OperationType::UnInstall, STATIC_UNINSTALL_GXL),
+        ])
+        .with_vars(builtin_vars(STATIC_VARS_YML))
+    }
+
+    // 用户模板目录: workflows/*.gxl, 可选 _gal/work.gxl, spec/**, vars.yml, targets.yml
+    pub fn load_dir(root: &Path) -> SpecResult<Self> {
+        let kind = path_file_name(root)?;
+        let mut ctx = WithContext::want("load scaffold");
+        ctx.with_path("root", root);
+        let targets_path = root.join(SCAFFOLD_TARGETS_YML);
+        let targets = if targets_path.exists() {
+            Vec::<ModelSTD>::from_conf(&targets_path).with(&ctx)?
+        } else {
+            vec![ModelSTD::x86_ubt22_host()]
+        };
+        let mut scaffold = Self::new(kind.clone(), format!("user scaffold {}", kind), targets);
+
+        let workflows_path = root.join(WORKFLOWS_DIR);
+        if !workflows_path.exists() {
+            return SpecReason::from(ElementReason::Miss(format!(
+                "scaffold workflows: {}",
+                workflows_path.display()
+            )))
+            .err_result();
+        }
+        for entry in std::fs::read_dir(&workflows_path).owe_res().with(&ctx)? {
+            let path = entry.owe_res()?.path();
+            if path.extension().is_some_and(|x| x == "gxl") {
+                scaffold
+                    .workflows
+                    .push(GxlAction::load_from(&path).with(&ctx)?);
+            }
+        }
+        scaffold.workflows.sort_by(|a, b| a.file().cmp(b.file()));
+        let work_path = root.join("_gal/work.gxl");
+        if work_path.exists() {
+            scaffold.work_gxl = std::fs::read_to_string(&work_path).owe_res().with(&ctx)?;
+        }
+        let spec_path = root.join(SPEC_DIR);
+        if spec_path.exists() {
+            for entry in WalkDir::new(&spec_path) {
+                let entry = entry.owe_res().with(&ctx)?;
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let rel = entry.path().strip_prefix(&spec_path).owe_sys()?;
+                let content = std::fs::read_to_string(entry.path()).owe_res().with(&ctx)?;
+                scaffold.files.push((rel.display().to_string(), content));
+            }
+        }
+        let vars_path = root.join(VARS_YML);
+        if vars_path.exists() {
+            scaffold.vars =
+                VarCollection::eval_from_file(&ValueDict::default(), &vars_path).with(&ctx)?;
+        }
+        Ok(scaffold)
+    }
+
+    // 变量默认值中的占位符同样替换为模块名
+    fn named_vars(&self, name: &str) -> SpecResult<VarCollection> {
+        let text = serde_yaml::to_string(&self.vars).owe_data()?;
+        serde_yaml::from_str(&text.replace(SCAFFOLD_NAME_HOLDER, name)).owe_data()
+    }
+    fn target_spec(&self, name: &str, target: &ModelSTD) -> SpecResult<ModModelSpec> {
+        let actions = self
+            .workflows
+            .iter()
+            .map(|x| {
+                Workflow::Gxl(GxlAction::new(
+                    x.task().clone(),
+                    x.file().clone(),
+                    x.code().replace(SCAFFOLD_NAME_HOLDER, name),
+                ))
+            })
+            .collect();
+        Ok(ModModelSpec::init(
+            target.clone(),
+            ArtifactPackage::from(vec![Artifact::new(
+                name,
+                HttpAddr::from(format!("https://example.com/{}/{}.tar.gz", name, name)),
+                format!("{}.tar.gz", name).as_str(),
+            )]),
+            ModWorkflows::new(actions),
+            GxlProject::from(self.work_gxl.as_str()),
+            CaculateResSpec::new(2, 4),
+            self.named_vars(name)?,
+            self.setting.clone(),
+        ))
+    }
+}
+
+// 内置模板与用户模板
+#[derive(Getters, Clone, Debug)]
+pub struct ScaffoldCatalog {
+    items: Vec<Scaffold>,
+}
+
+impl ScaffoldCatalog {
+    pub fn builtin() -> Self {
+        Self {
+            items: vec![
+                Scaffold::host(),
+                Scaffold::k8s(),
+                Scaffold::systemd(),
+                Scaffold::compose(),
+                Scaffold::helm(),
+                Scaffold::static_binary(),
+            ],
+        }
+    }
+    // 目录下每个子目录为一种模板, 同名覆盖内置模板
+    pub fn with_dir(mut self, root: &Path) -> SpecResult<Self> {
+        for sub in get_sub_dirs(root)? {
+            if path_file_name(&sub)?.starts_with('.') {
+                continue;
+            }
+            let scaffold = Scaffold::load_dir(&sub)?;
+            self.items.retain(|x| x.kind != scaffold.kind);
+            self.items.push(scaffold);
+        }
+        Ok(self)
+    }
+    // 拉取 git 中的用户模板到 ~/.cache/galaxy/scaffold
+    pub async fn with_git(self, addr: &GitAddr, options: &UpdateOptions) -> SpecResult<Self> {
+        let cache = home_dir()
+            .ok_or(StructError::from_res("unget home".into()))?
+            .join(".cache/galaxy/scaffold");
+        std::fs::create_dir_all(&cache).owe_res().with(&cache)?;
+        let path = addr.update_local(&cache, options).await?;
+        self.with_dir(&path)
+    }
+    pub fn find(&self, kind: &str) -> SpecResult<&Scaffold> {
+        self.items.iter().find(|x| x.kind == kind).ok_or(
+            SpecReason::from(ElementReason::Miss(format!(
+                "scaffold kind {}, use one of: {}",
+                kind,
+                self.kinds().join(",")
+            )))
+            .to_err(),
+        )
+    }
+    pub fn kinds(&self) -> Vec<&str> {
+        self.items.iter().map(|x| x.kind.as_str()).collect()
+    }
+}
+
+impl ModProject {
+    // 按模板生成模块工程, targets 为空时使用模板默认 target
+    pub fn make_scaffold(
+        prj_path: &Path,
+        name: &str,
+        scaffold: &Scaffold,
+        targets: &[ModelSTD],
+    ) -> SpecResult<Self> {
+        let targets = if targets.is_empty() {
+            scaffold.targets()
+        } else {
+            targets
+        };
+        let specs = targets
+            .iter()
+            .map(|x| scaffold.target_spec(name, x))
+            .collect::<SpecResult<Vec<_>>>()?;
+        let prj = ModProject::new(
+            ModuleSpec::init(name, specs),
+            DependencySet::default(),
+            prj_path.to_path_buf(),
+        );
+        prj.save()?;
+        for target in targets {
+            let spec_path = prj_path
+                .join(MOD_DIR)
+                .join(target.to_string())
+                .join(SPEC_DIR);
+            for (rel, content) in scaffold.files() {
+                let path = spec_path.join(rel.replace(SCAFFOLD_NAME_HOLDER, name));
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent).owe_res().with(parent)?;
+                }
+                std::fs::write(&path, content.replace(SCAFFOLD_NAME_HOLDER, name))
+                    .owe_res()
+                    .with(&path)?;
+            }
+        }
+        Ok(prj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        const_vars::LOCAL_DIR,
+        types::{Localizable, LocalizeOptions},
+        vars::{ValueType, env_var_refs},
+    };
+
+    #[test]
+    fn test_scaffold_builtin() {
+        let temp = tempfile::tempdir().assert();
+        let catalog = ScaffoldCatalog::builtin();
+        assert!(catalog.find("ansible").is_err());
+        let helm = catalog.find("helm").assert();
+        let targets = vec![ModelSTD::x86_ubt22_k8s(), ModelSTD::arm_mac14_host()];
+        let prj_path = temp.path().join("redis");
+        std::fs::create_dir_all(&prj_path).assert();
+        ModProject::make_scaffold(&prj_path, "redis", helm, &targets).assert();
+
+        let prj = ModProject::load(&prj_path).assert();
+        assert_eq!(prj.mod_spec().targets().len(), 2);
+        let target_root = prj_path
+            .join(MOD_DIR)
+            .join(ModelSTD::arm_mac14_host().to_string());
+        let values = std::fs::read_to_string(target_root.join("spec/values.yaml")).assert();
+        assert!(values.contains("{{REPLICAS}}"));
+        let setup =
+            std::fs::read_to_string(target_root.join(WORKFLOWS_DIR).join("setup.gxl")).assert();
+        assert!(setup.contains("helm upgrade --install redis"));
+        let target = prj
+            .mod_spec()
+            .targets()
+            .get(&ModelSTD::x86_ubt22_k8s())
+            .unwrap();
+        assert_eq!(
+            target.vars().value_dict().get("IMAGE"),
+            Some(&ValueType::from("redis"))
+        );
+
+        let systemd = catalog.find("systemd").assert();
+        let prj_path = temp.path().join("agent");
+        std::fs::create_dir_all(&prj_path).assert();
+        let prj = ModProject::make_scaffold(&prj_path, "agent", systemd, &[]).assert();
+        assert_eq!(
+            prj.mod_spec().targets().keys().cloned().collect::<Vec<_>>(),
+            vec![ModelSTD::x86_ubt22_host()]
+        );
+        assert!(
+            prj_path
+                .join(MOD_DIR)
+                .join(ModelSTD::x86_ubt22_host().to_string())
+                .join("spec/agent.service")
+                .exists()
+        );
+    }
+
+    // 每种内置模板: 生成, 加载, 渲染; 工作流引用的变量都已声明
+    #[tokio::test]
+    async fn test_scaffold_render() {
+        let temp = tempfile::tempdir().assert();
+        let catalog = ScaffoldCatalog::builtin();
+        for scaffold in catalog.items() {
+            let prj_path = temp.path().join(scaffold.kind());
+            std::fs::create_dir_all(&prj_path).assert();
+            ModProject::make_scaffold(&prj_path, "demo", scaffold, &[]).assert();
+            let prj = ModProject::load(&prj_path).assert();
+            prj.localize(None, LocalizeOptions::for_test())
+                .await
+                .assert();
+            for (target, spec) in prj.mod_spec().targets() {
+                let declared = spec.vars().value_dict();
+                for action in scaffold.workflows() {
+                    for name in env_var_refs(action.code()) {
+                        assert!(
+                            name.starts_with("ENV_")
+                                || name == "HOME"
+                                || declared.contains_key(&name),
+                            "{} {}: {} not declared",
+                            scaffold.kind(),
+                            action.file(),
+                            name
+                        );
+                    }
+                }
+                let local = prj_path
+                    .join(MOD_DIR)
+                    .join(target.to_string())
+                    .join(LOCAL_DIR);
+                for (rel, _) in scaffold.files() {
+                    let path = local.join(rel.replace(SCAFFOLD_NAME_HOLDER, "demo"));
+                    let text = std::fs::read_to_string(&path).assert();
+                    assert!(!text.contains("{{"), "{} not rendered", path.display());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_scaffold_user_dir() {
+        let temp = tempfile::tempdir().assert();
+        let root = temp.path().join("scaffolds");
+        let nginx = root.join("nginx");
+        std::fs::create_dir_all(nginx.join(WORKFLOWS_DIR)).assert();
+        std::fs::create_dir_all(nginx.join("spec/conf.d")).assert();
+        std::fs::write(
+            nginx.join(WORKFLOWS_DIR).join("setup.gxl"),
+            "mod setup {\n    flow do {\n      gx.echo ( value : \"setup __MOD_NAME__\" );\n    }\n}\n",
+        )
+        .assert();
+        std::fs::write(
+            nginx.join("spec/conf.d/__MOD_NAME__.conf"),
+            "listen {{PORT}};",
+        )
+        .assert();
+        vec![ModelSTD::x86_ubt22_k8s()]
+            .save_conf(&nginx.join(SCAFFOLD_TARGETS_YML))
+            .assert();
+
+        let catalog = ScaffoldCatalog::builtin().with_dir(&root).assert();
+        let scaffold = catalog.find("nginx").assert();
+        assert_eq!(scaffold.targets(), &vec![ModelSTD::x86_ubt22_k8s()]);
+        assert_eq!(scaffold.workflows()[0].task(), &OperationType::Setup);
+
+        let prj_path = temp.path().join("web");
+        std::fs::create_dir_all(&prj_path).assert();
+        ModProject::make_scaffold(&prj_path, "web", scaffold, &[]).assert();
+        let target_root = prj_path
+            .join(MOD_DIR)
+            .join(ModelSTD::x86_ubt22_k8s().to_string());
+        assert!(target_root.join("spec/conf.d/web.conf").exists());
+        let setup =
+            std::fs::read_to_string(target_root.join(WORKFLOWS_DIR).join("setup.gxl")).assert();
+        assert!(setup.contains("setup web"));
+    }
+}
@@ -1,7 +1,9 @@
+use std::path::Path;
+
 use derive_getters::Getters;
 use serde_derive::{Deserialize, Serialize};
 
-use super::LocalizeConf;
+use super::{LocalizeConf, TemplateConfig, TemplatePath};
 
 #[derive(Clone, Debug, Serialize, Deserialize, Getters)]
 pub struct Setting {
@@ -10,11 +12,29 @@ pub struct Setting {
 }
 
 impl Setting {
+    pub fn new(localize: Option<LocalizeConf>) -> Self {
+        Self { localize }
+    }
     pub fn example() -> Self {
         Self {
             localize: Some(LocalizeConf::example()),
         }
     }
+    // include/exclude 以 target 目录为根
+    pub fn templatize_path(&self, root: &Path) -> TemplatePath {
+        self.localize
+            .as_ref()
+            .and_then(|x| x.templatize_path().as_ref())
+            .map(|x| x.export_paths(root))
+            .unwrap_or_default()
+    }
+    // 自定义模板标签
+    pub fn template_config(&self) -> Option<TemplateConfig> {
+        self.localize
+            .as_ref()
+            .and_then(|x| x.templatize_cust().clone())
+            .map(TemplateConfig::from)
+    }
 }
 
 #[cfg(test)]
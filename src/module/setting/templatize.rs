@@ -35,6 +35,9 @@ pub struct TemplateTargets {
 }
 
 impl TemplateTargets {
+    pub fn new(includes: Vec<String>, excludes: Vec<String>) -> Self {
+        Self { includes, excludes }
+    }
     pub fn export_paths(&self, root: &Path) -> TemplatePath {
         let includes = self.includes().iter().map(|x| root.join(x)).collect();
         let excludes = self.excludes().iter().map(|x| root.join(x)).collect();
@@ -2,9 +2,9 @@ use crate::{predule::*, vars::VarDefinition};
 use std::collections::HashMap;
 
 use crate::{
-    const_vars::{CONFS_DIR, MOD_DIR},
-    types::{Localizable, ValuePath},
-    vars::VarCollection,
+    const_vars::{CONFS_DIR, MOD_DIR, MOD_REQUIRES_YML, VARS_YML},
+    types::{Configable, Localizable, ValuePath},
+    vars::{ValueDict, VarCollection},
     workflow::prj::GxlProject,
 };
 use async_trait::async_trait;
@@ -14,6 +14,7 @@ use super::{
     depend::DependencySet,
     init::{ModIniter, ModPrjIniter, mod_init_gitignore},
     model::ModModelSpec,
+    require::ModRequires,
     setting::Setting,
 };
 use crate::types::LocalizeOptions;
@@ -32,6 +33,10 @@ use crate::{
 pub struct ModuleSpec {
     name: String,
     targets: HashMap<ModelSTD, ModModelSpec>,
+    // mod/vars.yml: 各 target 共享的变量, 可用 when 按 target 覆盖
+    vars: VarCollection,
+    // mod/requires.yml: 对系统内其他模块的依赖
+    requires: ModRequires,
     local: Option<PathBuf>,
 }
 impl ModuleSpec {
@@ -43,9 +48,24 @@ impl ModuleSpec {
         Self {
             name: name.into(),
             targets,
+            vars: VarCollection::default(),
+            requires: ModRequires::default(),
             local: None,
         }
     }
+    pub fn with_requires(mut self, requires: ModRequires) -> Self {
+        self.requires = requires;
+        self
+    }
+    pub fn with_vars(mut self, vars: VarCollection) -> Self {
+        self.targets = self
+            .targets
+            .into_iter()
+            .map(|(k, v)| (k, v.with_shared_vars(vars.clone())))
+            .collect();
+        self.vars = vars;
+        self
+    }
     pub fn clean_other(&mut self, node: &ModelSTD) -> SpecResult<()> {
         if let Some(local) = &self.local {
             let src_path = local.join(MOD_DIR);
@@ -64,6 +84,52 @@ impl ModuleSpec {
         }
         Ok(())
     }
+    pub fn with_local(mut self, local: &Path) -> Self {
+        self.local = Some(local.to_path_buf());
+        self
+    }
+    // 写入 mod/ 目录的内容: 各 target, vars.yml, requires.yml
+    pub fn save_src(&self, src_path: &Path) -> SpecResult<()> {
+        std::fs::create_dir_all(src_path)
+            .owe_conf()
+            .with(src_path)?;
+        for node in self.targets.values() {
+            node.save_to(src_path, None)?;
+        }
+        if !self.vars.vars().is_empty() {
+            self.vars.save_conf(&src_path.join(VARS_YML))?;
+        }
+        if !self.requires.is_empty() {
+            self.requires.save_conf(&src_path.join(MOD_REQUIRES_YML))?;
+        }
+        Ok(())
+    }
+    pub fn load_src(name: String, src_path: &Path) -> SpecResult<Self> {
+        let vars_path = src_path.join(VARS_YML);
+        let vars = if vars_path.exists() {
+            VarCollection::eval_from_file(&ValueDict::default(), &vars_path).with(&vars_path)?
+        } else {
+            VarCollection::default()
+        };
+        let requires_path = src_path.join(MOD_REQUIRES_YML);
+        let requires = if requires_path.exists() {
+            ModRequires::from_conf(&requires_path)?
+        } else {
+            ModRequires::default()
+        };
+        let mut targets = HashMap::new();
+        for sub in get_sub_dirs(src_path)? {
+            let node = ModModelSpec::load_from(&sub).with(&sub)?;
+            targets.insert(node.model().clone(), node);
+        }
+        Ok(Self {
+            name,
+            targets,
+            vars,
+            requires,
+            local: Some(src_path.to_path_buf()),
+        })
+    }
     pub fn save_main(&self, path: &Path, name: Option<String>) -> SpecResult<()> {
         let mod_path = path.join(name.unwrap_or(self.name().clone()));
         std::fs::create_dir_all(&mod_path)
@@ -97,11 +163,7 @@ impl Persistable<ModuleSpec> for ModuleSpec {
             .with(format!("path: {}", mod_path.display()))?;
 
         mod_init_gitignore(&mod_path)?;
-        for node in self.targets.values() {
-            node.save_to(&src_path, None)?;
-        }
-
-        Ok(())
+        self.save_src(&src_path)
     }
 
     fn load_from(path: &Path) -> SpecResult<Self> {
@@ -111,19 +173,9 @@ impl Persistable<ModuleSpec> for ModuleSpec {
             info!(target: "mod/spec", "load mod-spec {} success!", name_copy ),
             error!(target: "mod/spec", "load mod-spec {} fail!", name_copy)
         );
-        let src_path = path.join(MOD_DIR);
-        let subs = get_sub_dirs(&src_path)?;
-        let mut targets = HashMap::new();
-        for sub in subs {
-            let node = ModModelSpec::load_from(&sub).with(&sub)?;
-            targets.insert(node.model().clone(), node);
-        }
+        let spec = Self::load_src(name, &path.join(MOD_DIR))?;
         flag.flag_suc();
-        Ok(Self {
-            name,
-            targets,
-            local: Some(path.to_path_buf()),
-        })
+        Ok(spec.with_local(path))
     }
 }
 
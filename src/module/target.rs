@@ -0,0 +1,623 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use derive_getters::Getters;
+use home::home_dir;
+use once_cell::sync::Lazy;
+use orion_common::cond::CmpOP;
+use serde_derive::{Deserialize, Serialize};
+
+use log::warn;
+
+use crate::{
+    error::SpecResult,
+    types::Yamlable,
+    vars::{RuntimeContext, get_os_info},
+};
+
+use super::{CpuArch, ModelSTD, OsCPE, RunSPC};
+
+// 用户自定义的 target 模型, 与内置列表合并
+pub const TARGET_MODELS_YML: &str = ".galaxy/target_models.yml";
+
+static REGISTRY: Lazy<TargetRegistry> = Lazy::new(TargetRegistry::load_default);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetPart {
+    Arch,
+    Os,
+    Spc,
+}
+
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TargetEntry {
+    name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    // 找不到对应 target 时依次尝试; os 可写成 `cos>=7`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fallback: Vec<String>,
+}
+
+impl TargetEntry {
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+    pub fn with_aliases(mut self, aliases: &[&str]) -> Self {
+        self.aliases = aliases.iter().map(|x| x.to_string()).collect();
+        self
+    }
+    pub fn with_fallback(mut self, fallback: &[&str]) -> Self {
+        self.fallback = fallback.iter().map(|x| x.to_string()).collect();
+        self
+    }
+    fn is_match(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+            || self.aliases.iter().any(|x| x.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct TargetRegistry {
+    #[serde(default)]
+    arch: Vec<TargetEntry>,
+    #[serde(default)]
+    os: Vec<TargetEntry>,
+    #[serde(default)]
+    spc: Vec<TargetEntry>,
+}
+
+impl TargetRegistry {
+    pub fn builtin() -> Self {
+        Self {
+            arch: vec![
+                TargetEntry::new("x86").with_aliases(&["amd64", "x86_64"]),
+                TargetEntry::new("arm").with_aliases(&["arm64", "aarch64"]),
+                TargetEntry::new("riscv64").with_aliases(&["riscv"]),
+            ],
+            os: vec![
+                TargetEntry::new("mac14").with_aliases(&["sonoma"]),
+                TargetEntry::new("mac15").with_aliases(&["sequoia"]),
+                TargetEntry::new("win10"),
+                TargetEntry::new("win11"),
+                TargetEntry::new("ubt22").with_aliases(&["jammy"]),
+                TargetEntry::new("ubt24").with_aliases(&["noble"]),
+                TargetEntry::new("deb12").with_aliases(&["bookworm"]),
+                TargetEntry::new("cos7").with_aliases(&["centos7"]),
+                TargetEntry::new("rocky9").with_fallback(&["cos>=7"]),
+            ],
+            spc: vec![
+                TargetEntry::new("host"),
+                TargetEntry::new("k8s").with_aliases(&["kubernetes"]),
+                TargetEntry::new("compose").with_aliases(&["docker_compose"]),
+                TargetEntry::new("systemd").with_fallback(&["host"]),
+            ],
+        }
+    }
+    // 内置列表 + ~/.galaxy/target_models.yml
+    fn load_default() -> Self {
+        let builtin = Self::builtin();
+        let Some(path) = Self::user_path().filter(|x| x.exists()) else {
+            return builtin;
+        };
+        match Self::from_yml(&path) {
+            Ok(user) => builtin.merge(user),
+            Err(e) => {
+                warn!(target: "spec/target", "load {} failed: {}", path.display(), e);
+                builtin
+            }
+        }
+    }
+    pub fn user_path() -> Option<PathBuf> {
+        home_dir().map(|x| x.join(TARGET_MODELS_YML))
+    }
+    pub fn global() -> &'static TargetRegistry {
+        &REGISTRY
+    }
+    pub fn load_from(path: &Path) -> SpecResult<Self> {
+        Ok(Self::builtin().merge(Self::from_yml(path)?))
+    }
+
+    // 同名条目以 other 为准
+    pub fn merge(mut self, other: TargetRegistry) -> Self {
+        fn merge_entries(base: &mut Vec<TargetEntry>, other: Vec<TargetEntry>) {
+            for entry in other {
+                match base.iter_mut().find(|x| x.name == entry.name) {
+                    Some(exist) => *exist = entry,
+                    None => base.push(entry),
+                }
+            }
+        }
+        merge_entries(&mut self.arch, other.arch);
+        merge_entries(&mut self.os, other.os);
+        merge_entries(&mut self.spc, other.spc);
+        self
+    }
+
+    fn entries(&self, part: TargetPart) -> &Vec<TargetEntry> {
+        match part {
+            TargetPart::Arch => &self.arch,
+            TargetPart::Os => &self.os,
+            TargetPart::Spc => &self.spc,
+        }
+    }
+    pub fn entry(&self, part: TargetPart, name: &str) -> Option<&TargetEntry> {
+        self.entries(part).iter().find(|x| x.is_match(name))
+    }
+    // 别名转为标准名, 未登记返回 None
+    pub fn canonical(&self, part: TargetPart, name: &str) -> Option<&str> {
+        self.entry(part, name).map(|x| x.name.as_str())
+    }
+
+    pub fn parse_model(&self, value: &str) -> Result<ModelSTD, String> {
+        let parts: Vec<&str> = value.split('-').collect();
+        if parts.len() != 3 {
+            return Err(value.into());
+        }
+        let name = |part, text: &str| {
+            self.canonical(part, text)
+                .ok_or_else(|| format!("unknown {:?} '{}' in {}", part, text, value))
+        };
+        Ok(ModelSTD::new(
+            CpuArch::from_name(name(TargetPart::Arch, parts[0])?),
+            OsCPE::from_name(name(TargetPart::Os, parts[1])?),
+            RunSPC::from_name(name(TargetPart::Spc, parts[2])?),
+        ))
+    }
+
+    fn chain(&self, part: TargetPart, name: &str) -> Vec<String> {
+        let mut chain = vec![name.to_string()];
+        if let Some(entry) = self.entry(part, name) {
+            chain.extend(entry.fallback.iter().cloned());
+        }
+        chain
+    }
+
+    // 在已有的 target 中为 want 选择最合适的:
+    // 先精确匹配, 再取同系列中不高于 want 的最高版本, 最后按 fallback 依次尝试
+    pub fn select<'a>(&self, want: &ModelSTD, available: &'a [ModelSTD]) -> Option<&'a ModelSTD> {
+        if let Some(found) = available.iter().find(|x| *x == want) {
+            return Some(found);
+        }
+        let mut os_rules = vec![OsMatch::Name(want.os().to_string())];
+        if let Some(version) = want.os().version() {
+            os_rules.push(OsMatch::Range(OsRange::new(
+                want.os().family(),
+                CmpOP::Le,
+                version,
+            )));
+        }
+        for rule in self
+            .chain(TargetPart::Os, &want.os().to_string())
+            .iter()
+            .skip(1)
+        {
+            if let Ok(rule) = OsMatch::parse_with(self, rule) {
+                os_rules.push(rule);
+            }
+        }
+        for spc in self.chain(TargetPart::Spc, &want.spc().to_string()) {
+            for arch in self.chain(TargetPart::Arch, &want.arch().to_string()) {
+                for rule in &os_rules {
+                    let found = available
+                        .iter()
+                        .filter(|x| x.spc().to_string() == spc && x.arch().to_string() == arch)
+                        .filter(|x| rule.is_match(x.os()))
+                        .max_by_key(|x| x.os().version());
+                    if found.is_some() {
+                        return found;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+// `ubt>=22`
+#[derive(Getters, Clone, Debug, PartialEq)]
+pub struct OsRange {
+    family: String,
+    op: CmpOP,
+    version: u32,
+}
+
+impl OsRange {
+    pub fn new<S: Into<String>>(family: S, op: CmpOP, version: u32) -> Self {
+        Self {
+            family: family.into(),
+            op,
+            version,
+        }
+    }
+    pub fn is_match(&self, os: &OsCPE) -> bool {
+        if os.family() != self.family {
+            return false;
+        }
+        let Some(version) = os.version() else {
+            return false;
+        };
+        match self.op {
+            CmpOP::Eq | CmpOP::We => version == self.version,
+            CmpOP::Ne => version != self.version,
+            CmpOP::Gt => version > self.version,
+            CmpOP::Ge => version >= self.version,
+            CmpOP::Lt => version < self.version,
+            CmpOP::Le => version <= self.version,
+        }
+    }
+}
+
+impl FromStr for OsRange {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const OPS: [(&str, CmpOP); 7] = [
+            (">=", CmpOP::Ge),
+            ("<=", CmpOP::Le),
+            ("==", CmpOP::Eq),
+            ("!=", CmpOP::Ne),
+            (">", CmpOP::Gt),
+            ("<", CmpOP::Lt),
+            ("=", CmpOP::Eq),
+        ];
+        for (sym, op) in OPS {
+            if let Some((family, version)) = s.split_once(sym) {
+                let version = version.trim().parse().map_err(|_| s.to_string())?;
+                return Ok(Self::new(family.trim(), op, version));
+            }
+        }
+        Err(s.to_string())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum OsMatch {
+    Any,
+    Name(String),
+    Range(OsRange),
+}
+
+impl OsMatch {
+    pub fn is_match(&self, os: &OsCPE) -> bool {
+        match self {
+            OsMatch::Any => true,
+            OsMatch::Name(name) => os.to_string() == *name,
+            OsMatch::Range(range) => range.is_match(os),
+        }
+    }
+}
+
+impl OsMatch {
+    pub fn parse_with(registry: &TargetRegistry, s: &str) -> Result<Self, String> {
+        if s == "*" {
+            return Ok(OsMatch::Any);
+        }
+        if s.contains(['<', '>', '=', '!']) {
+            return OsRange::from_str(s).map(OsMatch::Range);
+        }
+        let name = registry.canonical(TargetPart::Os, s).unwrap_or(s);
+        Ok(OsMatch::Name(name.to_string()))
+    }
+}
+
+impl FromStr for OsMatch {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(TargetRegistry::global(), s)
+    }
+}
+
+// `*-ubt>=22-host`: 各部分可用 `*`, os 可用版本范围
+#[derive(Getters, Clone, Debug, PartialEq)]
+pub struct ModelPattern {
+    arch: Option<String>,
+    os: OsMatch,
+    spc: Option<String>,
+}
+
+impl ModelPattern {
+    pub fn is_match(&self, model: &ModelSTD) -> bool {
+        self.arch
+            .as_ref()
+            .is_none_or(|x| *x == model.arch().to_string())
+            && self.os.is_match(model.os())
+            && self
+                .spc
+                .as_ref()
+                .is_none_or(|x| *x == model.spc().to_string())
+    }
+}
+
+impl ModelPattern {
+    pub fn parse_with(registry: &TargetRegistry, value: &str) -> Result<Self, String> {
+        let parts: Vec<&str> = value.split('-').collect();
+        if parts.len() != 3 {
+            return Err(value.into());
+        }
+        let name = |part, text: &str| -> Result<Option<String>, String> {
+            if text == "*" {
+                return Ok(None);
+            }
+            registry
+                .canonical(part, text)
+                .map(|x| Some(x.to_string()))
+                .ok_or_else(|| format!("unknown {:?} '{}' in {}", part, text, value))
+        };
+        Ok(Self {
+            arch: name(TargetPart::Arch, parts[0])?,
+            os: OsMatch::parse_with(registry, parts[1])?,
+            spc: name(TargetPart::Spc, parts[2])?,
+        })
+    }
+}
+
+impl FromStr for ModelPattern {
+    type Err = String;
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(TargetRegistry::global(), value)
+    }
+}
+
+pub const MODEL_AUTO: &str = "auto";
+// 在 pod 中运行时由 k8s 注入
+pub const K8S_SERVICE_ENV: &str = "KUBERNETES_SERVICE_HOST";
+
+// os_info 的系统名 -> target 中的 os 系列
+const OS_FAMILIES: [(&str, &str); 7] = [
+    ("ubuntu", "ubt"),
+    ("macos", "mac"),
+    ("windows", "win"),
+    ("centos", "cos"),
+    ("rockylinux", "rocky"),
+    ("rocky", "rocky"),
+    ("debian", "deb"),
+];
+
+impl TargetRegistry {
+    // 由主机信息组装 target, os 为 系列+主版本号
+    pub fn host_model(
+        &self,
+        arch: &str,
+        os_type: &str,
+        version: u64,
+        in_k8s: bool,
+    ) -> Result<ModelSTD, String> {
+        let os_key = os_type.replace([' ', '_'], "").to_lowercase();
+        let family = OS_FAMILIES
+            .iter()
+            .find(|(name, _)| *name == os_key)
+            .map(|(_, family)| *family)
+            .unwrap_or(os_key.as_str());
+        let spc = if in_k8s { "k8s" } else { "host" };
+        self.parse_model(&format!("{}-{}{}-{}", arch, family, version, spc))
+    }
+}
+
+// ModuleSpecRef 的 model: 指定的 target, 或 auto 按当前主机探测
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TargetModel {
+    Auto,
+    Fixed(ModelSTD),
+}
+
+impl From<ModelSTD> for TargetModel {
+    fn from(value: ModelSTD) -> Self {
+        Self::Fixed(value)
+    }
+}
+
+impl Display for TargetModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetModel::Auto => write!(f, "{}", MODEL_AUTO),
+            TargetModel::Fixed(model) => write!(f, "{}", model),
+        }
+    }
+}
+
+impl FromStr for TargetModel {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == MODEL_AUTO {
+            return Ok(Self::Auto);
+        }
+        ModelSTD::from_str(s).map(Self::Fixed)
+    }
+}
+
+impl serde::Serialize for TargetModel {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TargetModel {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// 当前主机对应的 target 及来源; 命令行 --model 优先
+pub fn detect_host_model(runtime: &RuntimeContext) -> Result<(ModelSTD, String), String> {
+    if let Some(model) = runtime.host_model() {
+        return Ok((model.clone(), "--model override".into()));
+    }
+    let (arch, os_type, version) = get_os_info();
+    let in_k8s =
+        runtime.envs().contains_key(K8S_SERVICE_ENV) || std::env::var_os(K8S_SERVICE_ENV).is_some();
+    let model = runtime
+        .targets()
+        .host_model(&arch, &os_type, version, in_k8s)?;
+    let mut reason = format!("detected host {} {} {}", arch, os_type, version);
+    if in_k8s {
+        reason.push_str(" in k8s");
+    }
+    Ok((model, reason))
+}
+
+// update 报告中的 target 选择结果
+#[derive(Getters, Clone, Debug, PartialEq, Serialize)]
+pub struct TargetChoice {
+    module: String,
+    want: TargetModel,
+    chosen: ModelSTD,
+    reason: String,
+}
+
+impl TargetChoice {
+    pub fn new<S: Into<String>>(
+        module: &str,
+        want: TargetModel,
+        chosen: ModelSTD,
+        reason: S,
+    ) -> Self {
+        Self {
+            module: module.to_string(),
+            want,
+            chosen,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl Display for TargetChoice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} ({})",
+            self.module, self.want, self.chosen, self.reason
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 测试只用内置登记表, 不读取 home 下的配置
+    fn model(value: &str) -> ModelSTD {
+        ModelSTD::parse_with(&TargetRegistry::builtin(), value).unwrap()
+    }
+    fn models(items: &[&str]) -> Vec<ModelSTD> {
+        items.iter().map(|x| model(x)).collect()
+    }
+
+    #[test]
+    fn test_parse_model() {
+        let builtin = TargetRegistry::builtin();
+        let parsed = model("amd64-jammy-host");
+        assert_eq!(parsed, ModelSTD::x86_ubt22_host());
+        assert_eq!(parsed.to_string(), "x86-ubt22-host");
+        let parsed = model("riscv64-rocky9-systemd");
+        assert_eq!(parsed.arch(), &CpuArch::Other("riscv64".into()));
+        assert_eq!(parsed.os().family(), "rocky");
+        assert_eq!(parsed.os().version(), Some(9));
+        assert_eq!(parsed.to_string(), "riscv64-rocky9-systemd");
+        assert!(ModelSTD::parse_with(&builtin, "x86-ubt99x-host").is_err());
+        assert!(ModelSTD::parse_with(&builtin, "x86-ubt22").is_err());
+
+        let user: TargetRegistry =
+            serde_yaml::from_str("os:\n  - name: alma9\n    aliases: [almalinux9]\n").unwrap();
+        let registry = TargetRegistry::builtin().merge(user);
+        let parsed = ModelSTD::parse_with(&registry, "x86-almalinux9-host").unwrap();
+        assert_eq!(parsed.os(), &OsCPE::Other("alma9".into()));
+        assert!(ModelSTD::parse_with(&builtin, "x86-almalinux9-host").is_err());
+    }
+
+    #[test]
+    fn test_select_fallback() {
+        let registry = TargetRegistry::builtin();
+        let available = models(&[
+            "x86-ubt22-host",
+            "x86-ubt22-k8s",
+            "x86-cos7-host",
+            "arm-mac14-host",
+        ]);
+        let select = |want: &str| {
+            registry
+                .select(&model(want), &available)
+                .map(|x| x.to_string())
+        };
+        assert_eq!(select("x86-ubt22-k8s").as_deref(), Some("x86-ubt22-k8s"));
+        assert_eq!(select("x86-ubt24-host").as_deref(), Some("x86-ubt22-host"));
+        assert_eq!(select("x86-rocky9-host").as_deref(), Some("x86-cos7-host"));
+        assert_eq!(
+            select("x86-ubt24-systemd").as_deref(),
+            Some("x86-ubt22-host")
+        );
+        assert_eq!(select("arm-mac15-host").as_deref(), Some("arm-mac14-host"));
+        assert_eq!(select("arm-ubt24-host"), None);
+        assert_eq!(select("x86-win11-host"), None);
+    }
+
+    #[test]
+    fn test_host_model() {
+        let registry = TargetRegistry::builtin();
+        let host = |arch, os, ver, k8s| {
+            registry
+                .host_model(arch, os, ver, k8s)
+                .map(|x| x.to_string())
+        };
+        assert_eq!(
+            host("x86_64", "ubuntu", 22, false).unwrap(),
+            "x86-ubt22-host"
+        );
+        assert_eq!(host("arm64", "macos", 14, false).unwrap(), "arm-mac14-host");
+        assert_eq!(
+            host("x86_64", "Rocky Linux", 9, true).unwrap(),
+            "x86-rocky9-k8s"
+        );
+        assert!(host("x86_64", "gentoo", 2, false).is_err());
+
+        let runtime = RuntimeContext::default().with_host_model(Some(ModelSTD::x86_ubt22_k8s()));
+        let (model, reason) = detect_host_model(&runtime).unwrap();
+        assert_eq!(model, ModelSTD::x86_ubt22_k8s());
+        assert!(reason.contains("--model"));
+        assert_eq!(TargetModel::from_str("auto").unwrap(), TargetModel::Auto);
+        // 主机探测使用 runtime 中的登记表
+        let user: TargetRegistry =
+            serde_yaml::from_str("os:\n  - name: alma9\n    aliases: [almalinux9]\n").unwrap();
+        let runtime = RuntimeContext::default().with_targets(TargetRegistry::builtin().merge(user));
+        assert!(
+            runtime
+                .targets()
+                .host_model("x86_64", "almalinux", 9, false)
+                .is_ok()
+        );
+        assert_eq!(
+            serde_yaml::to_string(&TargetModel::from(ModelSTD::x86_ubt22_host())).unwrap(),
+            "x86-ubt22-host\n"
+        );
+    }
+
+    #[test]
+    fn test_model_pattern() {
+        let registry = TargetRegistry::builtin();
+        let pattern = ModelPattern::parse_with(&registry, "*-ubt>=22-host").unwrap();
+        assert!(pattern.is_match(&ModelSTD::x86_ubt22_host()));
+        assert!(pattern.is_match(&model("arm-ubt24-host")));
+        assert!(!pattern.is_match(&ModelSTD::x86_ubt22_k8s()));
+        assert!(!pattern.is_match(&model("x86-cos7-host")));
+        let pattern = ModelPattern::parse_with(&registry, "amd64-*-*").unwrap();
+        assert!(pattern.is_match(&ModelSTD::x86_ubt22_k8s()));
+        assert!(!pattern.is_match(&ModelSTD::arm_mac14_host()));
+        assert!(ModelPattern::parse_with(&registry, "x86-ubt>=x-host").is_err());
+        assert_eq!(
+            OsMatch::parse_with(&registry, "jammy").unwrap(),
+            OsMatch::Name("ubt22".into())
+        );
+    }
+}
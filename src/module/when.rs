@@ -0,0 +1,146 @@
+use std::{collections::HashMap, str::FromStr};
+
+use orion_common::cond::{
+    CompareExpress, Condition, ExpressEnum, LogicExpress, RustSymbol, ValueGet, cmp_is_true,
+};
+
+use crate::{
+    error::{LocalizeReason, SpecReason, SpecResult, ToErr},
+    vars::{Expr, ValueType, VarCollection},
+};
+
+use super::ModelSTD;
+
+pub const WHEN_ARCH: &str = "arch";
+pub const WHEN_OS: &str = "os";
+pub const WHEN_SPC: &str = "spc";
+
+pub type TargetCond = ExpressEnum<String, RustSymbol>;
+
+// 条件中可用的 target 属性
+pub struct TargetFacts {
+    items: HashMap<&'static str, String>,
+}
+
+impl From<&ModelSTD> for TargetFacts {
+    fn from(model: &ModelSTD) -> Self {
+        let items = HashMap::from([
+            (WHEN_ARCH, model.arch().to_string()),
+            (WHEN_OS, model.os().to_string()),
+            (WHEN_SPC, model.spc().to_string()),
+        ]);
+        Self { items }
+    }
+}
+
+impl ValueGet<String> for TargetFacts {
+    fn value_get(&self, var: &str) -> Option<&String> {
+        self.items.get(var)
+    }
+}
+
+impl Condition<TargetFacts> for LogicExpress<String, RustSymbol> {
+    fn is_true(&self, data: &TargetFacts) -> bool {
+        cmp_is_true(&self.op, self.left.as_ref(), &self.right, data)
+    }
+}
+
+fn when_err<T, S: Into<String>>(msg: S) -> SpecResult<T> {
+    SpecReason::from(LocalizeReason::Expr(msg.into())).err_result()
+}
+
+// `spc == k8s && os =* 'mac*'`; 语法沿用 expr, 左侧只能是 arch/os/spc
+pub fn parse_when(when: &str) -> SpecResult<TargetCond> {
+    to_cond(&Expr::from_str(when)?, when)
+}
+
+fn to_cond(expr: &Expr, src: &str) -> SpecResult<TargetCond> {
+    match expr {
+        Expr::Compare(op, left, right) => {
+            let Expr::Var(name) = left.as_ref() else {
+                return when_err(format!("expect arch/os/spc on the left: {}", src));
+            };
+            if ![WHEN_ARCH, WHEN_OS, WHEN_SPC].contains(&name.as_str()) {
+                return when_err(format!("unknown target attr '{}': {}", name, src));
+            }
+            let value = match right.as_ref() {
+                Expr::Var(v) | Expr::Str(v) => v.clone(),
+                Expr::Const(v @ ValueType::Int(_)) => v.to_string(),
+                _ => return when_err(format!("expect literal on the right: {}", src)),
+            };
+            Ok(ExpressEnum::Compare(CompareExpress::new(*op, name, value)))
+        }
+        Expr::Logic(op, left, right) => {
+            let left = match left {
+                Some(x) => Some(to_cond(x, src)?),
+                None => None,
+            };
+            Ok(ExpressEnum::Logic(LogicExpress::new(
+                op.clone(),
+                left,
+                to_cond(right, src)?,
+            )))
+        }
+        _ => when_err(format!("not a condition: {}", src)),
+    }
+}
+
+pub fn is_target_match(when: &str, model: &ModelSTD) -> SpecResult<bool> {
+    Ok(parse_when(when)?.is_true(&TargetFacts::from(model)))
+}
+
+// 模块共享 vars 按 target 过滤, 靠后匹配的同名变量覆盖之前的
+pub fn target_vars(shared: &VarCollection, model: &ModelSTD) -> SpecResult<VarCollection> {
+    let mut result = VarCollection::define(Vec::new());
+    for var in shared.vars() {
+        if let Some(when) = var.when()
+            && !is_target_match(when, model)?
+        {
+            continue;
+        }
+        result = result.merge(&VarCollection::define(vec![var.clone()]));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vars::VarDefinition;
+
+    use super::*;
+
+    #[test]
+    fn test_when_match() {
+        let k8s = ModelSTD::x86_ubt22_k8s();
+        let mac = ModelSTD::arm_mac14_host();
+        assert!(is_target_match("spc == k8s", &k8s).unwrap());
+        assert!(!is_target_match("spc == k8s", &mac).unwrap());
+        assert!(is_target_match("os =* 'mac*' && arch != x86", &mac).unwrap());
+        assert!(is_target_match("!(spc == host) || os == mac14", &k8s).unwrap());
+        assert!(!is_target_match("!(spc == k8s)", &k8s).unwrap());
+        assert!(is_target_match("cpu == x86", &k8s).is_err());
+        assert!(is_target_match("spc", &k8s).is_err());
+    }
+
+    #[test]
+    fn test_target_vars() {
+        let shared = VarCollection::define(vec![
+            VarDefinition::from(("PORT", 80)),
+            VarDefinition::from(("REPLICAS", 1)),
+            VarDefinition::from(("REPLICAS", 3)).with_when("spc == k8s"),
+            VarDefinition::from(("BIN_DIR", "/opt/homebrew/bin")).with_when("os =* 'mac*'"),
+        ]);
+        let k8s = target_vars(&shared, &ModelSTD::x86_ubt22_k8s()).unwrap();
+        let dict = k8s.value_dict();
+        assert_eq!(dict.get("REPLICAS"), Some(&ValueType::Int(3)));
+        assert!(dict.get("BIN_DIR").is_none());
+        let mac = target_vars(&shared, &ModelSTD::arm_mac14_host()).unwrap();
+        let dict = mac.value_dict();
+        assert_eq!(dict.get("REPLICAS"), Some(&ValueType::Int(1)));
+        assert_eq!(
+            dict.get("BIN_DIR"),
+            Some(&ValueType::from("/opt/homebrew/bin"))
+        );
+        assert_eq!(dict.get("PORT"), Some(&ValueType::Int(80)));
+    }
+}
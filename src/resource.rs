@@ -1,23 +1,58 @@
 use orion_error::ErrorOwe;
 use orion_error::ErrorWith;
 use orion_error::WithContext;
+use serde::{
+    Deserialize as SerdeDeserialize, Deserializer, Serialize as SerdeSerialize, de::Error,
+};
 use serde_derive::Deserialize;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fs;
+use std::net::IpAddr;
 use std::net::Ipv4Addr;
+use std::net::Ipv6Addr;
+use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::rc::Weak;
 
 use derive_getters::Getters;
+use derive_more::Deref;
 use serde_derive::Serialize;
 
+use crate::error::ElementReason;
+use crate::error::SpecReason;
 use crate::error::SpecResult;
+use crate::error::ToErr;
+use crate::types::Configable;
+use crate::vars::ValueMap;
+use crate::vars::ValueType;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ResAddress {
     Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Host(String),
+}
+
+impl ResAddress {
+    pub fn host(&self) -> String {
+        match self {
+            ResAddress::Ipv4(ip) => ip.to_string(),
+            ResAddress::Ipv6(ip) => ip.to_string(),
+            ResAddress::Host(host) => host.clone(),
+        }
+    }
+}
+
+impl From<IpAddr> for ResAddress {
+    fn from(value: IpAddr) -> Self {
+        match value {
+            IpAddr::V4(ip) => ResAddress::Ipv4(ip),
+            IpAddr::V6(ip) => ResAddress::Ipv6(ip),
+        }
+    }
 }
 
 impl Display for ResAddress {
@@ -26,35 +61,162 @@ impl Display for ResAddress {
             ResAddress::Ipv4(ipv4_addr) => {
                 write!(f, "addr:{}", ipv4_addr)
             }
+            ResAddress::Ipv6(ipv6_addr) => {
+                write!(f, "addr:[{}]", ipv6_addr)
+            }
+            ResAddress::Host(host) => {
+                write!(f, "addr:{}", host)
+            }
         }
     }
 }
+// 资源结构导出为 localize 使用的值, 空值需先在序列化时跳过
+pub(crate) fn export_value<T: SerdeSerialize>(value: &T) -> SpecResult<ValueType> {
+    serde_json::to_value(value)
+        .and_then(serde_json::from_value::<ValueType>)
+        .owe_data()
+}
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
 pub trait CaculateResource: Debug {
     fn address(&self) -> ResAddress;
 }
 pub type ResHold = Rc<dyn CaculateResource>;
 pub type ResWeak = Weak<dyn CaculateResource>;
-#[derive(Clone, Getters, Debug, Serialize, Deserialize)]
+#[derive(Clone, Getters, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct CaculateResSpec {
     core_cnt: u32,
     mem_size: u32,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    gpu_cnt: u32,
 }
 impl CaculateResSpec {
     pub fn new(core_cnt: u32, mem_size: u32) -> Self {
-        Self { core_cnt, mem_size }
+        Self {
+            core_cnt,
+            mem_size,
+            gpu_cnt: 0,
+        }
+    }
+    pub fn with_gpu(mut self, gpu_cnt: u32) -> Self {
+        self.gpu_cnt = gpu_cnt;
+        self
+    }
+    pub fn add(&self, other: &CaculateResSpec) -> Self {
+        Self::new(
+            self.core_cnt + other.core_cnt,
+            self.mem_size + other.mem_size,
+        )
+        .with_gpu(self.gpu_cnt + other.gpu_cnt)
+    }
+    // 剩余资源, 不足时为 None
+    pub fn sub(&self, other: &CaculateResSpec) -> Option<Self> {
+        Some(
+            Self::new(
+                self.core_cnt.checked_sub(other.core_cnt)?,
+                self.mem_size.checked_sub(other.mem_size)?,
+            )
+            .with_gpu(self.gpu_cnt.checked_sub(other.gpu_cnt)?),
+        )
+    }
+    pub fn covers(&self, other: &CaculateResSpec) -> bool {
+        self.sub(other).is_some()
+    }
+}
+
+impl Display for CaculateResSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}c/{}G", self.core_cnt, self.mem_size)?;
+        if self.gpu_cnt > 0 {
+            write!(f, "/{}gpu", self.gpu_cnt)?;
+        }
+        Ok(())
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+// resource.yml 中以 kind 区分类型, 缺省为 vps
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum ResouceTypes {
     Vps(Vps),
+    K8s(K8sCluster),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum TaggedRes {
+    Vps(Vps),
+    K8s(K8sCluster),
+}
+
+// 兼容旧格式 `Vps: { .. }`; 没有 kind 时按 vps 读取
+impl<'de> SerdeDeserialize<'de> for ResouceTypes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut value = serde_yaml::Value::deserialize(deserializer)?;
+        if let serde_yaml::Value::Mapping(map) = &value
+            && map.len() == 1
+            && let Some(inner @ serde_yaml::Value::Mapping(_)) = map.get("Vps")
+        {
+            value = inner.clone();
+        }
+        if let serde_yaml::Value::Mapping(map) = &mut value
+            && !map.contains_key("kind")
+        {
+            map.insert("kind".into(), "vps".into());
+        }
+        match TaggedRes::deserialize(value).map_err(D::Error::custom)? {
+            TaggedRes::Vps(vps) => Ok(Self::Vps(vps)),
+            TaggedRes::K8s(k8s) => Ok(Self::K8s(k8s)),
+        }
+    }
 }
 impl ResouceTypes {
     pub fn address(&self) -> ResAddress {
         match self {
             ResouceTypes::Vps(vps) => vps.address(),
+            ResouceTypes::K8s(k8s) => k8s.address(),
         }
     }
+    pub fn res(&self) -> &CaculateResSpec {
+        match self {
+            ResouceTypes::Vps(vps) => vps.res(),
+            ResouceTypes::K8s(k8s) => k8s.res(),
+        }
+    }
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        match self {
+            ResouceTypes::Vps(vps) => vps.labels(),
+            ResouceTypes::K8s(k8s) => k8s.labels(),
+        }
+    }
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ResouceTypes::Vps(_) => "vps",
+            ResouceTypes::K8s(_) => "k8s",
+        }
+    }
+    // 导出时去掉枚举包装, 以 kind 区分类型
+    pub fn export(&self) -> SpecResult<ValueType> {
+        let value = match self {
+            ResouceTypes::Vps(vps) => export_value(vps)?,
+            ResouceTypes::K8s(k8s) => export_value(k8s)?,
+        };
+        let mut value = value;
+        if let ValueType::Obj(map) = &mut value {
+            map.insert("kind".to_string(), ValueType::from(self.kind()));
+            map.insert(
+                "address".to_string(),
+                ValueType::from(self.address().host().as_str()),
+            );
+        }
+        Ok(value)
+    }
+}
+impl From<K8sCluster> for ResouceTypes {
+    fn from(value: K8sCluster) -> Self {
+        Self::K8s(value)
+    }
 }
 impl From<Vps> for ResouceTypes {
     fn from(value: Vps) -> Self {
@@ -83,6 +245,12 @@ impl ResourceNode {
     pub fn add<R: Into<ResouceTypes>>(&mut self, res: R) {
         self.items.push(res.into())
     }
+    // 节点下各资源的合计
+    pub fn capacity(&self) -> CaculateResSpec {
+        self.items
+            .iter()
+            .fold(CaculateResSpec::default(), |acc, x| acc.add(x.res()))
+    }
     pub fn try_load(path: &PathBuf) -> SpecResult<Self> {
         let mut ctx = WithContext::want("load res node");
         ctx.with("path", format!("path: {}", path.display()));
@@ -106,31 +274,231 @@ impl ResourceNode {
             name: "localhost".to_string(),
             items: vec![ResouceTypes::Vps(Vps::new(
                 CaculateResSpec::new(cpu, mem),
-                vec![],
+                vec![Ipv4Addr::LOCALHOST],
             ))],
         }
     }
+    pub fn export(&self) -> SpecResult<ValueType> {
+        let mut map = ValueMap::new();
+        map.insert("name".to_string(), ValueType::from(self.name.as_str()));
+        let items = self
+            .items
+            .iter()
+            .map(|x| x.export())
+            .collect::<SpecResult<Vec<_>>>()?;
+        map.insert("items".to_string(), ValueType::List(items));
+        Ok(ValueType::Obj(map))
+    }
+}
+
+// resource.yml: 声明可用的资源节点
+#[derive(Getters, Clone, Debug, Default, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct SysResource {
+    nodes: Vec<ResourceNode>,
+}
+impl SysResource {
+    pub fn new(nodes: Vec<ResourceNode>) -> Self {
+        Self { nodes }
+    }
+    pub fn load(path: &Path) -> SpecResult<Self> {
+        let mut ctx = WithContext::want("load resource");
+        ctx.with_path("resource", path);
+        if !path.exists() {
+            return SpecReason::from(ElementReason::Miss(format!("resource: {}", path.display())))
+                .err_result()
+                .with(&ctx);
+        }
+        Self::from_conf(path).with(&ctx)
+    }
+    pub fn total(&self) -> CaculateResSpec {
+        self.nodes
+            .iter()
+            .fold(CaculateResSpec::default(), |acc, x| acc.add(&x.capacity()))
+    }
+    pub fn find(&self, name: &str) -> Option<&ResourceNode> {
+        self.nodes.iter().find(|x| x.name() == name)
+    }
+    pub fn export(&self) -> SpecResult<ValueType> {
+        Ok(ValueType::List(
+            self.nodes
+                .iter()
+                .map(|x| x.export())
+                .collect::<SpecResult<Vec<_>>>()?,
+        ))
+    }
+}
+
+#[derive(Clone, Getters, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SshConn {
+    user: String,
+    #[serde(default = "SshConn::default_port")]
+    port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+}
+impl SshConn {
+    pub fn new<S: Into<String>>(user: S) -> Self {
+        Self {
+            user: user.into(),
+            port: Self::default_port(),
+            key: None,
+        }
+    }
+    fn default_port() -> u16 {
+        22
+    }
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+    pub fn with_key<S: Into<String>>(mut self, key: S) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+}
+
+// 磁盘卷, size 单位 G
+#[derive(Clone, Getters, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DiskVolume {
+    mount: String,
+    size: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    device: Option<String>,
+}
+impl DiskVolume {
+    pub fn new<S: Into<String>>(mount: S, size: u32) -> Self {
+        Self {
+            mount: mount.into(),
+            size,
+            device: None,
+        }
+    }
+    pub fn with_device<S: Into<String>>(mut self, device: S) -> Self {
+        self.device = Some(device.into());
+        self
+    }
 }
 
 #[derive(Clone, Getters, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Vps {
-    ips: Vec<Ipv4Addr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hostname: Option<String>,
+    #[serde(default)]
+    ips: Vec<IpAddr>,
     res: CaculateResSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ssh: Option<SshConn>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    disks: Vec<DiskVolume>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
 }
 
 impl Vps {
-    pub fn new(res_spec: CaculateResSpec, mut ip: Vec<Ipv4Addr>) -> Self {
-        let mut ip_list = vec![Ipv4Addr::new(127, 0, 0, 1)];
-        ip_list.append(&mut ip);
+    pub fn new<I: Into<IpAddr>>(res_spec: CaculateResSpec, ip: Vec<I>) -> Self {
         Self {
+            hostname: None,
+            ips: ip.into_iter().map(Into::into).collect(),
             res: res_spec,
-            ips: ip_list,
+            ssh: None,
+            disks: Vec::new(),
+            labels: BTreeMap::new(),
         }
     }
+    pub fn with_hostname<S: Into<String>>(mut self, hostname: S) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+    pub fn with_ssh(mut self, ssh: SshConn) -> Self {
+        self.ssh = Some(ssh);
+        self
+    }
+    pub fn with_disk(mut self, disk: DiskVolume) -> Self {
+        self.disks.push(disk);
+        self
+    }
+    pub fn with_label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+    pub fn disk_size(&self) -> u32 {
+        self.disks.iter().map(|x| x.size).sum()
+    }
 }
 impl CaculateResource for Vps {
+    // 优先使用首个 ip, 其次 hostname
     fn address(&self) -> ResAddress {
-        ResAddress::Ipv4(*self.ips.first().unwrap())
+        if let Some(ip) = self.ips.first() {
+            return ResAddress::from(*ip);
+        }
+        match &self.hostname {
+            Some(host) => ResAddress::Host(host.clone()),
+            None => ResAddress::Ipv4(Ipv4Addr::LOCALHOST),
+        }
+    }
+}
+
+#[derive(Clone, Getters, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct K8sCluster {
+    name: String,
+    api_server: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kubeconfig: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    res: CaculateResSpec,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+}
+impl K8sCluster {
+    pub fn new<S: Into<String>, A: Into<String>>(
+        name: S,
+        api_server: A,
+        res: CaculateResSpec,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            api_server: api_server.into(),
+            kubeconfig: None,
+            context: None,
+            res,
+            labels: BTreeMap::new(),
+        }
+    }
+    pub fn with_kubeconfig<S: Into<String>>(mut self, kubeconfig: S) -> Self {
+        self.kubeconfig = Some(kubeconfig.into());
+        self
+    }
+    pub fn with_context<S: Into<String>>(mut self, context: S) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+    pub fn with_label<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+}
+impl CaculateResource for K8sCluster {
+    // api_server 形如 https://host:6443
+    fn address(&self) -> ResAddress {
+        let host = self
+            .api_server
+            .split_once("://")
+            .map(|(_, x)| x)
+            .unwrap_or(&self.api_server);
+        let host = host.split('/').next().unwrap_or(host);
+        let host = match host.rsplit_once(':') {
+            Some((h, port)) if port.chars().all(|c| c.is_ascii_digit()) => h,
+            _ => host,
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        match host.parse::<IpAddr>() {
+            Ok(ip) => ResAddress::from(ip),
+            Err(_) => ResAddress::Host(host.to_string()),
+        }
     }
 }
 
@@ -179,4 +547,97 @@ mod tests {
         // 验证数据完整性
         assert_eq!(root.name(), loaded.name());
     }
+
+    #[test]
+    fn test_inventory_load() {
+        let content = r#"
+- name: db
+  items:
+    - hostname: db1.local
+      ips: ["fd00::11", "10.0.0.11"]
+      res: { core_cnt: 8, mem_size: 32, gpu_cnt: 1 }
+      ssh: { user: ops, key: ~/.ssh/id_ed25519 }
+      disks:
+        - { mount: /data, size: 500, device: /dev/vdb }
+      labels: { role: db }
+- name: edge
+  items:
+    - hostname: edge.local
+      res: { core_cnt: 2, mem_size: 4 }
+    - kind: k8s
+      name: prod
+      api_server: https://10.0.1.1:6443
+      res: { core_cnt: 32, mem_size: 128 }
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("resource.yml");
+        fs::write(&path, content).unwrap();
+        let resource = SysResource::load(&path).unwrap();
+        assert_eq!(resource.total(), CaculateResSpec::new(42, 164).with_gpu(1));
+        let db = &resource.find("db").unwrap().items()[0];
+        assert_eq!(db.address(), ResAddress::Ipv6("fd00::11".parse().unwrap()));
+        assert_eq!(db.labels().get("role"), Some(&"db".to_string()));
+        let ResouceTypes::Vps(vps) = db else {
+            panic!("not vps")
+        };
+        assert_eq!(vps.ssh().as_ref().map(|x| *x.port()), Some(22));
+        assert_eq!(vps.disk_size(), 500);
+
+        let edge = resource.find("edge").unwrap();
+        assert_eq!(edge.items()[0].address().host(), "edge.local");
+        assert_eq!(
+            edge.items()[1].address(),
+            ResAddress::Ipv4(Ipv4Addr::new(10, 0, 1, 1))
+        );
+
+        let ValueType::List(nodes) = resource.export().unwrap() else {
+            panic!("not list")
+        };
+        let ValueType::Obj(edge) = &nodes[1] else {
+            panic!("not obj")
+        };
+        let ValueType::List(items) = &edge["items"] else {
+            panic!("not list")
+        };
+        let ValueType::Obj(k8s) = &items[1] else {
+            panic!("not obj")
+        };
+        assert_eq!(k8s["kind"], ValueType::from("k8s"));
+        assert_eq!(k8s["address"], ValueType::from("10.0.1.1"));
+    }
+
+    #[test]
+    fn test_resource_kind() {
+        // 旧格式 `Vps: {..}` 与缺省 kind 都按 vps 读取
+        let content = r#"
+- name: old
+  items:
+    - Vps:
+        ips: ["10.0.0.1"]
+        res: { core_cnt: 2, mem_size: 4 }
+    - ips: ["10.0.0.2"]
+      res: { core_cnt: 2, mem_size: 4 }
+"#;
+        let nodes: Vec<ResourceNode> = serde_yaml::from_str(content).unwrap();
+        assert!(
+            nodes[0]
+                .items()
+                .iter()
+                .all(|x| matches!(x, ResouceTypes::Vps(_)))
+        );
+        let text = serde_yaml::to_string(&nodes).unwrap();
+        assert!(text.contains("kind: vps"));
+        let reloaded: Vec<ResourceNode> = serde_yaml::from_str(&text).unwrap();
+        assert_eq!(reloaded[0].capacity(), CaculateResSpec::new(4, 8));
+
+        // 字段写错时报出具体字段, 而不是笼统的 untagged 错误
+        let err = serde_yaml::from_str::<ResouceTypes>("kind: k8s\nname: a\napi: x\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown field `api`"), "{}", err);
+        let err = serde_yaml::from_str::<ResouceTypes>("kind: lxc\n")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown variant `lxc`"), "{}", err);
+    }
 }
@@ -0,0 +1,318 @@
+mod provider;
+
+pub use provider::{
+    EncryptedFileSecret, EnvSecret, FileSecret, GEN_SECRET_SCHEME, GeneratedSecret, SecretProvider,
+};
+
+use std::sync::Arc;
+
+use log::debug;
+use orion_error::ErrorWith;
+
+use crate::error::{LocalizeReason, SpecReason, SpecResult, ToErr};
+use crate::vars::{ValueDict, ValueType};
+
+pub const SECRET_PREFIX: &str = "secret:";
+const SECRET_REF_BEG: &str = "${secret:";
+
+// 引用格式: ${secret:<scheme>:<key>}, 如 ${secret:env:DB_PASS}
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretRef {
+    scheme: String,
+    key: String,
+}
+
+impl SecretRef {
+    pub fn new<S: Into<String>, K: Into<String>>(scheme: S, key: K) -> Self {
+        Self {
+            scheme: scheme.into(),
+            key: key.into(),
+        }
+    }
+    // 解析去掉 ${ } 之后的变量名, 如 secret:file:/etc/db.pass
+    pub fn parse(var_name: &str) -> Option<Self> {
+        let body = var_name.strip_prefix(SECRET_PREFIX)?;
+        let (scheme, key) = body.split_once(':')?;
+        if scheme.is_empty() || key.is_empty() {
+            return None;
+        }
+        Some(Self {
+            scheme: scheme.to_string(),
+            key: key.to_string(),
+        })
+    }
+    pub fn scheme(&self) -> &str {
+        self.scheme.as_str()
+    }
+    pub fn key(&self) -> &str {
+        self.key.as_str()
+    }
+}
+
+impl std::fmt::Display for SecretRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${{{}{}:{}}}", SECRET_PREFIX, self.scheme, self.key)
+    }
+}
+
+pub fn has_secret_ref(value: &str) -> bool {
+    value.contains(SECRET_REF_BEG)
+}
+
+// _used.yml, 日志与报告中只出现引用本身, 不出现解析后的值
+pub fn redact(value: &str) -> String {
+    replace_refs(value, |_| Ok("******".to_string())).unwrap_or_else(|_| value.to_string())
+}
+pub fn redact_value(value: ValueType) -> ValueType {
+    match value {
+        ValueType::String(v) if has_secret_ref(v.as_str()) => ValueType::String(redact(&v)),
+        ValueType::List(items) => ValueType::List(items.into_iter().map(redact_value).collect()),
+        ValueType::Obj(map) => {
+            ValueType::Obj(map.into_iter().map(|(k, v)| (k, redact_value(v))).collect())
+        }
+        _ => value,
+    }
+}
+
+// 生成的随机值替换为 ${secret:gen:KEY}, 落盘的 _used.* 中不出现明文
+pub fn conceal_generated(value: ValueType, generated: &ValueDict) -> ValueType {
+    match value {
+        ValueType::String(mut v) => {
+            for (key, plain) in generated.iter() {
+                let plain = plain.to_string();
+                if !plain.is_empty() && v.contains(plain.as_str()) {
+                    let secret = SecretRef::new(GEN_SECRET_SCHEME, key.as_str()).to_string();
+                    v = v.replace(plain.as_str(), secret.as_str());
+                }
+            }
+            ValueType::String(v)
+        }
+        ValueType::List(items) => ValueType::List(
+            items
+                .into_iter()
+                .map(|x| conceal_generated(x, generated))
+                .collect(),
+        ),
+        ValueType::Obj(map) => ValueType::Obj(
+            map.into_iter()
+                .map(|(k, v)| (k, conceal_generated(v, generated)))
+                .collect(),
+        ),
+        _ => value,
+    }
+}
+
+fn replace_refs<F>(value: &str, mut fetch: F) -> SpecResult<String>
+where
+    F: FnMut(&SecretRef) -> SpecResult<String>,
+{
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(beg) = rest.find(SECRET_REF_BEG) {
+        out.push_str(&rest[..beg]);
+        let body = &rest[beg + 2..];
+        match body.find('}') {
+            Some(end) => {
+                let var_name = &body[..end];
+                match SecretRef::parse(var_name) {
+                    Some(secret) => out.push_str(fetch(&secret)?.as_str()),
+                    None => {
+                        return SpecReason::from(LocalizeReason::Secret(format!(
+                            "bad secret ref: {}",
+                            var_name
+                        )))
+                        .err_result();
+                    }
+                }
+                rest = &body[end + 1..];
+            }
+            None => {
+                out.push_str(&rest[beg..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[derive(Clone)]
+pub struct SecretResolver {
+    providers: Vec<Arc<dyn SecretProvider>>,
+}
+
+impl Default for SecretResolver {
+    fn default() -> Self {
+        Self {
+            providers: vec![
+                Arc::new(FileSecret),
+                Arc::new(EnvSecret),
+                Arc::new(EncryptedFileSecret::sops()),
+                Arc::new(EncryptedFileSecret::age()),
+            ],
+        }
+    }
+}
+
+impl std::fmt::Debug for SecretResolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let schemes: Vec<&str> = self.providers.iter().map(|x| x.scheme()).collect();
+        f.debug_struct("SecretResolver")
+            .field("providers", &schemes)
+            .finish()
+    }
+}
+
+impl SecretResolver {
+    pub fn empty() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+    // 后注册的 provider 优先
+    pub fn with_provider<P: SecretProvider + 'static>(mut self, provider: P) -> Self {
+        self.providers.insert(0, Arc::new(provider));
+        self
+    }
+    pub fn fetch(&self, secret: &SecretRef) -> SpecResult<String> {
+        let provider = self
+            .providers
+            .iter()
+            .find(|x| x.scheme() == secret.scheme())
+            .ok_or_else(|| {
+                SpecReason::from(LocalizeReason::Secret(format!(
+                    "unknow secret provider: {}",
+                    secret.scheme()
+                )))
+                .to_err()
+            })?;
+        debug!(target: "spec/secret", "resolve secret {}", secret);
+        provider.fetch(secret.key()).with(secret.to_string())
+    }
+    pub fn resolve_text(&self, value: &str) -> SpecResult<String> {
+        replace_refs(value, |secret| self.fetch(secret))
+    }
+    // 仅在渲染时调用, 解析结果不落盘
+    pub fn resolve_json(&self, data: serde_json::Value) -> SpecResult<serde_json::Value> {
+        use serde_json::Value;
+        match data {
+            Value::String(s) if has_secret_ref(s.as_str()) => {
+                Ok(Value::String(self.resolve_text(s.as_str())?))
+            }
+            Value::Array(items) => Ok(Value::Array(
+                items
+                    .into_iter()
+                    .map(|x| self.resolve_json(x))
+                    .collect::<SpecResult<Vec<_>>>()?,
+            )),
+            Value::Object(map) => {
+                let mut out = serde_json::Map::new();
+                for (k, v) in map {
+                    out.insert(k, self.resolve_json(v)?);
+                }
+                Ok(Value::Object(out))
+            }
+            _ => Ok(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ValueConfable;
+    use orion_error::TestAssert;
+
+    struct MockSecret;
+    impl SecretProvider for MockSecret {
+        fn scheme(&self) -> &str {
+            "mock"
+        }
+        fn fetch(&self, key: &str) -> SpecResult<String> {
+            Ok(format!("pass-of-{}", key))
+        }
+    }
+
+    #[test]
+    fn test_secret_ref_parse() {
+        let secret = SecretRef::parse("secret:env:DB_PASS").unwrap();
+        assert_eq!(secret.scheme(), "env");
+        assert_eq!(secret.key(), "DB_PASS");
+        assert_eq!(secret.to_string(), "${secret:env:DB_PASS}");
+        let secret = SecretRef::parse("secret:file:/etc/db.pass").unwrap();
+        assert_eq!(secret.key(), "/etc/db.pass");
+        assert_eq!(SecretRef::parse("secret:env"), None);
+        assert_eq!(SecretRef::parse("DB_PASS"), None);
+    }
+
+    #[test]
+    fn test_resolve_json() {
+        let resolver = SecretResolver::empty().with_provider(MockSecret);
+        let data = serde_json::json!({
+            "DB_USER": "root",
+            "DB_PASS": "${secret:mock:db}",
+            "DSN": ["root:${secret:mock:db}@localhost"],
+        });
+        let resolved = resolver.resolve_json(data).assert();
+        assert_eq!(resolved["DB_USER"], "root");
+        assert_eq!(resolved["DB_PASS"], "pass-of-db");
+        assert_eq!(resolved["DSN"][0], "root:pass-of-db@localhost");
+    }
+
+    #[test]
+    fn test_resolve_unknow_provider() {
+        let resolver = SecretResolver::empty();
+        assert!(resolver.resolve_text("${secret:vault:db}").is_err());
+    }
+
+    #[test]
+    fn test_file_secret() {
+        let temp_dir = tempfile::tempdir().assert();
+        let pass_file = temp_dir.path().join("db.pass");
+        std::fs::write(&pass_file, "s3cret\n").assert();
+        let resolver = SecretResolver::default();
+        let text = format!("${{secret:file:{}}}", pass_file.display());
+        assert_eq!(resolver.resolve_text(text.as_str()).assert(), "s3cret");
+    }
+
+    #[test]
+    fn test_redact_value() {
+        let value: ValueType = serde_yaml::from_str(
+            "{ user: root, pass: '${secret:env:DB_PASS}', dsn: ['x:${secret:file:/p}'] }",
+        )
+        .unwrap();
+        let ValueType::Obj(map) = redact_value(value) else {
+            panic!("not obj")
+        };
+        assert_eq!(map["user"], ValueType::from("root"));
+        assert_eq!(map["pass"], ValueType::from("******"));
+        assert_eq!(
+            map["dsn"],
+            ValueType::List(vec![ValueType::from("x:******")])
+        );
+    }
+
+    #[test]
+    fn test_conceal_generated() {
+        let temp_dir = tempfile::tempdir().assert();
+        let gen_file = temp_dir.path().join("_generated.yml");
+        std::fs::write(&gen_file, "DB_PASS: Xk29abc\n").assert();
+        let generated = ValueDict::from_valconf(&gen_file).assert();
+        let value = conceal_generated(ValueType::from("root:Xk29abc@db"), &generated);
+        assert_eq!(value, ValueType::from("root:${secret:gen:DB_PASS}@db"));
+        let resolver = SecretResolver::empty().with_provider(GeneratedSecret::new(gen_file));
+        assert_eq!(
+            resolver.resolve_text(value.to_string().as_str()).assert(),
+            "root:Xk29abc@db"
+        );
+    }
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(
+            redact("root:${secret:env:DB_PASS}@localhost"),
+            "root:******@localhost"
+        );
+        assert_eq!(redact("no secret"), "no secret");
+    }
+}
@@ -0,0 +1,162 @@
+use std::{path::PathBuf, process::Command};
+
+use home::home_dir;
+use orion_error::{ErrorOwe, ErrorWith, WithContext};
+
+use crate::error::{LocalizeReason, SpecReason, SpecResult, ToErr};
+use crate::types::ValueConfable;
+use crate::vars::ValueDict;
+
+pub const GEN_SECRET_SCHEME: &str = "gen";
+
+pub trait SecretProvider: Send + Sync {
+    // 引用中的 scheme, 如 file/env/sops/age
+    fn scheme(&self) -> &str;
+    fn fetch(&self, key: &str) -> SpecResult<String>;
+}
+
+// ${secret:file:/path/to/pass}
+pub struct FileSecret;
+impl SecretProvider for FileSecret {
+    fn scheme(&self) -> &str {
+        "file"
+    }
+    fn fetch(&self, key: &str) -> SpecResult<String> {
+        let path = PathBuf::from(key);
+        let content = std::fs::read_to_string(&path).owe_res().with(&path)?;
+        Ok(content.trim_end_matches(['\r', '\n']).to_string())
+    }
+}
+
+// ${secret:env:DB_PASS}
+pub struct EnvSecret;
+impl SecretProvider for EnvSecret {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+    fn fetch(&self, key: &str) -> SpecResult<String> {
+        std::env::var(key).map_err(|_| {
+            SpecReason::from(LocalizeReason::Secret(format!("env not exists: {}", key))).to_err()
+        })
+    }
+}
+
+// ${secret:gen:DB_PASS}: expr 生成的随机值, 保存在模块的 _generated.yml
+pub struct GeneratedSecret {
+    file: PathBuf,
+}
+impl GeneratedSecret {
+    pub fn new(file: PathBuf) -> Self {
+        Self { file }
+    }
+}
+impl SecretProvider for GeneratedSecret {
+    fn scheme(&self) -> &str {
+        GEN_SECRET_SCHEME
+    }
+    fn fetch(&self, key: &str) -> SpecResult<String> {
+        let dict = ValueDict::from_valconf(&self.file).with(&self.file)?;
+        match dict.get(key) {
+            Some(v) => Ok(v.to_string()),
+            None => SpecReason::from(LocalizeReason::Secret(format!(
+                "generated value not exists: {}",
+                key
+            )))
+            .err_result(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum CryptTool {
+    Sops,
+    Age,
+}
+
+// ${secret:sops:/path/secrets.yml#DB_PASS} 或 ${secret:age:/path/db.pass.age}
+// 调用本机的 sops / age 命令解密, 解密结果只保存在内存中
+pub struct EncryptedFileSecret {
+    tool: CryptTool,
+}
+
+impl EncryptedFileSecret {
+    pub fn sops() -> Self {
+        Self {
+            tool: CryptTool::Sops,
+        }
+    }
+    pub fn age() -> Self {
+        Self {
+            tool: CryptTool::Age,
+        }
+    }
+    fn age_identity() -> Option<PathBuf> {
+        if let Ok(key_file) = std::env::var("SOPS_AGE_KEY_FILE") {
+            return Some(PathBuf::from(key_file));
+        }
+        home_dir().map(|x| x.join(".config/sops/age/keys.txt"))
+    }
+    fn decrypt(&self, path: &str) -> SpecResult<String> {
+        let mut ctx = WithContext::want("decrypt secret file");
+        ctx.with("file", path);
+        let mut cmd = match self.tool {
+            CryptTool::Sops => {
+                let mut cmd = Command::new("sops");
+                cmd.arg("--decrypt").arg(path);
+                cmd
+            }
+            CryptTool::Age => {
+                let mut cmd = Command::new("age");
+                cmd.arg("--decrypt");
+                if let Some(identity) = Self::age_identity() {
+                    cmd.arg("--identity").arg(identity);
+                }
+                cmd.arg(path);
+                cmd
+            }
+        };
+        let output = cmd.output().owe_sys().with(&ctx)?;
+        if !output.status.success() {
+            return SpecReason::from(LocalizeReason::Secret(format!(
+                "{} decrypt failed: {}",
+                self.scheme(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )))
+            .err_result()
+            .with(&ctx);
+        }
+        String::from_utf8(output.stdout).owe_data().with(&ctx)
+    }
+}
+
+impl SecretProvider for EncryptedFileSecret {
+    fn scheme(&self) -> &str {
+        match self.tool {
+            CryptTool::Sops => "sops",
+            CryptTool::Age => "age",
+        }
+    }
+    fn fetch(&self, key: &str) -> SpecResult<String> {
+        let (path, item) = match key.split_once('#') {
+            Some((path, item)) => (path, Some(item)),
+            None => (key, None),
+        };
+        let plain = self.decrypt(path)?;
+        match item {
+            Some(item) => {
+                let doc: serde_yaml::Value = serde_yaml::from_str(plain.as_str()).owe_data()?;
+                match doc.get(item) {
+                    Some(serde_yaml::Value::String(v)) => Ok(v.clone()),
+                    Some(serde_yaml::Value::Number(v)) => Ok(v.to_string()),
+                    Some(serde_yaml::Value::Bool(v)) => Ok(v.to_string()),
+                    _ => SpecReason::from(LocalizeReason::Secret(format!(
+                        "secret item not exists: {}",
+                        item
+                    )))
+                    .err_result(),
+                }
+            }
+            None => Ok(plain.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    }
+}
@@ -79,10 +79,8 @@ pub enum Constraint {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
 
     use orion_error::ErrorOwe;
-    use tempfile::env::temp_dir;
 
     use crate::{
         addr::LocalAddr,
@@ -104,7 +102,8 @@ mod tests {
     // 序列化测试
     #[test]
     fn confspec_save_load() -> SpecResult<()> {
-        let root_path = PathBuf::from("./example/spec/redis");
+        let temp = tempfile::tempdir().owe_res()?;
+        let root_path = temp.path().join("redis");
         std::fs::create_dir_all(&root_path).owe_res()?;
         let mut redis = ConfSpec::new("1.0", CONFS_DIR);
         redis.add(ConfFile::new("./nginx.conf"));
@@ -128,11 +127,10 @@ mod tests {
     // 序列化测试
     #[test]
     fn software_save_load() -> SpecResult<()> {
-        //let root_path = PathBuf::from("./example/spec/redis");
-        let root_path = temp_dir();
-        std::fs::create_dir_all(&root_path).owe_res()?;
-
-        let conf_path = "./example/spec/redis/config_spec.yml";
+        let temp = tempfile::tempdir().owe_res()?;
+        let root_path = temp.path().to_path_buf();
+        let conf_path = root_path.join("config_spec.yml");
+        ConfSpec::new("1.0", CONFS_DIR).save_conf(&conf_path)?;
 
         let artifact = Artifact::new(
             "redis-7.0.1",
@@ -142,7 +140,7 @@ mod tests {
         let redis = SoftWare::new(
             artifact,
             WorkSpec::new("redis"),
-            ConfSpecRef::new(conf_path)?,
+            ConfSpecRef::new(conf_path.to_str().unwrap_or_default())?,
         );
 
         let path = root_path.join("redis_7.yml");
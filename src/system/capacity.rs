@@ -0,0 +1,312 @@
+use crate::predule::*;
+use std::fmt::Display;
+
+use super::spec::{SysModelSpec, SysTargetPaths};
+use crate::{
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::{ModelSTD, RunSPC},
+    resource::{CaculateResSpec, ResourceNode, SysResource},
+    vars::RuntimeContext,
+};
+
+// 单个模块 target 的资源需求
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct ModResDemand {
+    module: String,
+    target: ModelSTD,
+    res: CaculateResSpec,
+}
+impl ModResDemand {
+    pub fn new<S: Into<String>>(module: S, target: ModelSTD, res: CaculateResSpec) -> Self {
+        Self {
+            module: module.into(),
+            target,
+            res,
+        }
+    }
+}
+// k8s target 只能放到 k8s 集群, 其他 target 放到 vps, 与 ResouceTypes::kind 对应
+pub(crate) fn target_kind(target: &ModelSTD) -> &'static str {
+    match target.spc() {
+        RunSPC::K8S => "k8s",
+        _ => "vps",
+    }
+}
+
+impl Display for ModResDemand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}({}) {}", self.module, self.target, self.res)
+    }
+}
+
+// 节点上某一类资源的使用情况
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct NodeUsage {
+    node: String,
+    kind: String,
+    capacity: CaculateResSpec,
+    used: CaculateResSpec,
+    modules: Vec<String>,
+}
+impl NodeUsage {
+    fn new(node: &ResourceNode, kind: &str) -> Option<Self> {
+        let items: Vec<_> = node.items().iter().filter(|x| x.kind() == kind).collect();
+        if items.is_empty() {
+            return None;
+        }
+        Some(Self {
+            node: node.name().clone(),
+            kind: kind.to_string(),
+            capacity: items
+                .iter()
+                .fold(CaculateResSpec::default(), |acc, x| acc.add(x.res())),
+            used: CaculateResSpec::default(),
+            modules: Vec::new(),
+        })
+    }
+    pub fn free(&self) -> CaculateResSpec {
+        self.capacity.sub(&self.used).unwrap_or_default()
+    }
+    fn place(&mut self, demand: &ModResDemand) -> bool {
+        if !self.free().covers(demand.res()) {
+            return false;
+        }
+        self.used = self.used.add(demand.res());
+        self.modules.push(demand.module().clone());
+        true
+    }
+}
+
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct CapacityReport {
+    demands: Vec<ModResDemand>,
+    nodes: Vec<NodeUsage>,
+    unplaced: Vec<ModResDemand>,
+    total_demand: CaculateResSpec,
+    total_capacity: CaculateResSpec,
+}
+impl CapacityReport {
+    // 需求大的先放, 每次选同类资源中剩余最多且放得下的节点
+    pub fn plan(demands: Vec<ModResDemand>, resource: &SysResource) -> Self {
+        let mut nodes: Vec<NodeUsage> = resource
+            .nodes()
+            .iter()
+            .flat_map(|x| ["vps", "k8s"].map(|kind| NodeUsage::new(x, kind)))
+            .flatten()
+            .collect();
+        let mut sorted = demands.clone();
+        sorted.sort_by(|a, b| {
+            (b.res().core_cnt(), b.res().mem_size()).cmp(&(a.res().core_cnt(), a.res().mem_size()))
+        });
+        let mut unplaced = Vec::new();
+        for demand in sorted {
+            let target = nodes
+                .iter_mut()
+                .filter(|x| x.kind == target_kind(demand.target()))
+                .filter(|x| x.free().covers(demand.res()))
+                .max_by_key(|x| {
+                    let free = x.free();
+                    (*free.core_cnt(), *free.mem_size())
+                });
+            match target {
+                Some(node) => {
+                    node.place(&demand);
+                }
+                None => unplaced.push(demand),
+            }
+        }
+        let total_demand = demands
+            .iter()
+            .fold(CaculateResSpec::default(), |acc, x| acc.add(x.res()));
+        Self {
+            demands,
+            nodes,
+            unplaced,
+            total_demand,
+            total_capacity: resource.total(),
+        }
+    }
+    // 模块被放到的节点
+    pub fn placement(&self, module: &str) -> Option<&NodeUsage> {
+        self.nodes
+            .iter()
+            .find(|x| x.modules.iter().any(|m| m == module))
+    }
+    pub fn is_over_commit(&self) -> bool {
+        !self.total_capacity.covers(&self.total_demand) || !self.unplaced.is_empty()
+    }
+}
+
+impl Display for CapacityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<20} {:<24} {:<12}", "module", "target", "res")?;
+        for demand in &self.demands {
+            writeln!(
+                f,
+                "{:<20} {:<24} {:<12}",
+                demand.module(),
+                demand.target().to_string(),
+                demand.res().to_string()
+            )?;
+        }
+        writeln!(
+            f,
+            "\ndemand: {}  capacity: {}",
+            self.total_demand, self.total_capacity
+        )?;
+        writeln!(
+            f,
+            "\n{:<20} {:<6} {:<12} {:<12} modules",
+            "node", "kind", "used", "capacity"
+        )?;
+        for node in &self.nodes {
+            writeln!(
+                f,
+                "{:<20} {:<6} {:<12} {:<12} {}",
+                node.node(),
+                node.kind(),
+                node.used().to_string(),
+                node.capacity().to_string(),
+                node.modules().join(",")
+            )?;
+        }
+        for demand in &self.unplaced {
+            writeln!(f, "unplaced: {}", demand)?;
+        }
+        if self.is_over_commit() {
+            write!(f, "\nover-commit!")?;
+        } else {
+            write!(f, "\nok")?;
+        }
+        Ok(())
+    }
+}
+
+impl SysModelSpec {
+    // 已启用模块实际部署的 target (首个选中的) 的资源需求, 多 target 不累加
+    pub fn res_demands(&self, runtime: &RuntimeContext) -> SpecResult<Vec<ModResDemand>> {
+        let mut demands = Vec::new();
+        for m in self.mod_list().mods().iter().filter(|x| x.is_enable()) {
+            if let Some(spec) = m.target_specs(runtime)?.into_iter().next() {
+                demands.push(ModResDemand::new(
+                    m.name(),
+                    spec.model().clone(),
+                    spec.res_spec().clone(),
+                ));
+            }
+        }
+        Ok(demands)
+    }
+    pub fn load_resource(&self) -> SpecResult<SysResource> {
+        if let Some(resource) = self.resource() {
+            return Ok(resource.clone());
+        }
+        let root = self.local().clone().ok_or(
+            SpecReason::from(ElementReason::Miss(format!(
+                "sys {} local path",
+                self.name()
+            )))
+            .to_err(),
+        )?;
+        SysResource::load(SysTargetPaths::from(&root).res_path())
+    }
+    pub fn capacity(
+        &self,
+        resource: &SysResource,
+        runtime: &RuntimeContext,
+    ) -> SpecResult<CapacityReport> {
+        Ok(CapacityReport::plan(self.res_demands(runtime)?, resource))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::Ipv4Addr, str::FromStr};
+
+    use orion_error::TestAssert;
+
+    use super::*;
+    use crate::{
+        resource::{K8sCluster, Vps},
+        types::Configable,
+    };
+
+    fn node(name: &str, core: u32, mem: u32) -> ResourceNode {
+        let mut node = ResourceNode::new(name);
+        node.add(Vps::new(
+            CaculateResSpec::new(core, mem),
+            vec![Ipv4Addr::new(10, 0, 0, 1)],
+        ));
+        node
+    }
+    fn demand(name: &str, core: u32, mem: u32) -> ModResDemand {
+        ModResDemand::new(
+            name,
+            ModelSTD::from_str("x86-ubt22-host").assert(),
+            CaculateResSpec::new(core, mem),
+        )
+    }
+
+    #[test]
+    fn test_capacity_plan() {
+        let resource = SysResource::new(vec![node("n1", 6, 12), node("n2", 8, 16)]);
+        let report = CapacityReport::plan(
+            vec![
+                demand("redis", 2, 4),
+                demand("mysql", 4, 8),
+                demand("nginx", 1, 2),
+            ],
+            &resource,
+        );
+        assert!(!report.is_over_commit());
+        assert_eq!(report.total_demand(), &CaculateResSpec::new(7, 14));
+        assert_eq!(report.total_capacity(), &CaculateResSpec::new(14, 28));
+        // mysql 先放到 n2, redis 放到剩余更多的 n1
+        assert!(report.nodes()[1].modules().contains(&"mysql".to_string()));
+        assert!(report.nodes()[0].modules().contains(&"redis".to_string()));
+    }
+
+    #[test]
+    fn test_capacity_over_commit() {
+        let resource = SysResource::new(vec![node("n1", 4, 8), node("n2", 4, 8)]);
+        // 总量足够, 但单个需求放不进任何节点
+        let report = CapacityReport::plan(vec![demand("mysql", 6, 8)], &resource);
+        assert!(report.is_over_commit());
+        assert_eq!(report.unplaced().len(), 1);
+        assert!(report.to_string().contains("over-commit"));
+    }
+
+    #[test]
+    fn test_capacity_kind() {
+        let mut cluster = ResourceNode::new("k8s");
+        cluster.add(K8sCluster::new(
+            "prod",
+            "https://10.0.0.9:6443",
+            CaculateResSpec::new(32, 64),
+        ));
+        let resource = SysResource::new(vec![node("n1", 4, 8), cluster]);
+        let k8s = ModResDemand::new(
+            "web",
+            ModelSTD::x86_ubt22_k8s(),
+            CaculateResSpec::new(8, 16),
+        );
+        let report = CapacityReport::plan(vec![demand("mysql", 6, 8), k8s], &resource);
+        // host 需求不能放到 k8s 集群, k8s 需求放到集群
+        assert_eq!(report.unplaced().len(), 1);
+        assert_eq!(report.unplaced()[0].module(), "mysql");
+        let cluster = report.nodes().iter().find(|x| x.kind() == "k8s").unwrap();
+        assert_eq!(cluster.modules(), &vec!["web".to_string()]);
+    }
+
+    #[test]
+    fn test_resource_load() {
+        let dir = tempfile::tempdir().assert();
+        let path = dir.path().join("resource.yml");
+        assert!(SysResource::load(&path).is_err());
+        SysResource::new(vec![node("n1", 4, 8)])
+            .save_conf(&path)
+            .assert();
+        let loaded = SysResource::load(&path).assert();
+        assert_eq!(loaded.total(), CaculateResSpec::new(4, 8));
+    }
+}
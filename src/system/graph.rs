@@ -0,0 +1,180 @@
+use derive_getters::Getters;
+use indexmap::IndexMap;
+use log::warn;
+use semver::Version;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::require::ModRequires,
+};
+
+// 写入 mod_order.yml, 供系统 workflow 按序处理模块
+#[derive(Getters, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModOrder {
+    order: Vec<String>,
+    // 同一批次内的模块互不依赖, 可并行
+    stages: Vec<Vec<String>>,
+}
+
+#[derive(Getters, Clone, Debug)]
+pub struct ModNode {
+    version: Option<Version>,
+    requires: ModRequires,
+}
+
+// 系统内模块的依赖图
+#[derive(Getters, Clone, Debug, Default)]
+pub struct ModGraph {
+    nodes: IndexMap<String, ModNode>,
+}
+
+impl ModGraph {
+    pub fn add<S: Into<String>>(
+        &mut self,
+        name: S,
+        version: Option<Version>,
+        requires: ModRequires,
+    ) {
+        self.nodes
+            .insert(name.into(), ModNode { version, requires });
+    }
+
+    // 检查 requires 的模块存在且版本满足
+    pub fn check(&self) -> SpecResult<()> {
+        for (name, node) in &self.nodes {
+            for req in node.requires.requires() {
+                let Some(dep) = self.nodes.get(req.name()) else {
+                    return SpecReason::from(ElementReason::Miss(format!(
+                        "module {} requires {}",
+                        name, req
+                    )))
+                    .err_result();
+                };
+                if req.version().is_none() {
+                    continue;
+                }
+                match &dep.version {
+                    Some(version) if req.is_match(version) => {}
+                    Some(version) => {
+                        return SpecReason::from(ElementReason::Version(format!(
+                            "module {} requires {}, found {}",
+                            name, req, version
+                        )))
+                        .err_result();
+                    }
+                    None => {
+                        warn!(target: "sys/graph", "module {} version unknown, skip check {}", req.name(), req);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // 被依赖者在前的拓扑序; 无约束的模块保持 mod_list 中的顺序
+    pub fn topo_order(&self) -> SpecResult<ModOrder> {
+        self.check()?;
+        let mut before: IndexMap<&str, Vec<&str>> = self
+            .nodes
+            .keys()
+            .map(|x| (x.as_str(), Vec::new()))
+            .collect();
+        for (name, node) in &self.nodes {
+            let deps = node
+                .requires
+                .requires()
+                .iter()
+                .map(|x| x.name().as_str())
+                .chain(node.requires.after().iter().map(|x| x.as_str()));
+            for dep in deps {
+                if dep != name && self.nodes.contains_key(dep) {
+                    before[name.as_str()].push(dep);
+                }
+            }
+        }
+        let mut order = ModOrder::default();
+        while !before.is_empty() {
+            let stage: Vec<String> = before
+                .iter()
+                .filter(|(_, deps)| deps.iter().all(|x| !before.contains_key(x)))
+                .map(|(name, _)| name.to_string())
+                .collect();
+            if stage.is_empty() {
+                let rest: Vec<&str> = before.keys().copied().collect();
+                return SpecReason::from(ElementReason::Cycle(format!(
+                    "module dependency cycle in: {}",
+                    rest.join(", ")
+                )))
+                .err_result();
+            }
+            before.retain(|name, _| !stage.iter().any(|x| x == name));
+            order.order.extend(stage.iter().cloned());
+            order.stages.push(stage);
+        }
+        Ok(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use orion_error::StructErrorTrait;
+
+    use crate::{error::SpecReason, module::require::ModRequire};
+
+    use super::*;
+
+    fn requires(reqs: &[&str], after: &[&str]) -> ModRequires {
+        ModRequires::new(
+            reqs.iter()
+                .map(|x| ModRequire::from_str(x).unwrap())
+                .collect(),
+            after.iter().map(|x| x.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn test_topo_order() {
+        let mut graph = ModGraph::default();
+        graph.add("app", None, requires(&["mysql@>=8.0", "redis"], &["nginx"]));
+        graph.add("nginx", None, requires(&[], &["monitor"]));
+        graph.add("mysql", Some(Version::new(8, 0, 3)), ModRequires::default());
+        graph.add("redis", Some(Version::new(7, 2, 0)), ModRequires::default());
+        let order = graph.topo_order().unwrap();
+        assert_eq!(order.order(), &vec!["nginx", "mysql", "redis", "app"]);
+        assert_eq!(order.stages().len(), 2);
+        assert_eq!(order.stages()[0], vec!["nginx", "mysql", "redis"]);
+    }
+
+    #[test]
+    fn test_graph_error() {
+        let mut graph = ModGraph::default();
+        graph.add("app", None, requires(&["mysql@^5.7"], &[]));
+        graph.add("mysql", Some(Version::new(8, 0, 3)), ModRequires::default());
+        let err = graph.topo_order().unwrap_err();
+        assert!(matches!(
+            err.get_reason(),
+            SpecReason::Element(ElementReason::Version(_))
+        ));
+
+        graph.add("app", None, requires(&["postgres"], &[]));
+        let err = graph.topo_order().unwrap_err();
+        assert!(matches!(
+            err.get_reason(),
+            SpecReason::Element(ElementReason::Miss(_))
+        ));
+
+        let mut graph = ModGraph::default();
+        graph.add("a", None, requires(&["b"], &[]));
+        graph.add("b", None, requires(&[], &["c"]));
+        graph.add("c", None, requires(&["a"], &[]));
+        graph.add("d", None, ModRequires::default());
+        let err = graph.topo_order().unwrap_err();
+        assert!(matches!(
+            err.get_reason(),
+            SpecReason::Element(ElementReason::Cycle(x)) if x.contains("a, b, c")
+        ));
+    }
+}
@@ -71,3 +71,17 @@ pub fn sys_init_gitignore(path: &Path) -> SpecResult<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sys_init_gitignore() {
+        let dir = tempfile::tempdir().unwrap();
+        sys_init_gitignore(dir.path()).unwrap();
+        let content = std::fs::read_to_string(dir.path().join(".gitignore")).unwrap();
+        // 随机生成的密钥不应提交
+        assert!(content.lines().any(|x| x.trim() == "_generated.yml"));
+    }
+}
@@ -1,17 +1,29 @@
+pub mod capacity;
+pub mod graph;
 pub mod init;
+pub mod ports;
 pub mod proj;
 pub mod refs;
 pub mod spec;
 use crate::predule::*;
-use std::{net::Ipv4Addr, path::PathBuf};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+};
 
-use crate::types::{Localizable, LocalizeOptions, ValuePath};
+use crate::error::{LocalizeReason, SpecReason, ToErr};
+use crate::resource::export_value;
+use crate::types::{Configable, Localizable, LocalizeOptions, ValuePath};
 use crate::vars::{ValueDict, ValueType};
 use async_trait::async_trait;
 use derive_more::Deref;
+use orion_error::{ErrorWith, WithContext};
+use ports::PortPlan;
 
+use crate::const_vars::MODS_DIR;
 use crate::module::refs::ModuleSpecRef;
 use crate::module::spec::ModuleSpec;
+use crate::module::target::TargetChoice;
 use crate::{
     error::SpecResult,
     resource::{ResouceTypes, Vps},
@@ -21,9 +33,11 @@ use crate::{
 #[derive(Getters, Clone, Debug, Default, Serialize, Deserialize, Deref)]
 #[serde(transparent)]
 pub struct ModulesList {
+    #[deref]
     mods: Vec<ModuleSpecRef>,
-    //#[serde(skip)]
-    //mod_map: HashMap<String, ModuleSpec>,
+    // add_mod 加入的内联模块, 保存时写入 inline_mods/<name>
+    #[serde(skip)]
+    inline_mods: Vec<ModuleSpec>,
 }
 impl ModulesList {
     pub fn add_ref(&mut self, spec_ref: ModuleSpecRef) {
@@ -42,7 +56,7 @@ impl ModulesList {
     pub fn set_mods_local(&mut self, spec_path: PathBuf) {
         self.mods
             .iter_mut()
-            .for_each(|x| x.set_local(spec_path.join("mods")));
+            .for_each(|x| x.set_local(spec_path.join(MODS_DIR)));
     }
 
     pub fn find(&self, arg: &str) -> Option<&ModuleSpecRef> {
@@ -51,34 +65,54 @@ impl ModulesList {
 }
 
 impl ModulesList {
-    pub async fn update(&self, sys_root: &Path, options: &UpdateOptions) -> SpecResult<()> {
+    pub async fn update(
+        &self,
+        sys_root: &Path,
+        options: &UpdateOptions,
+    ) -> SpecResult<Vec<TargetChoice>> {
+        let mut choices = Vec::new();
         for m in &self.mods {
             if m.is_enable() {
-                m.update(sys_root, options).await?;
+                choices.extend(m.update(sys_root, options).await?);
             }
         }
-        Ok(())
+        Ok(choices)
     }
     pub fn value_path(&self, parent: ValuePath) -> ValuePath {
         parent.join_all("mods")
     }
 }
-#[async_trait]
-impl Localizable for ModulesList {
-    async fn localize(
+impl ModulesList {
+    // 分配给各模块的端口作为其全局值的底层
+    pub async fn localize_with_ports(
         &self,
         dst_path: Option<ValuePath>,
         options: LocalizeOptions,
+        ports: &PortPlan,
     ) -> SpecResult<()> {
         let root = dst_path.map(|x| x.join_all("mods"));
         for m in &self.mods {
             if m.is_enable() {
-                m.localize(root.clone(), options.clone()).await?;
+                let cur_opt = options
+                    .clone()
+                    .with_global_base(ports.module_dict(m.name()));
+                m.localize(root.clone(), cur_opt).await?;
             }
         }
         Ok(())
     }
 }
+#[async_trait]
+impl Localizable for ModulesList {
+    async fn localize(
+        &self,
+        dst_path: Option<ValuePath>,
+        options: LocalizeOptions,
+    ) -> SpecResult<()> {
+        self.localize_with_ports(dst_path, options, &PortPlan::default())
+            .await
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum NoneValue<T> {
@@ -86,9 +120,18 @@ pub enum NoneValue<T> {
     Value(T),
 }
 impl ModulesList {
-    pub fn add_mod(&mut self, _modx: ModuleSpec) {
-        todo!();
-        //self.mod_map.insert(modx.name().clone(), modx);
+    // 同名模块被替换
+    pub fn add_mod(&mut self, modx: ModuleSpec) {
+        self.mods.retain(|x| x.name() != modx.name());
+        self.inline_mods.retain(|x| x.name() != modx.name());
+        self.mods.push(ModuleSpecRef::inline(&modx));
+        self.inline_mods.push(modx);
+    }
+    pub fn save_inline(&self, mods_root: &Path) -> SpecResult<()> {
+        for modx in &self.inline_mods {
+            modx.save_src(&mods_root.join(modx.name()))?;
+        }
+        Ok(())
     }
 }
 
@@ -132,14 +175,83 @@ impl From<Vec<Vps>> for ModelResource {
     }
 }
 
+// 网段, cidr 如 10.0.0.0/24 或 fd00::/64
+#[derive(Getters, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSpec {
+    name: String,
+    cidr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    gateway: Option<IpAddr>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dns: Vec<IpAddr>,
+}
+impl NetworkSpec {
+    pub fn new<S: Into<String>, C: Into<String>>(name: S, cidr: C) -> Self {
+        Self {
+            name: name.into(),
+            cidr: cidr.into(),
+            gateway: None,
+            dns: Vec::new(),
+        }
+    }
+    pub fn with_gateway(mut self, gateway: IpAddr) -> Self {
+        self.gateway = Some(gateway);
+        self
+    }
+    pub fn with_dns(mut self, dns: Vec<IpAddr>) -> Self {
+        self.dns = dns;
+        self
+    }
+    pub fn is_ipv6(&self) -> bool {
+        self.cidr.contains(':')
+    }
+    pub fn check(&self) -> SpecResult<()> {
+        let valid = self.cidr.split_once('/').is_some_and(|(addr, prefix)| {
+            let max = if self.is_ipv6() { 128 } else { 32 };
+            addr.parse::<IpAddr>().is_ok() && prefix.parse::<u8>().is_ok_and(|x| x <= max)
+        });
+        if !valid {
+            return SpecReason::from(LocalizeReason::Validate(format!(
+                "network {} bad cidr: {}",
+                self.name, self.cidr
+            )))
+            .err_result();
+        }
+        Ok(())
+    }
+}
+
+// net_res.yml
 #[derive(Getters, Clone, Debug, Serialize, Deserialize)]
 pub struct NetResSpace {
     master: Ipv4Addr,
     node_scope: (Ipv4Addr, Ipv4Addr),
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    networks: Vec<NetworkSpec>,
 }
 impl NetResSpace {
     pub fn new(master: Ipv4Addr, node_scope: (Ipv4Addr, Ipv4Addr)) -> Self {
-        Self { master, node_scope }
+        Self {
+            master,
+            node_scope,
+            networks: Vec::new(),
+        }
+    }
+    pub fn with_network(mut self, network: NetworkSpec) -> Self {
+        self.networks.push(network);
+        self
+    }
+    pub fn load(path: &Path) -> SpecResult<Self> {
+        let mut ctx = WithContext::want("load net res");
+        ctx.with_path("net_res", path);
+        let net_res = Self::from_conf(path).with(&ctx)?;
+        for network in &net_res.networks {
+            network.check().with(&ctx)?;
+        }
+        Ok(net_res)
+    }
+    pub fn export(&self) -> SpecResult<ValueType> {
+        export_value(self)
     }
 }
 pub struct NetAllocator {
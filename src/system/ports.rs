@@ -0,0 +1,432 @@
+use crate::predule::*;
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::path::Path;
+
+use derive_more::Deref;
+use log::warn;
+use orion_error::{ErrorWith, WithContext};
+
+use super::{
+    capacity::target_kind,
+    spec::{SysModelSpec, SysTargetPaths},
+};
+use crate::{
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::{
+        ModelSTD,
+        ports::{ModPorts, PortProto, port_var_name},
+    },
+    resource::{ResouceTypes, SysResource},
+    types::Configable,
+    vars::{RuntimeContext, ValueDict, ValueMap, ValueType},
+};
+
+// 未指定 place 的模块归入同一位置
+pub const DEFAULT_PLACE: &str = "default";
+
+// sys/ports.yml: 默认端口冲突时的分配范围
+#[derive(Getters, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SysPortConf {
+    range: (u16, u16),
+}
+impl Default for SysPortConf {
+    fn default() -> Self {
+        Self {
+            range: (30000, 39999),
+        }
+    }
+}
+impl SysPortConf {
+    pub fn new(start: u16, end: u16) -> Self {
+        Self {
+            range: (start, end),
+        }
+    }
+    pub fn load(path: &Path) -> SpecResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let mut ctx = WithContext::want("load port conf");
+        ctx.with_path("ports", path);
+        Self::from_conf(path).with(&ctx)
+    }
+}
+
+// 模块在某位置声明的端口
+#[derive(Getters, Clone, Debug)]
+pub struct PortDemand {
+    module: String,
+    place: String,
+    ports: ModPorts,
+}
+impl PortDemand {
+    pub fn new<S: Into<String>, P: Into<String>>(module: S, place: P, ports: ModPorts) -> Self {
+        Self {
+            module: module.into(),
+            place: place.into(),
+            ports,
+        }
+    }
+}
+
+#[derive(Getters, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PortAssign {
+    module: String,
+    place: String,
+    name: String,
+    #[serde(default)]
+    protocol: PortProto,
+    port: u16,
+    default: u16,
+}
+impl PortAssign {
+    pub fn var_name(&self) -> String {
+        port_var_name(&self.name)
+    }
+    // 默认端口被占用, 已改为范围内分配的端口
+    pub fn is_moved(&self) -> bool {
+        self.port != self.default
+    }
+    // 默认端口变化后不再沿用旧记录
+    fn is_same(&self, other: &PortAssign) -> bool {
+        self.module == other.module
+            && self.place == other.place
+            && self.name == other.name
+            && self.protocol == other.protocol
+            && self.default == other.default
+    }
+}
+impl Display for PortAssign {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<20} {:<12} {:<16} {:<5} {:<6}",
+            self.module,
+            self.place,
+            self.var_name(),
+            self.protocol.to_string(),
+            self.port
+        )?;
+        if self.is_moved() {
+            write!(f, " conflict, default {}", self.default)?;
+        }
+        Ok(())
+    }
+}
+
+// port_alloc.yml: 分配结果, 再次分配时优先沿用
+#[derive(Getters, Clone, Debug, Default, PartialEq, Serialize, Deserialize, Deref)]
+#[serde(transparent)]
+pub struct PortPlan {
+    items: Vec<PortAssign>,
+}
+impl PortPlan {
+    // 依次: 沿用上次记录, 使用默认端口, 从范围内分配; 同一 place 内 (协议, 端口) 唯一
+    pub fn plan(demands: &[PortDemand], conf: &SysPortConf, prev: &PortPlan) -> SpecResult<Self> {
+        let mut items: Vec<PortAssign> = demands
+            .iter()
+            .flat_map(|d| {
+                d.ports.iter().map(|p| PortAssign {
+                    module: d.module.clone(),
+                    place: d.place.clone(),
+                    name: p.name().clone(),
+                    protocol: *p.protocol(),
+                    port: 0,
+                    default: *p.port(),
+                })
+            })
+            .collect();
+        let mut taken: HashSet<(String, PortProto, u16)> = HashSet::new();
+        let mut take = |item: &mut PortAssign, port: u16| {
+            if taken.insert((item.place.clone(), item.protocol, port)) {
+                item.port = port;
+                true
+            } else {
+                false
+            }
+        };
+        for item in items.iter_mut() {
+            if let Some(old) = prev.iter().find(|x| x.is_same(item)) {
+                take(item, old.port);
+            }
+        }
+        for item in items.iter_mut().filter(|x| x.port == 0) {
+            let default = item.default;
+            take(item, default);
+        }
+        let (start, end) = conf.range;
+        for item in items.iter_mut().filter(|x| x.port == 0) {
+            if !(start..=end).any(|port| take(item, port)) {
+                return SpecReason::from(ElementReason::Miss(format!(
+                    "free port in {}-{} for {}.{} at {}",
+                    start, end, item.module, item.name, item.place
+                )))
+                .err_result();
+            }
+            warn!(target: "sys/ports", "{}.{} port {} conflict, use {}", item.module, item.name, item.default, item.port);
+        }
+        Ok(Self { items })
+    }
+    pub fn conflicts(&self) -> Vec<&PortAssign> {
+        self.items.iter().filter(|x| x.is_moved()).collect()
+    }
+    // 注入到模块 used value 的 PORT_<NAME>
+    pub fn module_dict(&self, module: &str) -> ValueDict {
+        let mut dict = ValueDict::new();
+        for item in self.items.iter().filter(|x| x.module == module) {
+            dict.insert(item.var_name(), ValueType::Int(item.port as u64));
+        }
+        dict
+    }
+    // 全局值 PORTS.<module>.PORT_<NAME>, 供其他模块引用
+    pub fn export(&self) -> ValueType {
+        let mut map = ValueMap::new();
+        for item in &self.items {
+            let entry = map
+                .entry(item.module.clone())
+                .or_insert_with(|| ValueType::Obj(ValueMap::new()));
+            if let ValueType::Obj(ports) = entry {
+                ports.insert(item.var_name(), ValueType::Int(item.port as u64));
+            }
+        }
+        ValueType::Obj(map)
+    }
+}
+
+// place 需为 resource.yml 中有同类资源的节点, 或 k8s 集群名 (仅 k8s target)
+fn check_place(
+    resource: &SysResource,
+    module: &str,
+    place: &str,
+    target: &ModelSTD,
+) -> SpecResult<()> {
+    let kind = target_kind(target);
+    let on_node = resource
+        .find(place)
+        .is_some_and(|x| x.items().iter().any(|r| r.kind() == kind));
+    let on_cluster = resource.nodes().iter().any(|x| {
+        x.items()
+            .iter()
+            .any(|r| matches!(r, ResouceTypes::K8s(k8s) if k8s.name() == place && r.kind() == kind))
+    });
+    if on_node || on_cluster {
+        return Ok(());
+    }
+    SpecReason::from(ElementReason::Miss(format!(
+        "place {} of mod {} ({}) in resource.yml",
+        place, module, kind
+    )))
+    .err_result()
+}
+
+impl SysModelSpec {
+    // 已启用模块各 target 的端口声明, 同名端口只保留一份;
+    // 有 resource.yml 时校验 place, 未指定 place 的模块按容量规划放到的节点
+    pub fn port_demands(&self, runtime: &RuntimeContext) -> SpecResult<Vec<PortDemand>> {
+        let report = match self.resource() {
+            Some(resource) => Some(self.capacity(resource, runtime)?),
+            None => None,
+        };
+        let mut demands = Vec::new();
+        for m in self.mod_list().mods().iter().filter(|x| x.is_enable()) {
+            let specs = m.target_specs(runtime)?;
+            let ports = specs
+                .iter()
+                .fold(ModPorts::default(), |acc, x| acc.merge(x.ports()));
+            if ports.is_empty() {
+                continue;
+            }
+            let place = match m.place() {
+                Some(place) => {
+                    if let (Some(resource), Some(spec)) = (self.resource(), specs.first()) {
+                        check_place(resource, m.name(), place, spec.model())?;
+                    }
+                    place.clone()
+                }
+                None => report
+                    .as_ref()
+                    .and_then(|x| x.placement(m.name()))
+                    .map(|x| x.node().clone())
+                    .unwrap_or(DEFAULT_PLACE.to_string()),
+            };
+            demands.push(PortDemand::new(m.name(), place, ports));
+        }
+        Ok(demands)
+    }
+    pub fn port_plan(&self, runtime: &RuntimeContext) -> SpecResult<PortPlan> {
+        let root = self.local().clone().ok_or(
+            SpecReason::from(ElementReason::Miss(format!(
+                "sys {} local path",
+                self.name()
+            )))
+            .to_err(),
+        )?;
+        let paths = SysTargetPaths::from(&root);
+        let conf = SysPortConf::load(paths.ports_path())?;
+        let prev = if paths.port_alloc_path().exists() {
+            PortPlan::from_conf(paths.port_alloc_path())?
+        } else {
+            PortPlan::default()
+        };
+        PortPlan::plan(&self.port_demands(runtime)?, &conf, &prev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use orion_error::TestAssert;
+
+    use crate::{
+        const_vars::{MODS_DIR, USED_JSON, VALUE_DIR},
+        module::{ports::PortSpec, spec::ModuleSpec},
+        resource::{CaculateResSpec, ResourceNode, Vps},
+        system::{ModulesList, init::SysIniter},
+        types::{Localizable, LocalizeOptions},
+        update::UpdateOptions,
+        workflow::act::SysWorkflows,
+    };
+
+    use super::*;
+
+    fn demand(module: &str, place: &str, ports: Vec<PortSpec>) -> PortDemand {
+        PortDemand::new(module, place, ModPorts::new(ports))
+    }
+
+    #[test]
+    fn test_port_plan() {
+        let demands = vec![
+            demand("web", DEFAULT_PLACE, vec![PortSpec::new("http", 8080)]),
+            demand(
+                "api",
+                DEFAULT_PLACE,
+                vec![
+                    PortSpec::new("http", 8080),
+                    PortSpec::new("dns", 8080).with_protocol(PortProto::Udp),
+                ],
+            ),
+            demand("admin", "node2", vec![PortSpec::new("http", 8080)]),
+        ];
+        let conf = SysPortConf::new(30000, 30010);
+        let plan = PortPlan::plan(&demands, &conf, &PortPlan::default()).unwrap();
+        // 同一位置同协议冲突; udp 与其他位置不冲突
+        assert_eq!(plan.conflicts().len(), 1);
+        assert_eq!(
+            plan.module_dict("api").get("PORT_HTTP"),
+            Some(&ValueType::Int(30000))
+        );
+        assert_eq!(
+            plan.module_dict("api").get("PORT_DNS"),
+            Some(&ValueType::Int(8080))
+        );
+        assert_eq!(
+            plan.module_dict("admin").get("PORT_HTTP"),
+            Some(&ValueType::Int(8080))
+        );
+
+        // 沿用上次记录: web 之前已分到 30005
+        let mut prev = plan.clone();
+        prev.items[0].port = 30005;
+        let plan = PortPlan::plan(&demands, &conf, &prev).unwrap();
+        assert_eq!(
+            plan.module_dict("web").get("PORT_HTTP"),
+            Some(&ValueType::Int(30005))
+        );
+        assert_eq!(
+            plan.module_dict("api").get("PORT_HTTP"),
+            Some(&ValueType::Int(30000))
+        );
+        // 默认端口改变后不沿用旧记录
+        let mut prev = plan.clone();
+        prev.items[0].default = 9090;
+        let moved = PortPlan::plan(&demands, &conf, &prev).unwrap();
+        assert_eq!(
+            moved.module_dict("web").get("PORT_HTTP"),
+            Some(&ValueType::Int(8080))
+        );
+        let ValueType::Obj(ports) = plan.export() else {
+            panic!("not obj")
+        };
+        assert!(ports.contains_key("admin"));
+    }
+
+    #[test]
+    fn test_port_range_exhausted() {
+        let demands = vec![
+            demand("a", DEFAULT_PLACE, vec![PortSpec::new("http", 80)]),
+            demand("b", DEFAULT_PLACE, vec![PortSpec::new("http", 80)]),
+            demand("c", DEFAULT_PLACE, vec![PortSpec::new("http", 80)]),
+        ];
+        let conf = SysPortConf::new(30000, 30000);
+        assert!(PortPlan::plan(&demands, &conf, &PortPlan::default()).is_err());
+    }
+
+    fn port_mod(name: &str) -> ModuleSpec {
+        let host = ModuleSpec::make_new(name)
+            .assert()
+            .targets()
+            .get(&ModelSTD::arm_mac14_host())
+            .cloned()
+            .unwrap()
+            .with_ports(ModPorts::new(vec![PortSpec::new("http", 8080)]));
+        ModuleSpec::init(name, vec![host])
+    }
+
+    fn used_port(root: &Path, module: &str) -> serde_json::Value {
+        let used = root
+            .join(MODS_DIR)
+            .join(module)
+            .join(ModelSTD::arm_mac14_host().to_string())
+            .join(VALUE_DIR)
+            .join(USED_JSON);
+        let used: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(used).assert()).assert();
+        used["PORT_HTTP"].clone()
+    }
+
+    // 端口注入模块 _used.json, 分配结果保存到 port_alloc.yml
+    #[tokio::test]
+    async fn test_sys_port_inject() {
+        let dir = tempfile::tempdir().assert();
+        let mut node = ResourceNode::new("n1");
+        node.add(Vps::new(
+            CaculateResSpec::new(16, 32),
+            vec![Ipv4Addr::new(10, 0, 0, 1)],
+        ));
+        let mut spec = SysModelSpec::new("port_sys", SysWorkflows::sys_tpl_init())
+            .with_resource(SysResource::new(vec![node]));
+        spec.add_mod(port_mod("web"));
+        spec.add_mod(port_mod("api"));
+        spec.save_to(dir.path()).assert();
+
+        let root = dir.path().join("port_sys");
+        let spec = SysModelSpec::load_from(&root).assert();
+        spec.update_local(&UpdateOptions::for_test()).await.assert();
+        spec.localize(None, LocalizeOptions::for_test())
+            .await
+            .assert();
+        // 都被容量规划放到 n1, 第二个改用范围内的端口
+        assert_eq!(used_port(&root, "web"), 8080);
+        assert_eq!(used_port(&root, "api"), 30000);
+        let paths = SysTargetPaths::from(&root);
+        let saved = PortPlan::from_conf(paths.port_alloc_path()).assert();
+        assert_eq!(saved, spec.port_plan(&RuntimeContext::default()).assert());
+        assert!(saved.iter().all(|x| x.place() == "n1"));
+
+        // place 不在 resource.yml 中
+        let mut list = ModulesList::from_conf(paths.modlist_path()).assert();
+        list.mods = list
+            .mods
+            .into_iter()
+            .map(|x| match x.name().as_str() {
+                "api" => x.with_place("n9"),
+                _ => x,
+            })
+            .collect();
+        list.save_conf(paths.modlist_path()).assert();
+        let spec = SysModelSpec::load_from(&root).assert();
+        assert!(spec.port_plan(&RuntimeContext::default()).is_err());
+    }
+}
@@ -4,11 +4,15 @@ use crate::predule::*;
 use crate::tools::ensure_path;
 use crate::{
     const_vars::SYS_MODEL_SPC_ROOT,
-    error::SpecResult,
-    module::depend::DependencySet,
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    module::{
+        depend::DependencySet,
+        proj::{ensure_sample_value, global_value_explainer, project_value_files},
+        target::TargetChoice,
+    },
     tools::make_clean_path,
     types::{Configable, Localizable, Persistable, ValuePath},
-    vars::{ValueDict, ValueType},
+    vars::{ImportMode, ValueDict, ValueType, VarExplain},
     workflow::prj::GxlProject,
 };
 
@@ -16,7 +20,7 @@ use super::{
     init::{SYS_PRJ_ADM, SYS_PRJ_WORK, sys_init_gitignore},
     spec::SysModelSpec,
 };
-use crate::types::{LocalizeOptions, ValueConfable};
+use crate::types::LocalizeOptions;
 use async_trait::async_trait;
 
 #[derive(Getters, Clone, Debug, Serialize, Deserialize)]
@@ -118,10 +122,9 @@ impl SysConf {
 }
 
 impl SysProject {
-    pub async fn update(&self, options: &UpdateOptions) -> SpecResult<()> {
+    pub async fn update(&self, options: &UpdateOptions) -> SpecResult<Vec<TargetChoice>> {
         self.conf.update(options).await?;
-        self.sys_spec().update_local(options).await?;
-        Ok(())
+        self.sys_spec().update_local(options).await
     }
 }
 
@@ -137,11 +140,11 @@ impl Localizable for SysConf {
 }
 
 impl SysProject {
+    // 全局值由调用方按 --value / --value-layer 载入, 见 load_project_global_value
     pub async fn localize(&self, options: LocalizeOptions) -> SpecResult<()> {
+        ensure_sample_value(self.root_local())?;
         let value_path = self.value_path().ensure_exist()?;
-        let value_file = value_path.value_file();
-        let dict = ValueDict::from_valconf(&value_file)?;
-        let cur_opt = options.with_global(dict);
+        let cur_opt = options.with_global_profile(value_path.path(), value_path.path())?;
         let dst_path = Some(value_path);
 
         self.conf
@@ -150,6 +153,33 @@ impl SysProject {
         self.sys_spec().localize(dst_path, cur_opt).await?;
         Ok(())
     }
+    pub fn explain(
+        &self,
+        mod_name: &str,
+        key: &str,
+        values: &[String],
+        layers: &[String],
+        mode: ImportMode,
+        options: &LocalizeOptions,
+    ) -> SpecResult<VarExplain> {
+        let mod_ref = self.sys_spec().mod_list().find(mod_name).ok_or(
+            SpecReason::from(ElementReason::Miss(format!("module: {}", mod_name))).to_err(),
+        )?;
+        let spec = mod_ref.get_target_spec(options.runtime())?.ok_or(
+            SpecReason::from(ElementReason::Miss(format!(
+                "module {} local spec, run update first",
+                mod_name
+            )))
+            .to_err(),
+        )?;
+        let value_path = self.value_path();
+        let value_files =
+            project_value_files(self.root_local(), values, layers, options.runtime())?;
+        let global = global_value_explainer(value_path.path(), &value_files, mode, options);
+        let mod_value_path =
+            mod_ref.spec_value_path(self.sys_spec().mod_list().value_path(value_path));
+        spec.explain(key, Some(mod_value_path), global, options)
+    }
     pub fn value_path(&self) -> ValuePath {
         let value_root = self.root_local().join(VALUE_DIR);
         ValuePath::from_root(value_root)
@@ -2,10 +2,14 @@ use crate::predule::*;
 use std::path::{Path, PathBuf};
 
 use crate::{
-    addr::LocalAddr,
-    const_vars::{MOD_LIST_YML, MODULES_SPC_ROOT, NET_RES_YML, RESOURCE_YML, VARS_YML},
+    addr::{LocalAddr, version::OutdatedItem},
+    const_vars::{
+        INLINE_MODS_DIR, MOD_LIST_YML, MOD_ORDER_YML, MODULES_SPC_ROOT, NET_RES_YML,
+        PORT_ALLOC_YML, PORTS_YML, RESOURCE_YML, VARS_YML,
+    },
     error::ElementReason,
     module::proj::ModProject,
+    resource::SysResource,
     types::{Localizable, ValuePath},
     workflow::act::SysWorkflows,
 };
@@ -13,14 +17,19 @@ use async_trait::async_trait;
 use orion_error::{ErrorOwe, ErrorWith, StructError, UvsConfFrom, WithContext};
 
 use super::{
-    ModulesList,
+    ModulesList, NetResSpace,
+    graph::{ModGraph, ModOrder},
     init::{SysIniter, sys_init_gitignore},
 };
 use crate::types::LocalizeOptions;
+use crate::vars::ValueDict;
 use crate::{
     addr::GitAddr,
     error::{SpecReason, SpecResult, ToErr},
-    module::{CpuArch, ModelSTD, OsCPE, RunSPC, refs::ModuleSpecRef, spec::ModuleSpec},
+    module::{
+        CpuArch, ModelSTD, OsCPE, RunSPC, refs::ModuleSpecRef, spec::ModuleSpec,
+        target::TargetChoice,
+    },
     types::{Configable, Persistable},
 };
 #[derive(Getters, Clone, Debug)]
@@ -29,6 +38,9 @@ pub struct SysModelSpec {
     mod_list: ModulesList,
     local: Option<PathBuf>,
     workflow: SysWorkflows,
+    // resource.yml / net_res.yml, 文件不存在时为空
+    resource: Option<SysResource>,
+    net_res: Option<NetResSpace>,
 }
 
 #[derive(Getters, Clone, Debug)]
@@ -37,6 +49,8 @@ pub struct SysTargetPaths {
     spec_path: PathBuf,
     net_path: PathBuf,
     res_path: PathBuf,
+    ports_path: PathBuf,
+    port_alloc_path: PathBuf,
     vars_path: PathBuf,
     modlist_path: PathBuf,
     workflow_path: PathBuf,
@@ -48,6 +62,8 @@ impl From<&PathBuf> for SysTargetPaths {
             target_root: target_root.to_path_buf(),
             net_path: target_root.join(NET_RES_YML),
             res_path: target_root.join(RESOURCE_YML),
+            ports_path: target_root.join(PORTS_YML),
+            port_alloc_path: target_root.join(PORT_ALLOC_YML),
             vars_path: target_root.join(VARS_YML),
             modlist_path: target_root.join(MOD_LIST_YML),
             workflow_path: target_root.to_path_buf(),
@@ -56,12 +72,68 @@ impl From<&PathBuf> for SysTargetPaths {
     }
 }
 impl SysModelSpec {
+    // 已启用模块的依赖图, requires 与版本来自 update 后的本地模块
+    pub fn mod_graph(&self) -> SpecResult<ModGraph> {
+        let mut graph = ModGraph::default();
+        for m in self.mod_list.mods().iter().filter(|x| x.is_enable()) {
+            graph.add(m.name(), m.local_version()?, m.mod_requires()?);
+        }
+        Ok(graph)
+    }
+    pub fn mod_order(&self) -> SpecResult<ModOrder> {
+        self.mod_graph()?.topo_order()
+    }
+    pub fn outdated(&self, options: &UpdateOptions) -> SpecResult<Vec<OutdatedItem>> {
+        let mut items = Vec::new();
+        for m in self.mod_list.mods().iter().filter(|x| x.is_enable()) {
+            if let Some(item) = m.outdated(options)? {
+                items.push(item);
+            }
+        }
+        Ok(items)
+    }
     pub fn add_mod(&mut self, modx: ModuleSpec) {
         self.mod_list.add_mod(modx);
+        if let Some(local) = &self.local {
+            let paths = SysTargetPaths::from(local);
+            self.mod_list.set_mods_local(paths.spec_path().clone());
+        }
+    }
+    pub fn with_resource(mut self, resource: SysResource) -> Self {
+        self.resource = Some(resource);
+        self
+    }
+    pub fn with_net_res(mut self, net_res: NetResSpace) -> Self {
+        self.net_res = Some(net_res);
+        self
+    }
+    // 资源清单导出为全局值: RESOURCE / NET_RES
+    pub fn inventory_dict(&self) -> SpecResult<ValueDict> {
+        let mut dict = ValueDict::new();
+        if let Some(resource) = &self.resource {
+            dict.insert("RESOURCE", resource.export()?);
+        }
+        if let Some(net_res) = &self.net_res {
+            dict.insert("NET_RES", net_res.export()?);
+        }
+        Ok(dict)
     }
     pub fn add_mod_ref(&mut self, modx: ModuleSpecRef) {
         self.mod_list.add_ref(modx)
     }
+    // 追加模块引用并写回本地 mod_list.yml
+    pub fn append_mod_ref(&mut self, modx: ModuleSpecRef) -> SpecResult<()> {
+        if self.mod_list.find(modx.name()).is_some() {
+            return SpecReason::from(ElementReason::Duplicate(format!("module: {}", modx.name())))
+                .err_result();
+        }
+        let root = self.local.clone().ok_or(
+            SpecReason::from(ElementReason::Miss(format!("sys {} local path", self.name))).to_err(),
+        )?;
+        self.mod_list.add_ref(modx);
+        self.mod_list
+            .save_conf(SysTargetPaths::from(&root).modlist_path())
+    }
     pub fn save_to(&self, path: &Path) -> SpecResult<()> {
         self.save_local(path, self.name())
     }
@@ -76,6 +148,14 @@ impl SysModelSpec {
         std::fs::create_dir_all(paths.spec_path()).owe_conf()?;
         sys_init_gitignore(&root)?;
         self.mod_list.save_conf(paths.modlist_path())?;
+        self.mod_list
+            .save_inline(&paths.spec_path().join(INLINE_MODS_DIR))?;
+        if let Some(resource) = &self.resource {
+            resource.save_conf(paths.res_path())?;
+        }
+        if let Some(net_res) = &self.net_res {
+            net_res.save_conf(paths.net_path())?;
+        }
 
         self.workflow.save_to(paths.workflow_path(), None)?;
         flag.flag_suc();
@@ -101,12 +181,24 @@ impl SysModelSpec {
             .with(&ctx)?;
         mod_list.set_mods_local(paths.spec_path().clone());
         let workflow = SysWorkflows::load_from(paths.workflow_path()).with(&ctx)?;
+        let resource = if paths.res_path().exists() {
+            Some(SysResource::load(paths.res_path()).with(&ctx)?)
+        } else {
+            None
+        };
+        let net_res = if paths.net_path().exists() {
+            Some(NetResSpace::load(paths.net_path()).with(&ctx)?)
+        } else {
+            None
+        };
         flag.flag_suc();
         Ok(Self {
             name: name.to_string(),
             mod_list,
             local: Some(root.to_path_buf()),
             workflow,
+            resource,
+            net_res,
         })
     }
 
@@ -116,13 +208,18 @@ impl SysModelSpec {
             mod_list: ModulesList::default(),
             local: None,
             workflow: actions,
+            resource: None,
+            net_res: None,
         }
     }
 
-    pub async fn update_local(&self, options: &UpdateOptions) -> SpecResult<()> {
+    // 返回各模块选用的 target
+    pub async fn update_local(&self, options: &UpdateOptions) -> SpecResult<Vec<TargetChoice>> {
         if let Some(local) = &self.local {
-            self.mod_list.update(local, options).await?;
-            Ok(())
+            let choices = self.mod_list.update(local, options).await?;
+            let order = self.mod_order()?;
+            order.save_conf(&local.join(MOD_ORDER_YML))?;
+            Ok(choices)
         } else {
             SpecReason::from(ElementReason::Miss("local path".into())).err_result()
         }
@@ -136,8 +233,26 @@ impl Localizable for SysModelSpec {
         dst_path: Option<ValuePath>,
         options: LocalizeOptions,
     ) -> SpecResult<()> {
-        if let Some(_local) = &self.local {
-            self.mod_list.localize(dst_path, options).await?;
+        if let Some(local) = &self.local {
+            // 端口分配记录在 port_alloc.yml, 供下次沿用
+            let ports = self.port_plan(options.runtime())?;
+            let alloc_path = SysTargetPaths::from(local).port_alloc_path().clone();
+            if ports.is_empty() {
+                // 不再有端口声明时删除旧记录
+                if alloc_path.exists() {
+                    std::fs::remove_file(&alloc_path)
+                        .owe_res()
+                        .with(&alloc_path)?;
+                }
+            } else {
+                ports.save_conf(&alloc_path)?;
+            }
+            let mut global = self.inventory_dict()?;
+            global.insert("PORTS", ports.export());
+            let options = options.with_global_base(global);
+            self.mod_list
+                .localize_with_ports(dst_path, options, &ports)
+                .await?;
             Ok(())
         } else {
             SpecReason::from(ElementReason::Miss("local path".into())).err_result()
@@ -204,11 +319,24 @@ pub mod tests {
 
     use orion_error::TestAssertWithMsg;
 
+    use std::str::FromStr;
+
     use crate::{
-        const_vars::SYS_MODEL_SPC_ROOT,
-        module::proj::ModProject,
-        tools::{make_clean_path, test_init},
+        const_vars::{
+            LOCAL_DIR, MOD_REQUIRES_YML, MODS_DIR, SYS_MODEL_SPC_ROOT, USED_JSON, VALUE_DIR,
+            VERSION_TXT,
+        },
+        module::{
+            proj::ModProject,
+            require::{ModRequire, ModRequires},
+            target::TargetModel,
+        },
+        resource::{CaculateResSpec, ResourceNode, Vps},
+        system::NetworkSpec,
+        tools::{get_sub_dirs, make_clean_path, test_init},
+        vars::{ValueType, VarCollection, VarDefinition},
     };
+    use std::net::Ipv4Addr;
 
     use super::*;
 
@@ -230,9 +358,197 @@ pub mod tests {
         spec.update_local(&UpdateOptions::for_test())
             .await
             .assert("update");
+        assert!(spec_path.join(MOD_ORDER_YML).exists());
         spec.localize(None, LocalizeOptions::for_test())
             .await
             .assert("localize");
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_sys_localize_shared_vars() {
+        test_init();
+        let dir = tempfile::tempdir().assert("tempdir");
+        let mut spec = SysModelSpec::new("shared_sys", SysWorkflows::sys_tpl_init());
+        let k8s = ModuleSpec::make_new("shared_mod")
+            .assert("mod spec")
+            .targets()
+            .get(&ModelSTD::x86_ubt22_k8s())
+            .cloned()
+            .expect("k8s target");
+        let shared = VarCollection::define(vec![
+            VarDefinition::from(("REPLICAS", 1)),
+            VarDefinition::from(("REPLICAS", 3)).with_when("spc == k8s"),
+            VarDefinition::from(("BIN_DIR", "/opt/homebrew/bin")).with_when("os =* 'mac*'"),
+        ]);
+        spec.add_mod(ModuleSpec::init("shared_mod", vec![k8s]).with_vars(shared));
+        spec.save_to(dir.path()).assert("save");
+
+        let root = dir.path().join("shared_sys");
+        let spec = SysModelSpec::load_from(&root).assert("load");
+        spec.update_local(&UpdateOptions::for_test())
+            .await
+            .assert("update");
+        spec.localize(None, LocalizeOptions::for_test())
+            .await
+            .assert("localize");
+        // mods/<name>/vars.yml 经 when 筛选后进入 _used.json
+        let used_json = root
+            .join(MODS_DIR)
+            .join("shared_mod")
+            .join(ModelSTD::x86_ubt22_k8s().to_string())
+            .join(VALUE_DIR)
+            .join(USED_JSON);
+        let used: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&used_json).assert("read"))
+                .assert("json");
+        assert_eq!(used["REPLICAS"], 3);
+        assert!(used.get("BIN_DIR").is_none());
+    }
+
+    #[test]
+    fn test_sys_inventory() {
+        let dir = tempfile::tempdir().assert("tempdir");
+        let mut node = ResourceNode::new("node1");
+        node.add(
+            Vps::new(CaculateResSpec::new(4, 8), vec![Ipv4Addr::new(10, 0, 0, 2)])
+                .with_hostname("node1.local"),
+        );
+        let net_res = NetResSpace::new(
+            Ipv4Addr::new(10, 0, 0, 1),
+            (Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 9)),
+        )
+        .with_network(NetworkSpec::new("v6", "fd00::/64"));
+        let spec = SysModelSpec::new("inv_sys", SysWorkflows::sys_tpl_init())
+            .with_resource(SysResource::new(vec![node]))
+            .with_net_res(net_res);
+        spec.save_to(dir.path()).assert("save");
+
+        let spec = SysModelSpec::load_from(&dir.path().join("inv_sys")).assert("load");
+        assert_eq!(spec.resource().as_ref().map(|x| x.len()), Some(1));
+        let dict = spec.inventory_dict().assert("export");
+        assert!(dict.get("RESOURCE").is_some());
+        let Some(ValueType::Obj(net)) = dict.get("NET_RES") else {
+            panic!("miss NET_RES")
+        };
+        assert_eq!(net["master"], ValueType::from("10.0.0.1"));
+
+        // 非法 cidr 在加载时报错
+        let bad = NetResSpace::new(
+            Ipv4Addr::new(10, 0, 0, 1),
+            (Ipv4Addr::new(10, 0, 0, 2), Ipv4Addr::new(10, 0, 0, 9)),
+        )
+        .with_network(NetworkSpec::new("bad", "10.0.0.0/40"));
+        let net_path = SysTargetPaths::from(&dir.path().join("inv_sys"))
+            .net_path()
+            .clone();
+        bad.save_conf(&net_path).assert("save net");
+        assert!(SysModelSpec::load_from(&dir.path().join("inv_sys")).is_err());
+    }
+
+    #[test]
+    fn test_mod_order() {
+        let dir = tempfile::tempdir().assert("tempdir");
+        let mut spec = SysModelSpec::new("order_sys", SysWorkflows::sys_tpl_init());
+        for (name, requires) in [
+            ("app", vec!["mysql@>=8.0"]),
+            ("mysql", vec![]),
+            ("redis", vec![]),
+        ] {
+            let requires = requires
+                .into_iter()
+                .map(|x| ModRequire::from_str(x).assert("require"))
+                .collect();
+            spec.add_mod_ref(
+                ModuleSpecRef::from(
+                    name,
+                    LocalAddr::from(format!("./{}", name)),
+                    ModelSTD::x86_ubt22_host(),
+                )
+                .with_requires(ModRequires::new(requires, vec![])),
+            );
+        }
+        spec.save_to(dir.path()).assert("save");
+        let spec = SysModelSpec::load_from(&dir.path().join("order_sys")).assert("load");
+        let mods = dir.path().join("order_sys").join("mods");
+        std::fs::create_dir_all(mods.join("mysql")).assert("mkdir");
+        std::fs::create_dir_all(mods.join("app")).assert("mkdir");
+        std::fs::write(mods.join("mysql").join(VERSION_TXT), "8.0.3").assert("write");
+        // 模块自带的 requires.yml
+        std::fs::write(
+            mods.join("app").join(MOD_REQUIRES_YML),
+            "after:\n  - redis\n",
+        )
+        .assert("write");
+        let order = spec.mod_order().assert("order");
+        assert_eq!(order.order(), &vec!["mysql", "redis", "app"]);
+
+        std::fs::write(mods.join("mysql").join(VERSION_TXT), "5.7.0").assert("write");
+        assert!(spec.mod_order().is_err());
+    }
+
+    #[test]
+    fn test_append_mod_ref() {
+        let dir = tempfile::tempdir().assert("tempdir");
+        let spec = make_sys_spec_test("append_sys", vec!["redis_mock"]).assert("spec");
+        spec.save_to(dir.path()).assert("save");
+        let root = dir.path().join("append_sys");
+        let mut spec = SysModelSpec::load_from(&root).assert("load");
+        let mysql = ModuleSpecRef::from(
+            "mysql",
+            GitAddr::from("https://github.com/galaxy-sec/mysql.git").tag("v8.0.3"),
+            ModelSTD::x86_ubt22_k8s(),
+        );
+        spec.append_mod_ref(mysql.clone()).assert("append");
+        assert!(spec.append_mod_ref(mysql).is_err());
+        let spec = SysModelSpec::load_from(&root).assert("reload");
+        assert_eq!(spec.mod_list().mods().len(), 2);
+        assert!(spec.mod_list().find("mysql").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_add_inline_mod() {
+        test_init();
+        let dir = tempfile::tempdir().assert("tempdir");
+        let mut spec = SysModelSpec::new("inline_sys", SysWorkflows::sys_tpl_init());
+        let k8s = ModuleSpec::make_new("inline_mod")
+            .assert("mod spec")
+            .targets()
+            .get(&ModelSTD::x86_ubt22_k8s())
+            .cloned()
+            .expect("k8s target");
+        let inline = ModuleSpec::init("inline_mod", vec![k8s]);
+        let target_cnt = inline.targets().len();
+        spec.add_mod(inline);
+        spec.save_to(dir.path()).assert("save");
+
+        let root = dir.path().join("inline_sys");
+        let src_root = root.join(INLINE_MODS_DIR).join("inline_mod");
+        let mod_root = root.join(MODS_DIR).join("inline_mod");
+        let spec = SysModelSpec::load_from(&root).assert("load");
+        let modx = spec.mod_list().find("inline_mod").expect("find");
+        assert!(modx.is_inline());
+        assert_eq!(
+            modx.model().clone(),
+            Some(TargetModel::from(ModelSTD::x86_ubt22_k8s()))
+        );
+        let loaded = modx.inline_spec().assert("inline spec").expect("exists");
+        assert_eq!(loaded.targets().len(), target_cnt);
+
+        let choices = spec
+            .update_local(&UpdateOptions::for_test())
+            .await
+            .assert("update");
+        assert!(!choices.is_empty());
+        // 源文件不会被 update 清理
+        assert_eq!(get_sub_dirs(&src_root).assert("subs").len(), target_cnt);
+        spec.localize(None, LocalizeOptions::for_test())
+            .await
+            .assert("localize");
+        // 输出在 mods/<name>, 源目录保持不变
+        let target = ModelSTD::x86_ubt22_k8s().to_string();
+        assert!(mod_root.join(&target).join(LOCAL_DIR).exists());
+        assert!(!src_root.join(&target).join(LOCAL_DIR).exists());
+        assert!(!src_root.join(&target).join(VALUE_DIR).exists());
+    }
 }
@@ -1,9 +1,22 @@
-use handlebars::Handlebars;
+use handlebars::{Handlebars, handlebars_helper};
 use orion_error::ErrorOwe;
 use serde::Serialize;
 
 use crate::error::SpecResult;
 
+// 单行时使用 serde_yaml 的输出; 多行的值改为 json 形式, 同样是合法的 yaml 行内标量
+fn yaml_scalar(value: &serde_json::Value) -> String {
+    let text = serde_yaml::to_string(value).unwrap_or_default();
+    let text = text.trim_end_matches('\n');
+    if text.contains('\n') {
+        serde_json::to_string(value).unwrap_or_default()
+    } else {
+        text.to_string()
+    }
+}
+
+handlebars_helper!(yaml: |value: Json| yaml_scalar(value));
+
 pub struct TplHandleBars<'a> {
     handlebars: Handlebars<'a>,
 }
@@ -11,6 +24,8 @@ impl TplHandleBars<'_> {
     pub fn init() -> Self {
         let mut handlebars = Handlebars::new();
         handlebars.set_strict_mode(true);
+        // {{{yaml VAR}}}: 按 yaml 转义输出
+        handlebars.register_helper("yaml", Box::new(yaml));
         Self { handlebars }
     }
 
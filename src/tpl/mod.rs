@@ -4,7 +4,9 @@ mod error;
 mod export;
 //mod gtmpl;
 mod handlebars;
+mod refs;
 pub use comment::CommentFmt;
 pub use covert::LabelCoverter;
 pub use export::CustTmplLabel;
 pub use handlebars::TplHandleBars;
+pub use refs::{RefKind, TplRef, template_refs};
@@ -0,0 +1,213 @@
+use derive_getters::Getters;
+use handlebars::{
+    Path as HbsPath, PathSeg, Template,
+    template::{BlockParam, HelperTemplate, Parameter, TemplateElement},
+};
+use orion_error::ErrorOwe;
+use serde_derive::Serialize;
+
+use crate::error::{LocalizeReason, SpecReason, SpecResult};
+
+// 会切换上下文的 block, 其中的相对路径不指向顶层变量
+const SCOPE_HELPERS: [&str; 2] = ["each", "with"];
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum RefKind {
+    // 引用顶层变量
+    Var,
+    // `as |x|` 声明的 block 参数
+    BlockParam,
+}
+
+#[derive(Getters, Clone, Debug, PartialEq, Serialize)]
+pub struct TplRef {
+    name: String,
+    line: usize,
+    kind: RefKind,
+}
+
+// 解析 handlebars 模板, 返回其中引用的顶层变量及 block 参数
+pub fn template_refs(content: &str) -> SpecResult<Vec<TplRef>> {
+    let template = Template::compile(content).owe(SpecReason::from(LocalizeReason::Templatize(
+        "parse tpl".into(),
+    )))?;
+    let mut collector = RefCollector::default();
+    collector.template(&template, 1);
+    Ok(collector.refs)
+}
+
+#[derive(Default)]
+struct RefCollector {
+    refs: Vec<TplRef>,
+    // 所在 each/with 的层数
+    scope: usize,
+    params: Vec<String>,
+}
+
+impl RefCollector {
+    fn template(&mut self, template: &Template, parent_line: usize) {
+        for (idx, element) in template.elements.iter().enumerate() {
+            let line = template
+                .mapping
+                .get(idx)
+                .map(|x| x.0)
+                .unwrap_or(parent_line);
+            match element {
+                TemplateElement::Expression(ht) | TemplateElement::HtmlExpression(ht) => {
+                    self.helper(ht, line)
+                }
+                TemplateElement::HelperBlock(ht) => self.block(ht, line),
+                _ => {}
+            }
+        }
+    }
+
+    fn helper(&mut self, ht: &HelperTemplate, line: usize) {
+        if ht.params.is_empty() && ht.hash.is_empty() {
+            self.param(&ht.name, line);
+        }
+        for param in ht.params.iter().chain(ht.hash.values()) {
+            self.param(param, line);
+        }
+    }
+
+    fn block(&mut self, ht: &HelperTemplate, line: usize) {
+        for param in ht.params.iter().chain(ht.hash.values()) {
+            self.param(param, line);
+        }
+        let params = block_param_names(ht.block_param.as_ref());
+        for name in &params {
+            self.refs.push(TplRef {
+                name: name.clone(),
+                line,
+                kind: RefKind::BlockParam,
+            });
+        }
+        let scoped = ht
+            .name
+            .as_name()
+            .is_some_and(|x| SCOPE_HELPERS.contains(&x));
+        let depth = self.params.len();
+        self.params.extend(params);
+        if scoped {
+            self.scope += 1;
+        }
+        if let Some(tpl) = &ht.template {
+            self.template(tpl, line);
+        }
+        if scoped {
+            self.scope -= 1;
+        }
+        if let Some(tpl) = &ht.inverse {
+            self.template(tpl, line);
+        }
+        self.params.truncate(depth);
+    }
+
+    fn param(&mut self, param: &Parameter, line: usize) {
+        match param {
+            Parameter::Name(name) => self.var(name, line),
+            Parameter::Path(HbsPath::Relative((segs, raw))) => {
+                let first = segs.iter().find_map(|x| match x {
+                    PathSeg::Named(name) => Some(name),
+                    _ => None,
+                });
+                match first {
+                    // @root 始终指向顶层数据
+                    Some(name) if raw.starts_with("@root") => self.push_var(name, line),
+                    Some(name) if matches!(segs.first(), Some(PathSeg::Named(_))) => {
+                        self.var(name, line)
+                    }
+                    _ => {}
+                }
+            }
+            Parameter::Subexpression(sub) => {
+                if let TemplateElement::Expression(ht) = sub.as_element() {
+                    for param in ht.params.iter().chain(ht.hash.values()) {
+                        self.param(param, line);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn var(&mut self, raw: &str, line: usize) {
+        let name = raw.split(['.', '/', '[']).next().unwrap_or(raw);
+        if self.scope > 0 || name.is_empty() || name == "this" || name == "else" {
+            return;
+        }
+        if self.params.iter().any(|x| x == name) {
+            return;
+        }
+        self.push_var(name, line);
+    }
+
+    fn push_var(&mut self, name: &str, line: usize) {
+        self.refs.push(TplRef {
+            name: name.to_string(),
+            line,
+            kind: RefKind::Var,
+        });
+    }
+}
+
+fn block_param_names(param: Option<&BlockParam>) -> Vec<String> {
+    let names = match param {
+        Some(BlockParam::Single(x)) => vec![x],
+        Some(BlockParam::Pair((x, y))) => vec![x, y],
+        _ => Vec::new(),
+    };
+    names
+        .into_iter()
+        .filter_map(|x| x.as_name().map(String::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(refs: &[TplRef]) -> Vec<(&str, usize)> {
+        refs.iter()
+            .filter(|x| x.kind == RefKind::Var)
+            .map(|x| (x.name.as_str(), x.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_template_refs() {
+        let tpl = r#"host: {{HOST}}
+port: {{ DB.port }}
+{{#if ENABLE_TLS}}
+cert: {{CERT_FILE}}
+{{else}}
+cert: {{{ NONE_CERT }}}
+{{/if}}
+{{#each SERVERS as |srv|}}
+- {{srv.name}} {{this}} {{@index}} {{@root.DOMAIN}}
+{{/each}}
+{{!-- {{COMMENTED}} --}}
+"#;
+        let refs = template_refs(tpl).unwrap();
+        assert_eq!(
+            vars(&refs),
+            vec![
+                ("HOST", 1),
+                ("DB", 2),
+                ("ENABLE_TLS", 3),
+                ("CERT_FILE", 4),
+                ("NONE_CERT", 6),
+                ("SERVERS", 8),
+                ("DOMAIN", 9),
+            ]
+        );
+        let params: Vec<_> = refs
+            .iter()
+            .filter(|x| x.kind == RefKind::BlockParam)
+            .map(|x| x.name.as_str())
+            .collect();
+        assert_eq!(params, vec!["srv"]);
+        assert!(template_refs("{{#if A}}").is_err());
+    }
+}
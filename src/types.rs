@@ -9,8 +9,16 @@ use orion_error::{ErrorOwe, ErrorWith, WithContext};
 use serde::{Serialize, de::DeserializeOwned};
 
 use crate::{
-    addr::rename_path, const_vars::VALUE_FILE, error::SpecResult, tools::ensure_path,
-    update::UpdateOptions, vars::ValueDict,
+    addr::rename_path,
+    const_vars::VALUE_FILE,
+    error::{ElementReason, SpecReason, SpecResult, ToErr},
+    secret::SecretResolver,
+    tools::ensure_path,
+    update::UpdateOptions,
+    vars::{
+        EnvDict, GLOBAL_SOURCE, OriginDict, RuntimeContext, ValueDict, profile_exists_under,
+        profile_source, profile_value_file,
+    },
 };
 
 pub trait Persistable<T> {
@@ -68,31 +76,103 @@ impl ValuePath {
 }
 #[derive(Clone, Debug, Default)]
 pub struct LocalizeOptions {
-    global_dict: ValueDict,
+    global_dict: OriginDict,
     use_default_value: bool,
+    profile: Option<String>,
+    runtime: RuntimeContext,
+    // 渲染时解析 ${secret:..} 的 provider
+    secrets: SecretResolver,
 }
 impl LocalizeOptions {
     pub fn new(global_dict: ValueDict, mod_user_value: bool) -> Self {
         Self {
-            global_dict,
+            global_dict: Self::global_origin(global_dict),
             use_default_value: mod_user_value,
+            profile: None,
+            runtime: RuntimeContext::default(),
+            secrets: SecretResolver::default(),
         }
     }
-    pub fn global_value(&self) -> &ValueDict {
+    fn global_origin(value: ValueDict) -> OriginDict {
+        let mut dict = OriginDict::from(value);
+        dict.set_source(GLOBAL_SOURCE);
+        dict
+    }
+    pub fn global_value(&self) -> &OriginDict {
         &self.global_dict
     }
     pub fn with_global(mut self, value: ValueDict) -> Self {
-        self.global_dict = value;
+        self.global_dict = Self::global_origin(value);
+        self
+    }
+    // base 作为最底层, 已有的全局值优先
+    pub fn with_global_base(mut self, base: ValueDict) -> Self {
+        let mut dict = Self::global_origin(base);
+        dict.overlay(self.global_dict);
+        self.global_dict = dict;
+        self
+    }
+    pub fn with_profile(mut self, profile: Option<String>) -> Self {
+        self.profile = profile;
+        self
+    }
+    pub fn profile(&self) -> Option<&String> {
+        self.profile.as_ref()
+    }
+    pub fn with_runtime(mut self, runtime: RuntimeContext) -> Self {
+        self.runtime = runtime;
         self
     }
+    pub fn runtime(&self) -> &RuntimeContext {
+        &self.runtime
+    }
+    pub fn with_secrets(mut self, secrets: SecretResolver) -> Self {
+        self.secrets = secrets;
+        self
+    }
+    pub fn secrets(&self) -> &SecretResolver {
+        &self.secrets
+    }
+    // 求值字典: 已确定的值优先, 其次运行时上下文
+    pub fn eval_dict(&self, used: &OriginDict) -> EnvDict {
+        self.runtime.eval_dict(&used.export_dict())
+    }
+    // 叠加全局 profile: <value_root>/profiles/<profile>.yml 优先于基础 value.yml
+    // 全局缺少时, layer_root 下须有模块层提供该 profile, 否则报错
+    pub fn with_global_profile(mut self, value_root: &Path, layer_root: &Path) -> SpecResult<Self> {
+        if let Some(profile) = &self.profile {
+            let profile_file = profile_value_file(value_root, profile);
+            if !profile_file.exists() {
+                if profile_exists_under(layer_root, profile) {
+                    return Ok(self);
+                }
+                return SpecReason::from(ElementReason::Miss(format!(
+                    "profile {} in any layer: {}",
+                    profile,
+                    profile_file.display()
+                )))
+                .err_result();
+            }
+            let mut profile_dict = OriginDict::from(ValueDict::eval_from_file(
+                &self.eval_dict(&self.global_dict),
+                &profile_file,
+            )?);
+            profile_dict.set_source(profile_source(GLOBAL_SOURCE, profile));
+            self.global_dict.overlay(profile_dict);
+        }
+        Ok(self)
+    }
     pub fn use_default_value(&self) -> bool {
         self.use_default_value
     }
 
     pub fn for_test() -> Self {
         Self {
-            global_dict: ValueDict::new(),
+            global_dict: OriginDict::new(),
             use_default_value: false,
+            profile: None,
+            runtime: RuntimeContext::default(),
+            secrets: SecretResolver::default(),
         }
     }
 }
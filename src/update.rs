@@ -1,6 +1,6 @@
 use derive_more::From;
 
-use crate::vars::ValueDict;
+use crate::vars::{EnvDict, RuntimeContext, ValueDict};
 
 //use super::predule::*;
 #[derive(Debug, From, Clone, Default, PartialEq)]
@@ -34,23 +34,37 @@ impl From<(usize, ValueDict)> for UpdateOptions {
 pub struct UpdateOptions {
     scope_level: UpdateScope,
     values: ValueDict,
+    runtime: RuntimeContext,
 }
 impl UpdateOptions {
     pub fn new(re_level: UpdateScope, values: ValueDict) -> Self {
         Self {
             scope_level: re_level,
             values,
+            runtime: RuntimeContext::default(),
         }
     }
     pub fn for_test() -> Self {
         Self {
             scope_level: UpdateScope::InProj,
             values: ValueDict::default(),
+            runtime: RuntimeContext::default(),
         }
     }
+    pub fn with_runtime(mut self, runtime: RuntimeContext) -> Self {
+        self.runtime = runtime;
+        self
+    }
     pub fn values(&self) -> &ValueDict {
         &self.values
     }
+    pub fn runtime(&self) -> &RuntimeContext {
+        &self.runtime
+    }
+    // 地址/路径求值使用: values 优先, 其次运行时上下文
+    pub fn env_dict(&self) -> EnvDict {
+        self.runtime.eval_dict(&self.values)
+    }
 }
 impl UpdateOptions {
     pub fn clean_git_cache(&self) -> bool {
@@ -1,13 +1,22 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    str::FromStr,
+};
 
 use derive_getters::Getters;
 use serde_derive::{Deserialize, Serialize};
 
+use orion_error::ErrorWith;
+
 use crate::{error::SpecResult, types::Yamlable};
 
-use super::{EnvDict, EnvEvalable, ValueDict, VarDefinition};
+use super::{
+    EnvDict, EnvEvalable, Expr, ExprContext, MergePolicy, ValueDict, VarDefinition, merge_value,
+    strip_reset,
+};
 
-#[derive(Getters, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Getters, Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 //#[serde(transparent)]
 pub struct VarCollection {
     vars: Vec<VarDefinition>,
@@ -23,7 +32,17 @@ impl VarCollection {
         }
         dict
     }
-    // 基于VarType的name进行合并，相同的name会被覆盖
+    pub fn merge_policy(&self) -> MergePolicy {
+        let mut policy = MergePolicy::default();
+        for var in &self.vars {
+            if let Some(strategy) = var.merge() {
+                policy = policy.with_key(var.name(), strategy);
+            }
+        }
+        policy
+    }
+    // 基于VarType的name进行合并，相同的name以 other 为准;
+    // 值按 merge 策略深度合并, other 中为 !reset 的变量被移除
     pub fn merge(&self, other: &VarCollection) -> Self {
         let mut merged = HashMap::new();
         let mut order = Vec::new();
@@ -37,25 +56,63 @@ impl VarCollection {
             merged.insert(name, var.clone());
         }
 
-        // 添加other的变量，同名会覆盖
+        // 添加other的变量，同名会合并
         for var in &other.vars {
             let name = var.name().to_string();
-            if !merged.contains_key(&name) {
+            let mut cur = var.clone();
+            if let Some(base) = merged.get(&name) {
+                let strategy = var.merge().or(base.merge()).unwrap_or_default();
+                cur.set_value(merge_value(&var.var_value(), &base.var_value(), strategy));
+                if cur.merge().is_none()
+                    && let Some(strategy) = base.merge()
+                {
+                    cur = cur.with_merge(strategy);
+                }
+            } else {
                 order.push(name.clone());
             }
-            merged.insert(name, var.clone());
+            merged.insert(name, cur);
         }
 
         // 按原始顺序重新排序
         let mut result = Vec::new();
         for name in order {
-            if let Some(var) = merged.get(&name) {
-                result.push(var.clone());
+            if let Some(var) = merged.get(&name)
+                && let Some(value) = strip_reset(var.var_value())
+            {
+                let mut var = var.clone();
+                var.set_value(value);
+                result.push(var);
             }
         }
 
         Self { vars: result }
     }
+    // 按声明顺序计算 expr 变量, 结果依次加入 scope 供后续表达式使用;
+    // skip 中的变量已被更高优先级的 value 覆盖, 不再计算
+    pub fn eval_expr(
+        &self,
+        scope: &ValueDict,
+        skip: &HashSet<String>,
+        generated: &mut ValueDict,
+    ) -> SpecResult<ValueDict> {
+        let mut scope = scope.clone();
+        let mut computed = ValueDict::new();
+        for var in &self.vars {
+            let Some(expr) = var.expr() else {
+                continue;
+            };
+            if skip.contains(var.name()) {
+                continue;
+            }
+            let value = Expr::from_str(expr)
+                .and_then(|x| x.eval(&mut ExprContext::new(var.name(), &scope, generated)))
+                .with(("var", var.name()))?;
+            scope.insert(var.name(), value.clone());
+            computed.insert(var.name(), value);
+        }
+        Ok(computed)
+    }
     pub fn eval_from_file(dict: &EnvDict, file_path: &Path) -> SpecResult<Self> {
         let mut cur_dict = dict.clone();
         let ins = VarCollection::from_yml(file_path)?;
@@ -64,10 +121,11 @@ impl VarCollection {
 
     fn eval_import(self, dict: &mut ValueDict) -> Self {
         let mut vars = Vec::new();
-        for v in self.vars {
+        for mut v in self.vars {
             let e_v = v.var_value().env_eval(dict);
             dict.insert(v.name(), e_v.clone());
-            vars.push(VarDefinition::from((v.name(), e_v)));
+            v.set_value(e_v);
+            vars.push(v);
         }
         Self { vars }
     }
@@ -75,7 +133,7 @@ impl VarCollection {
 
 #[cfg(test)]
 mod tests {
-    use crate::vars::ValueType;
+    use crate::vars::{MergeStrategy, ValueType};
 
     use super::*;
     use std::io::Write;
@@ -144,6 +202,96 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_eval_expr_vars() {
+        let file = create_temp_file(
+            r#"
+vars:
+  - name: ROOT
+    value: /opt
+  - name: MEM
+    value: 1000
+  - name: DATA_DIR
+    expr: "'${ROOT}/data'"
+  - name: MEM_LIMIT
+    expr: int(MEM * 0.8)
+  - name: LOG_DIR
+    expr: DATA_DIR + '/log'
+"#,
+        );
+        let vars = VarCollection::eval_from_file(&ValueDict::default(), file.path()).unwrap();
+        assert_eq!(
+            vars.vars()[2].expr().map(|x| x.as_str()),
+            Some("'${ROOT}/data'")
+        );
+        let mut generated = ValueDict::new();
+        let mut skip = HashSet::new();
+        let computed = vars
+            .eval_expr(&vars.value_dict(), &skip, &mut generated)
+            .unwrap();
+        assert_eq!(
+            computed.get("DATA_DIR"),
+            Some(&ValueType::from("/opt/data"))
+        );
+        assert_eq!(computed.get("MEM_LIMIT"), Some(&ValueType::Int(800)));
+        assert_eq!(
+            computed.get("LOG_DIR"),
+            Some(&ValueType::from("/opt/data/log"))
+        );
+
+        skip.insert("DATA_DIR".to_string());
+        let mut scope = vars.value_dict();
+        scope.insert("DATA_DIR", ValueType::from("/mnt"));
+        let computed = vars.eval_expr(&scope, &skip, &mut generated).unwrap();
+        assert!(computed.get("DATA_DIR").is_none());
+        assert_eq!(computed.get("LOG_DIR"), Some(&ValueType::from("/mnt/log")));
+    }
+
+    #[test]
+    fn test_merge_vars_strategy() {
+        let base: VarCollection = serde_yaml::from_str(
+            r#"
+vars:
+  - name: args
+    value: [--a]
+    merge: append
+  - name: conf
+    value: { host: localhost, port: 80 }
+  - name: debug
+    value: true
+"#,
+        )
+        .unwrap();
+        let over: VarCollection = serde_yaml::from_str(
+            r#"
+vars:
+  - name: args
+    value: [--b]
+  - name: conf
+    value: { host: 10.0.0.1 }
+  - name: debug
+    value: !reset
+"#,
+        )
+        .unwrap();
+        let merged = base.merge(&over);
+        let names: Vec<&str> = merged.vars().iter().map(|v| v.name()).collect();
+        assert_eq!(names, vec!["args", "conf"]);
+        assert_eq!(
+            merged.vars()[0].var_value(),
+            ValueType::List(vec![ValueType::from("--a"), ValueType::from("--b")])
+        );
+        assert_eq!(merged.vars()[0].merge(), Some(MergeStrategy::Append));
+        assert_eq!(
+            merged.vars()[1].var_value().to_string(),
+            r#"{"host":"10.0.0.1","port":80}"#
+        );
+        assert_eq!(
+            merged.merge_policy().strategy("args"),
+            MergeStrategy::Append
+        );
+    }
+
     #[test]
     fn test_toml_serialization() {
         let collection = VarCollection::define(vec![
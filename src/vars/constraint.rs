@@ -1,5 +1,7 @@
 use serde_derive::{Deserialize, Serialize};
 
+use super::ValueType;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct ValueScope {
     pub beg: u64,
@@ -12,6 +14,12 @@ pub enum ValueConstraint {
     Locked,
     #[serde(rename = "scope")]
     Scope(ValueScope),
+    // 可选值列表
+    #[serde(rename = "options")]
+    Options(Vec<ValueType>),
+    // 字符串正则
+    #[serde(rename = "pattern")]
+    Pattern(String),
 }
 impl ValueConstraint {
     pub fn scope(beg: u64, end: u64) -> Self {
@@ -1,13 +1,30 @@
 use serde_derive::{Deserialize, Serialize};
 
-use super::ValueType;
+use super::{MergeStrategy, ValueConstraint, ValueType};
+
+fn expr_placeholder() -> ValueType {
+    ValueType::String(String::new())
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct VarDefinition {
     name: String,
+    // 有 expr 时可省略, 计算结果会覆盖它
+    #[serde(default = "expr_placeholder")]
     value: ValueType,
-    //#[serde(skip_serializing_if = "Option::is_none")]
-    //constr: Option<ValueConstraint>,
+    // 计算变量, 在合并各层 value 后求值
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    expr: Option<String>,
+    // 模块共享 vars 中按 target 生效的条件, 如 `spc == k8s`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    when: Option<String>,
+    // 列表值的合并策略, 默认 replace
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    merge: Option<MergeStrategy>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    desc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    constr: Option<ValueConstraint>,
 }
 impl VarDefinition {
     pub(crate) fn var_value(&self) -> ValueType {
@@ -17,12 +34,55 @@ impl VarDefinition {
     pub(crate) fn name(&self) -> &str {
         self.name.as_str()
     }
+    pub(crate) fn set_value(&mut self, value: ValueType) {
+        self.value = value;
+    }
+    pub fn merge(&self) -> Option<MergeStrategy> {
+        self.merge
+    }
+    pub fn with_merge(mut self, strategy: MergeStrategy) -> Self {
+        self.merge = Some(strategy);
+        self
+    }
+    pub fn desc(&self) -> Option<&String> {
+        self.desc.as_ref()
+    }
+    pub fn with_desc<S: Into<String>>(mut self, desc: S) -> Self {
+        self.desc = Some(desc.into());
+        self
+    }
+    pub fn expr(&self) -> Option<&String> {
+        self.expr.as_ref()
+    }
+    pub fn with_expr<S: Into<String>>(mut self, expr: S) -> Self {
+        self.expr = Some(expr.into());
+        self
+    }
+    pub fn when(&self) -> Option<&String> {
+        self.when.as_ref()
+    }
+    pub fn with_when<S: Into<String>>(mut self, when: S) -> Self {
+        self.when = Some(when.into());
+        self
+    }
+    pub fn constr(&self) -> Option<&ValueConstraint> {
+        self.constr.as_ref()
+    }
+    pub fn with_constr(mut self, constr: ValueConstraint) -> Self {
+        self.constr = Some(constr);
+        self
+    }
 }
 impl From<(&str, &str)> for VarDefinition {
     fn from(value: (&str, &str)) -> Self {
         VarDefinition {
             name: value.0.to_string(),
             value: ValueType::from(value.1),
+            expr: None,
+            when: None,
+            merge: None,
+            desc: None,
+            constr: None,
         }
     }
 }
@@ -31,6 +91,11 @@ impl From<(&str, bool)> for VarDefinition {
         VarDefinition {
             name: value.0.to_string(),
             value: ValueType::from(value.1),
+            expr: None,
+            when: None,
+            merge: None,
+            desc: None,
+            constr: None,
         }
     }
 }
@@ -39,6 +104,11 @@ impl From<(&str, u64)> for VarDefinition {
         VarDefinition {
             name: value.0.to_string(),
             value: ValueType::from(value.1),
+            expr: None,
+            when: None,
+            merge: None,
+            desc: None,
+            constr: None,
         }
     }
 }
@@ -47,6 +117,11 @@ impl From<(&str, f64)> for VarDefinition {
         VarDefinition {
             name: value.0.to_string(),
             value: ValueType::from(value.1),
+            expr: None,
+            when: None,
+            merge: None,
+            desc: None,
+            constr: None,
         }
     }
 }
@@ -56,6 +131,11 @@ impl From<(&str, ValueType)> for VarDefinition {
         VarDefinition {
             name: value.0.to_string(),
             value: value.1,
+            expr: None,
+            when: None,
+            merge: None,
+            desc: None,
+            constr: None,
         }
     }
 }
@@ -5,10 +5,12 @@ use derive_more::{Deref, From};
 use indexmap::IndexMap;
 use serde_derive::{Deserialize, Serialize};
 
-use crate::{error::SpecResult, types::Yamlable};
+use crate::error::SpecResult;
 
 use super::{
-    EnvDict,
+    EnvDict, MergePolicy,
+    import::{ImportMode, import_value_file},
+    merge::{merge_value, strip_reset},
     types::{EnvEvalable, ValueType},
 };
 
@@ -41,13 +43,27 @@ impl ValueDict {
     pub fn insert<S: Into<String>>(&mut self, k: S, v: ValueType) -> Option<ValueType> {
         self.dict.insert(k.into(), v)
     }
+    // self 优先级高于 other, 相同 key 深度合并
     pub fn merge(&mut self, other: &ValueDict) {
+        self.merge_with(other, &MergePolicy::default());
+    }
+    pub fn merge_with(&mut self, other: &ValueDict, policy: &MergePolicy) {
         for (k, v) in other.iter() {
-            if !self.contains_key(k) {
+            if let Some(cur) = self.dict.get_mut(k) {
+                *cur = merge_value(cur, v, policy.strategy(k));
+            } else {
                 self.dict.insert(k.clone(), v.clone());
             }
         }
     }
+    // 移除 !reset 删除标记
+    pub fn strip_reset(&mut self) {
+        let dict = std::mem::take(&mut self.dict);
+        self.dict = dict
+            .into_iter()
+            .filter_map(|(k, v)| strip_reset(v).map(|v| (k, v)))
+            .collect();
+    }
     pub fn env_eval(self, dict: &EnvDict) -> Self {
         let mut map = ValueMap::new();
         for (k, v) in self.dict {
@@ -56,9 +72,17 @@ impl ValueDict {
         }
         Self { dict: map }
     }
+    // 按扩展名选择格式: yml/yaml, toml, json, ini, env
     pub fn eval_from_file(dict: &EnvDict, file_path: &Path) -> SpecResult<Self> {
+        Self::eval_import_file(dict, file_path, ImportMode::Structured)
+    }
+    pub fn eval_import_file(
+        dict: &EnvDict,
+        file_path: &Path,
+        mode: ImportMode,
+    ) -> SpecResult<Self> {
         let mut cur_dict = dict.clone();
-        let ins = ValueDict::from_yml(file_path)?;
+        let ins = import_value_file(file_path, mode)?;
         Ok(ins.eval_import(&mut cur_dict))
     }
 
@@ -3,6 +3,8 @@ use std::env;
 use log::debug;
 use tracing::error;
 
+use crate::secret::SECRET_PREFIX;
+
 use super::{EnvDict, ValueType};
 
 pub fn expand_env_vars(dict: &EnvDict, input: &str) -> String {
@@ -28,7 +30,12 @@ pub fn expand_env_vars(dict: &EnvDict, input: &str) -> String {
 
             // 处理变量替换
             if found_closing_brace {
-                if let Some(ValueType::String(value)) = dict.get(&var_name) {
+                if var_name.starts_with(SECRET_PREFIX) {
+                    // secret 引用在渲染时才解析
+                    result.push_str("${");
+                    result.push_str(&var_name);
+                    result.push('}');
+                } else if let Some(ValueType::String(value)) = dict.get(&var_name) {
                     result.push_str(value);
                 } else {
                     match env::var(&var_name) {
@@ -57,10 +64,26 @@ pub fn expand_env_vars(dict: &EnvDict, input: &str) -> String {
     result
 }
 
+// 字符串中 ${VAR} 引用的变量名, 不含 secret 引用
+pub fn env_var_refs(input: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = input;
+    while let Some(beg) = rest.find("${") {
+        rest = &rest[beg + 2..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[..end];
+        if !name.is_empty() && !name.starts_with(SECRET_PREFIX) {
+            names.push(name.to_string());
+        }
+        rest = &rest[end + 1..];
+    }
+    names
+}
+
 #[cfg(test)]
 mod tests {
-    use std::env;
-
     use crate::{
         tools::get_repo_name,
         vars::{EnvDict, ValueType, env_eval::expand_env_vars},
@@ -96,24 +119,35 @@ mod tests {
         assert_eq!(get_repo_name("not_a_url"), None);
     }
 
+    fn dict_of(items: &[(&str, &str)]) -> EnvDict {
+        let mut dict = EnvDict::new();
+        for (k, v) in items {
+            dict.insert(*k, ValueType::from(*v));
+        }
+        dict
+    }
+
     #[test]
     fn test_basic_expansion() {
-        unsafe { env::set_var("HOME", "/home/user") };
+        let dict = dict_of(&[("HOME", "/home/user")]);
+        assert_eq!(expand_env_vars(&dict, "${HOME}/bin"), "/home/user/bin");
+    }
+
+    #[test]
+    fn test_process_env_fallback() {
+        // cargo test 运行时会设置 CARGO_PKG_NAME
         assert_eq!(
-            expand_env_vars(&EnvDict::default(), "${HOME}/bin"),
-            "/home/user/bin"
+            expand_env_vars(&EnvDict::default(), "${CARGO_PKG_NAME}/bin"),
+            format!("{}/bin", env!("CARGO_PKG_NAME"))
         );
     }
 
     #[test]
     fn test_multiple_variables() {
-        unsafe { env::set_var("USER", "john") };
-        unsafe { env::set_var("APP", "myapp") };
-        let mut dict = EnvDict::new();
-        dict.insert("USER", ValueType::from("galaxy"));
-        dict.insert("APP", ValueType::from("galaxy"));
+        let env = dict_of(&[("USER", "john"), ("APP", "myapp")]);
+        let dict = dict_of(&[("USER", "galaxy"), ("APP", "galaxy")]);
         assert_eq!(
-            expand_env_vars(&EnvDict::default(), "/opt/${APP}/bin/${USER}"),
+            expand_env_vars(&env, "/opt/${APP}/bin/${USER}"),
             "/opt/myapp/bin/john"
         );
         assert_eq!(
@@ -124,28 +158,24 @@ mod tests {
 
     #[test]
     fn test_undefined_variable() {
-        unsafe { env::remove_var("UNDEFINED_VAR") };
         assert_eq!(
-            expand_env_vars(&EnvDict::default(), "Path: ${UNDEFINED_VAR}/data"),
-            "Path: ${UNDEFINED_VAR}/data"
+            expand_env_vars(&EnvDict::default(), "Path: ${GXL_UNDEFINED_VAR_4T}/data"),
+            "Path: ${GXL_UNDEFINED_VAR_4T}/data"
         );
     }
 
     #[test]
     fn test_nested_braces() {
-        unsafe { env::set_var("VAR", "value") };
-        assert_eq!(expand_env_vars(&EnvDict::default(), "${VAR}}"), "value}");
-        assert_eq!(expand_env_vars(&EnvDict::default(), "${VAR}}}"), "value}}");
+        let dict = dict_of(&[("VAR", "value")]);
+        assert_eq!(expand_env_vars(&dict, "${VAR}}"), "value}");
+        assert_eq!(expand_env_vars(&dict, "${VAR}}}"), "value}}");
     }
 
     #[test]
     fn test_unclosed_brace() {
-        unsafe { env::set_var("HOME", "/home/user") };
-        assert_eq!(expand_env_vars(&EnvDict::default(), "${HOME"), "${HOME");
-        assert_eq!(
-            expand_env_vars(&EnvDict::default(), "${HOME${USER"),
-            "${HOME${USER"
-        );
+        let dict = dict_of(&[("HOME", "/home/user")]);
+        assert_eq!(expand_env_vars(&dict, "${HOME"), "${HOME");
+        assert_eq!(expand_env_vars(&dict, "${HOME${USER"), "${HOME${USER");
     }
 
     #[test]
@@ -155,11 +185,8 @@ mod tests {
 
     #[test]
     fn test_special_characters() {
-        unsafe { env::set_var("VAR_WITH_UNDERSCORE", "ok") };
-        assert_eq!(
-            expand_env_vars(&EnvDict::default(), "${VAR_WITH_UNDERSCORE}"),
-            "ok"
-        );
+        let dict = dict_of(&[("VAR_WITH_UNDERSCORE", "ok")]);
+        assert_eq!(expand_env_vars(&dict, "${VAR_WITH_UNDERSCORE}"), "ok");
     }
 
     #[test]
@@ -175,10 +202,25 @@ mod tests {
         assert_eq!(expand_env_vars(&EnvDict::default(), "${}"), "${}");
     }
 
+    #[test]
+    fn test_secret_ref_keep() {
+        let mut dict = EnvDict::new();
+        dict.insert("secret:env:DB_PASS", ValueType::from("leak"));
+        assert_eq!(
+            expand_env_vars(&dict, "root:${secret:env:DB_PASS}"),
+            "root:${secret:env:DB_PASS}"
+        );
+    }
+
+    #[test]
+    fn test_env_var_refs() {
+        let refs = super::env_var_refs("${A}:${B}/${secret:file:/tmp/x} ${} ${C");
+        assert_eq!(refs, vec!["A".to_string(), "B".to_string()]);
+    }
+
     #[test]
     fn test_consecutive_variables() {
-        unsafe { env::set_var("A", "1") };
-        unsafe { env::set_var("B", "2") };
-        assert_eq!(expand_env_vars(&EnvDict::default(), "${A}${B}"), "12");
+        let dict = dict_of(&[("A", "1"), ("B", "2")]);
+        assert_eq!(expand_env_vars(&dict, "${A}${B}"), "12");
     }
 }
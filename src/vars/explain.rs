@@ -0,0 +1,290 @@
+use std::{
+    fmt::Display,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use derive_getters::Getters;
+use serde_derive::Serialize;
+
+use crate::{error::SpecResult, secret::redact_value, types::Yamlable};
+
+use super::{
+    ImportMode, RuntimeContext, ValueDict, ValueFormat, ValueType, VarCollection,
+    import::import_value_file,
+    location::{assign_key_line, value_key_location, var_name_line},
+};
+
+pub const ENV_SOURCE: &str = "env";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LayerKind {
+    // key: value 形式的 value.yml
+    Value,
+    // vars: [{name, value}] 形式的 vars.yml
+    Vars,
+    // 已筛选的 vars, 如按 when 过滤后的模块共享 vars
+    Defined(VarCollection),
+}
+
+#[derive(Getters, Clone, Debug)]
+pub struct ValueLayer {
+    source: String,
+    path: PathBuf,
+    kind: LayerKind,
+    mode: ImportMode,
+}
+
+// 变量在某一层中的定义
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct LayerHit {
+    source: String,
+    path: Option<PathBuf>,
+    line: Option<usize>,
+    raw: ValueType,
+    value: ValueType,
+}
+
+#[derive(Getters, Clone, Debug, Serialize)]
+pub struct VarExplain {
+    key: String,
+    hits: Vec<LayerHit>,
+}
+
+impl VarExplain {
+    // hits 按优先级排列, 第一个即生效值
+    pub fn winner(&self) -> Option<&LayerHit> {
+        self.hits.first()
+    }
+    // 不来自文件的计算值, 如 expr 变量, 优先级最高
+    pub fn with_computed<S: Into<String>>(
+        mut self,
+        source: S,
+        path: PathBuf,
+        raw: ValueType,
+        value: ValueType,
+    ) -> Self {
+        let line = find_key_line(&path, &self.key, &LayerKind::Vars);
+        self.hits.insert(
+            0,
+            LayerHit {
+                source: source.into(),
+                path: Some(path),
+                line,
+                raw,
+                value,
+            },
+        );
+        self
+    }
+}
+
+impl Display for VarExplain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}:", self.key)?;
+        if self.hits.is_empty() {
+            return writeln!(f, "  (undefined)");
+        }
+        for (idx, hit) in self.hits.iter().enumerate() {
+            let mark = if idx == 0 { "*" } else { " " };
+            let location = match (&hit.path, hit.line) {
+                (Some(path), Some(line)) => format!("{}:{}", path.display(), line),
+                (Some(path), None) => path.display().to_string(),
+                _ => "-".to_string(),
+            };
+            writeln!(f, "{} {:<24} {}", mark, hit.source, location)?;
+            // 报告输出不展示 secret 引用
+            let raw = redact_value(hit.raw.clone());
+            let value = redact_value(hit.value.clone());
+            if raw == value {
+                writeln!(f, "    value: {}", value)?;
+            } else {
+                writeln!(f, "    raw  : {}", raw)?;
+                writeln!(f, "    value: {}", value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// 按 localize 的取值顺序重放各层, 记录定义了 key 的每一层
+#[derive(Getters, Clone, Debug, Default)]
+pub struct ValueExplainer {
+    layers: Vec<ValueLayer>,
+    runtime: RuntimeContext,
+}
+
+impl ValueExplainer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_runtime(mut self, runtime: RuntimeContext) -> Self {
+        self.runtime = runtime;
+        self
+    }
+    // 层按优先级从高到低加入, 不存在的文件忽略
+    pub fn with_layer<S: Into<String>>(
+        mut self,
+        source: S,
+        path: PathBuf,
+        kind: LayerKind,
+    ) -> Self {
+        if path.exists() {
+            self.layers.push(ValueLayer {
+                source: source.into(),
+                path,
+                kind,
+                mode: ImportMode::Structured,
+            });
+        }
+        self
+    }
+    pub fn with_value_file<S: Into<String>>(self, source: S, path: PathBuf) -> Self {
+        self.with_layer(source, path, LayerKind::Value)
+    }
+    pub fn with_vars_file<S: Into<String>>(self, source: S, path: PathBuf) -> Self {
+        self.with_layer(source, path, LayerKind::Vars)
+    }
+    pub fn with_defined_vars<S: Into<String>>(
+        mut self,
+        source: S,
+        path: PathBuf,
+        vars: VarCollection,
+    ) -> Self {
+        if !vars.vars().is_empty() {
+            self.layers.push(ValueLayer {
+                source: source.into(),
+                path,
+                kind: LayerKind::Defined(vars),
+                mode: ImportMode::Structured,
+            });
+        }
+        self
+    }
+    // 非 yaml 格式或需要展开嵌套 key 的 value 文件
+    pub fn with_import_file<S: Into<String>>(
+        mut self,
+        source: S,
+        path: PathBuf,
+        mode: ImportMode,
+    ) -> Self {
+        self = self.with_layer(source, path, LayerKind::Value);
+        if let Some(layer) = self.layers.last_mut() {
+            layer.mode = mode;
+        }
+        self
+    }
+
+    pub fn explain(&self, key: &str) -> SpecResult<VarExplain> {
+        let mut used = ValueDict::new();
+        let mut hits = Vec::new();
+        for layer in &self.layers {
+            let (raw, evaluated) = match &layer.kind {
+                LayerKind::Value => (
+                    import_value_file(&layer.path, layer.mode)?,
+                    ValueDict::eval_import_file(
+                        &self.runtime.eval_dict(&used),
+                        &layer.path,
+                        layer.mode,
+                    )?,
+                ),
+                LayerKind::Vars => (
+                    VarCollection::from_yml(&layer.path)?.value_dict(),
+                    VarCollection::eval_from_file(&ValueDict::default(), &layer.path)?
+                        .value_dict()
+                        .env_eval(&self.runtime.eval_dict(&used)),
+                ),
+                LayerKind::Defined(vars) => (
+                    vars.value_dict(),
+                    vars.value_dict().env_eval(&self.runtime.eval_dict(&used)),
+                ),
+            };
+            if let Some(raw_v) = raw.get(key) {
+                hits.push(LayerHit {
+                    source: layer.source.clone(),
+                    path: Some(layer.path.clone()),
+                    line: find_key_line(&layer.path, key, &layer.kind),
+                    raw: raw_v.clone(),
+                    value: evaluated.get(key).cloned().unwrap_or(raw_v.clone()),
+                });
+            }
+            used.merge(&evaluated);
+        }
+        // 未在字典中定义时, ${KEY} 会回退到运行时上下文及进程环境变量
+        let env_v = match self.runtime.env_dict().get(key) {
+            Some(v) => Some(v.clone()),
+            None => std::env::var(key).ok().map(|x| ValueType::from(x.as_str())),
+        };
+        if let Some(value) = env_v {
+            hits.push(LayerHit {
+                source: ENV_SOURCE.to_string(),
+                path: None,
+                line: None,
+                raw: value.clone(),
+                value,
+            });
+        }
+        Ok(VarExplain {
+            key: key.to_string(),
+            hits,
+        })
+    }
+}
+
+// 行号从 1 开始; value 文件只匹配顶层 key, vars 文件匹配 `name: key`
+fn find_key_line(path: &Path, key: &str, kind: &LayerKind) -> Option<usize> {
+    let content = read_to_string(path).ok()?;
+    match kind {
+        LayerKind::Value => match ValueFormat::from_path(path) {
+            ValueFormat::Yaml | ValueFormat::Json => {
+                value_key_location(&content, key).map(|(line, _)| line)
+            }
+            _ => assign_key_line(&content, key),
+        },
+        LayerKind::Vars | LayerKind::Defined(_) => var_name_line(&content, key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn test_explain_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = dir.path().join("value.yml");
+        let cust = dir.path().join("mod_value.yml");
+        let vars = dir.path().join("vars.yml");
+        write(&global, "HOST: 10.0.0.1\n").unwrap();
+        write(&cust, "PORT: 5432\nDB_URL: ${HOST}:5432\n").unwrap();
+        write(
+            &vars,
+            "vars:\n  - name: DB_URL\n    value: localhost\n  - name: HOST\n    value: 127.0.0.1\n",
+        )
+        .unwrap();
+        let explainer = ValueExplainer::new()
+            .with_value_file("global", global.clone())
+            .with_value_file("mod-cust", cust.clone())
+            .with_value_file("miss", dir.path().join("none.yml"))
+            .with_vars_file("mod-default", vars.clone());
+        assert_eq!(explainer.layers().len(), 3);
+
+        let url = explainer.explain("DB_URL").unwrap();
+        assert_eq!(url.hits().len(), 2);
+        let winner = url.winner().unwrap();
+        assert_eq!(winner.source(), "mod-cust");
+        assert_eq!(winner.line(), &Some(2));
+        assert_eq!(winner.raw(), &ValueType::from("${HOST}:5432"));
+        assert_eq!(winner.value(), &ValueType::from("10.0.0.1:5432"));
+        assert_eq!(url.hits()[1].source(), "mod-default");
+        assert_eq!(url.hits()[1].line(), &Some(2));
+
+        let host = explainer.explain("HOST").unwrap();
+        assert_eq!(host.winner().unwrap().source(), "global");
+        assert_eq!(host.hits()[1].line(), &Some(4));
+
+        let none = explainer.explain("NOT_DEFINED_KEY").unwrap();
+        assert!(none.winner().is_none());
+    }
+}
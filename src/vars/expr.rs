@@ -0,0 +1,674 @@
+use std::{fmt::Display, str::FromStr};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use orion_common::cond::{CmpOP, LogicOP, WildMatchAble};
+
+use crate::error::{LocalizeReason, SpecError, SpecReason, SpecResult, ToErr};
+
+use super::{ValueDict, ValueType};
+
+pub const PASSWORD_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz23456789";
+pub const DEFAULT_PASSWORD_LEN: usize = 16;
+pub const MAX_PASSWORD_LEN: usize = 256;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ArithOP {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Display for ArithOP {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithOP::Add => write!(f, "+"),
+            ArithOP::Sub => write!(f, "-"),
+            ArithOP::Mul => write!(f, "*"),
+            ArithOP::Div => write!(f, "/"),
+            ArithOP::Rem => write!(f, "%"),
+        }
+    }
+}
+
+// vars.yml 中 expr 的语法树; 比较与逻辑运算沿用 orion_common::cond 的定义
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Const(ValueType),
+    // 字符串字面量, 其中的 ${VAR} 在求值时替换
+    Str(String),
+    // NAME 或 NAME.sub.key
+    Var(String),
+    Neg(Box<Expr>),
+    Arith(ArithOP, Box<Expr>, Box<Expr>),
+    Compare(CmpOP, Box<Expr>, Box<Expr>),
+    Logic(LogicOP, Option<Box<Expr>>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+fn expr_err<T, S: Into<String>>(msg: S) -> SpecResult<T> {
+    SpecReason::from(LocalizeReason::Expr(msg.into())).err_result()
+}
+
+// 求值上下文; 随机值按 key 缓存在 generated 中, 保证多次求值结果一致
+pub struct ExprContext<'a> {
+    scope: &'a ValueDict,
+    generated: &'a mut ValueDict,
+    key: String,
+    rand_cnt: usize,
+}
+
+impl<'a> ExprContext<'a> {
+    pub fn new<S: Into<String>>(
+        key: S,
+        scope: &'a ValueDict,
+        generated: &'a mut ValueDict,
+    ) -> Self {
+        Self {
+            scope,
+            generated,
+            key: key.into(),
+            rand_cnt: 0,
+        }
+    }
+    fn lookup(&self, path: &str) -> SpecResult<ValueType> {
+        let mut segs = path.split('.');
+        let first = segs.next().unwrap_or_default();
+        let Some(mut cur) = self.scope.get(first) else {
+            return expr_err(format!("undefined var: {}", path));
+        };
+        for seg in segs {
+            match cur {
+                ValueType::Obj(obj) if obj.contains_key(seg) => cur = &obj[seg],
+                _ => return expr_err(format!("undefined var: {}", path)),
+            }
+        }
+        Ok(cur.clone())
+    }
+    fn random_password(&mut self, len: usize) -> SpecResult<ValueType> {
+        let key = if self.rand_cnt == 0 {
+            self.key.clone()
+        } else {
+            format!("{}.{}", self.key, self.rand_cnt)
+        };
+        self.rand_cnt += 1;
+        if let Some(v) = self.generated.get(&key) {
+            return Ok(v.clone());
+        }
+        if len == 0 || len > MAX_PASSWORD_LEN {
+            return expr_err(format!(
+                "random_password len {} not in 1-{}",
+                len, MAX_PASSWORD_LEN
+            ));
+        }
+        // 拒绝采样: 丢弃超出字符表整数倍的字节, 避免取模带来的偏差
+        let limit = 256 - 256 % PASSWORD_CHARS.len();
+        let mut password = String::with_capacity(len);
+        let mut buf = [0u8; 64];
+        while password.len() < len {
+            if let Err(e) = getrandom::fill(&mut buf) {
+                return expr_err(format!("random: {}", e));
+            }
+            for x in buf.iter().filter(|x| (**x as usize) < limit) {
+                if password.len() == len {
+                    break;
+                }
+                password.push(PASSWORD_CHARS[*x as usize % PASSWORD_CHARS.len()] as char);
+            }
+        }
+        let value = ValueType::from(password.as_str());
+        self.generated.insert(key, value.clone());
+        Ok(value)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = SpecError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = ExprParser {
+            src: s.chars().collect(),
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        parser.skip_ws();
+        if parser.pos < parser.src.len() {
+            return expr_err(format!("unexpected '{}' in: {}", parser.rest(), s));
+        }
+        Ok(expr)
+    }
+}
+
+struct ExprParser {
+    src: Vec<char>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn rest(&self) -> String {
+        self.src[self.pos..].iter().collect()
+    }
+    fn skip_ws(&mut self) {
+        while self.pos < self.src.len() && self.src[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.src.get(self.pos).copied()
+    }
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        let chars: Vec<char> = token.chars().collect();
+        if self.src[self.pos..].starts_with(&chars) {
+            self.pos += chars.len();
+            return true;
+        }
+        false
+    }
+    fn expect(&mut self, token: &str) -> SpecResult<()> {
+        if self.eat(token) {
+            return Ok(());
+        }
+        expr_err(format!("expect '{}' at: {}", token, self.rest()))
+    }
+
+    fn parse_or(&mut self) -> SpecResult<Expr> {
+        let mut left = self.parse_and()?;
+        while self.eat("||") {
+            let right = self.parse_and()?;
+            left = Expr::Logic(LogicOP::Or, Some(Box::new(left)), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn parse_and(&mut self) -> SpecResult<Expr> {
+        let mut left = self.parse_cmp()?;
+        while self.eat("&&") {
+            let right = self.parse_cmp()?;
+            left = Expr::Logic(LogicOP::And, Some(Box::new(left)), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn parse_cmp(&mut self) -> SpecResult<Expr> {
+        let left = self.parse_add()?;
+        // 长的符号在前
+        let ops = [
+            ("=*", CmpOP::We),
+            ("==", CmpOP::Eq),
+            ("!=", CmpOP::Ne),
+            (">=", CmpOP::Ge),
+            ("<=", CmpOP::Le),
+            (">", CmpOP::Gt),
+            ("<", CmpOP::Lt),
+        ];
+        for (symbol, op) in ops {
+            if self.eat(symbol) {
+                let right = self.parse_add()?;
+                return Ok(Expr::Compare(op, Box::new(left), Box::new(right)));
+            }
+        }
+        Ok(left)
+    }
+    fn parse_add(&mut self) -> SpecResult<Expr> {
+        let mut left = self.parse_mul()?;
+        loop {
+            let op = if self.eat("+") {
+                ArithOP::Add
+            } else if self.eat("-") {
+                ArithOP::Sub
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_mul()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+    fn parse_mul(&mut self) -> SpecResult<Expr> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = if self.eat("*") {
+                ArithOP::Mul
+            } else if self.eat("/") {
+                ArithOP::Div
+            } else if self.eat("%") {
+                ArithOP::Rem
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_unary()?;
+            left = Expr::Arith(op, Box::new(left), Box::new(right));
+        }
+    }
+    fn parse_unary(&mut self) -> SpecResult<Expr> {
+        if self.peek() == Some('!') && self.src.get(self.pos + 1) != Some(&'=') {
+            self.pos += 1;
+            let right = self.parse_unary()?;
+            return Ok(Expr::Logic(LogicOP::Not, None, Box::new(right)));
+        }
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+    fn parse_primary(&mut self) -> SpecResult<Expr> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.parse_or()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(q @ ('\'' | '"')) => {
+                self.pos += 1;
+                let mut s = String::new();
+                while let Some(c) = self.src.get(self.pos).copied() {
+                    self.pos += 1;
+                    if c == q {
+                        return Ok(Expr::Str(s));
+                    }
+                    s.push(c);
+                }
+                expr_err("unclosed string")
+            }
+            Some('$') => {
+                self.expect("${")?;
+                let name = self.ident()?;
+                self.expect("}")?;
+                Ok(Expr::Var(name))
+            }
+            Some(c) if c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.ident()?;
+                match name.as_str() {
+                    "true" => return Ok(Expr::Const(ValueType::Bool(true))),
+                    "false" => return Ok(Expr::Const(ValueType::Bool(false))),
+                    _ => {}
+                }
+                if self.eat("(") {
+                    let mut args = Vec::new();
+                    if !self.eat(")") {
+                        loop {
+                            args.push(self.parse_or()?);
+                            if self.eat(")") {
+                                break;
+                            }
+                            self.expect(",")?;
+                        }
+                    }
+                    return Ok(Expr::Call(name, args));
+                }
+                Ok(Expr::Var(name))
+            }
+            _ => expr_err(format!("unexpected: '{}'", self.rest())),
+        }
+    }
+    fn ident(&mut self) -> SpecResult<String> {
+        self.skip_ws();
+        let beg = self.pos;
+        while let Some(c) = self.src.get(self.pos)
+            && (c.is_alphanumeric() || *c == '_' || *c == '.')
+        {
+            self.pos += 1;
+        }
+        if beg == self.pos {
+            return expr_err(format!("expect name at: {}", self.rest()));
+        }
+        Ok(self.src[beg..self.pos].iter().collect())
+    }
+    fn number(&mut self) -> SpecResult<Expr> {
+        let beg = self.pos;
+        while let Some(c) = self.src.get(self.pos)
+            && (c.is_ascii_digit() || *c == '.')
+        {
+            self.pos += 1;
+        }
+        let text: String = self.src[beg..self.pos].iter().collect();
+        if let Ok(v) = text.parse::<u64>() {
+            return Ok(Expr::Const(ValueType::Int(v)));
+        }
+        match text.parse::<f64>() {
+            Ok(v) => Ok(Expr::Const(ValueType::Float(v))),
+            Err(_) => expr_err(format!("bad number: {}", text)),
+        }
+    }
+}
+
+fn to_number(v: &ValueType) -> SpecResult<f64> {
+    match v {
+        ValueType::Int(x) => Ok(*x as f64),
+        ValueType::Float(x) => Ok(*x),
+        ValueType::String(s) => match s.trim().parse::<f64>() {
+            Ok(x) => Ok(x),
+            Err(_) => expr_err(format!("not a number: {}", s)),
+        },
+        _ => expr_err(format!("not a number: {}", v)),
+    }
+}
+
+// 非负整数保持 Int, 与 ValueType 的反序列化一致
+fn from_number(v: f64) -> ValueType {
+    if v >= 0.0 && v.fract() == 0.0 && v <= u64::MAX as f64 {
+        ValueType::Int(v as u64)
+    } else {
+        ValueType::Float(v)
+    }
+}
+
+fn is_true(v: &ValueType) -> bool {
+    match v {
+        ValueType::Bool(x) => *x,
+        ValueType::Int(x) => *x != 0,
+        ValueType::Float(x) => *x != 0.0,
+        ValueType::String(s) => !(s.is_empty() || s == "false"),
+        ValueType::List(x) => !x.is_empty(),
+        ValueType::Obj(x) => !x.is_empty(),
+        ValueType::Reset => false,
+    }
+}
+
+fn is_number(v: &ValueType) -> bool {
+    matches!(v, ValueType::Int(_) | ValueType::Float(_))
+}
+
+impl Expr {
+    pub fn eval(&self, ctx: &mut ExprContext) -> SpecResult<ValueType> {
+        match self {
+            Expr::Const(v) => Ok(v.clone()),
+            Expr::Str(s) => interpolate(s, ctx).map(|x| ValueType::from(x.as_str())),
+            Expr::Var(name) => ctx.lookup(name),
+            Expr::Neg(x) => Ok(from_number(-to_number(&x.eval(ctx)?)?)),
+            Expr::Arith(op, l, r) => arith(*op, &l.eval(ctx)?, &r.eval(ctx)?),
+            Expr::Compare(op, l, r) => {
+                let (l, r) = (l.eval(ctx)?, r.eval(ctx)?);
+                Ok(ValueType::Bool(compare(op, &l, &r)?))
+            }
+            Expr::Logic(op, l, r) => {
+                let value = match (op, l) {
+                    (LogicOP::And, Some(l)) => is_true(&l.eval(ctx)?) && is_true(&r.eval(ctx)?),
+                    (LogicOP::Or, Some(l)) => is_true(&l.eval(ctx)?) || is_true(&r.eval(ctx)?),
+                    (LogicOP::Not, _) => !is_true(&r.eval(ctx)?),
+                    (_, None) => is_true(&r.eval(ctx)?),
+                };
+                Ok(ValueType::Bool(value))
+            }
+            Expr::Call(name, args) => call(name, args, ctx),
+        }
+    }
+
+    // 表达式中引用的变量名(取首段)
+    pub fn var_refs(&self) -> Vec<String> {
+        let mut refs = Vec::new();
+        self.collect_refs(&mut refs);
+        refs
+    }
+    fn collect_refs(&self, refs: &mut Vec<String>) {
+        match self {
+            Expr::Const(_) => {}
+            Expr::Str(s) => refs.extend(super::env_var_refs(s)),
+            Expr::Var(name) => refs.push(name.split('.').next().unwrap_or(name).to_string()),
+            Expr::Neg(x) => x.collect_refs(refs),
+            Expr::Arith(_, l, r) | Expr::Compare(_, l, r) => {
+                l.collect_refs(refs);
+                r.collect_refs(refs);
+            }
+            Expr::Logic(_, l, r) => {
+                if let Some(l) = l {
+                    l.collect_refs(refs);
+                }
+                r.collect_refs(refs);
+            }
+            Expr::Call(_, args) => args.iter().for_each(|x| x.collect_refs(refs)),
+        }
+    }
+}
+
+fn interpolate(s: &str, ctx: &ExprContext) -> SpecResult<String> {
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(beg) = rest.find("${") {
+        out.push_str(&rest[..beg]);
+        let Some(end) = rest[beg..].find('}') else {
+            out.push_str(&rest[beg..]);
+            return Ok(out);
+        };
+        let name = &rest[beg + 2..beg + end];
+        out.push_str(&ctx.lookup(name)?.to_string());
+        rest = &rest[beg + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn arith(op: ArithOP, l: &ValueType, r: &ValueType) -> SpecResult<ValueType> {
+    // 有一侧为非数值字符串时 + 为拼接
+    if op == ArithOP::Add && (to_number(l).is_err() || to_number(r).is_err()) {
+        return Ok(ValueType::String(format!("{}{}", l, r)));
+    }
+    let (a, b) = (to_number(l)?, to_number(r)?);
+    let v = match op {
+        ArithOP::Add => a + b,
+        ArithOP::Sub => a - b,
+        ArithOP::Mul => a * b,
+        ArithOP::Div | ArithOP::Rem if b == 0.0 => return expr_err("divide by zero"),
+        ArithOP::Div => a / b,
+        ArithOP::Rem => a % b,
+    };
+    Ok(from_number(v))
+}
+
+fn compare(op: &CmpOP, l: &ValueType, r: &ValueType) -> SpecResult<bool> {
+    if let (Ok(a), Ok(b)) = (to_number(l), to_number(r))
+        && (is_number(l) || is_number(r))
+    {
+        return Ok(match op {
+            CmpOP::We | CmpOP::Eq => a.wild_match(&b),
+            CmpOP::Ne => !a.wild_match(&b),
+            CmpOP::Gt => a > b,
+            CmpOP::Ge => a >= b,
+            CmpOP::Lt => a < b,
+            CmpOP::Le => a <= b,
+        });
+    }
+    let (a, b) = (l.to_string(), r.to_string());
+    Ok(match op {
+        // 右侧为通配模式
+        CmpOP::We => b.wild_match(&a),
+        CmpOP::Eq => a == b,
+        CmpOP::Ne => a != b,
+        CmpOP::Gt => a > b,
+        CmpOP::Ge => a >= b,
+        CmpOP::Lt => a < b,
+        CmpOP::Le => a <= b,
+    })
+}
+
+fn call(name: &str, args: &[Expr], ctx: &mut ExprContext) -> SpecResult<ValueType> {
+    // default/if 只对需要的分支求值
+    match name {
+        "default" => {
+            let [value, fallback] = args else {
+                return expr_err("default(value, fallback)");
+            };
+            return match value.eval(ctx) {
+                Ok(v) if is_true(&v) => Ok(v),
+                _ => fallback.eval(ctx),
+            };
+        }
+        "if" => {
+            let [cond, yes, no] = args else {
+                return expr_err("if(cond, then, else)");
+            };
+            return if is_true(&cond.eval(ctx)?) {
+                yes.eval(ctx)
+            } else {
+                no.eval(ctx)
+            };
+        }
+        _ => {}
+    }
+    let mut values = Vec::new();
+    for arg in args {
+        values.push(arg.eval(ctx)?);
+    }
+    let text = |idx: usize| values.get(idx).map(|x| x.to_string()).unwrap_or_default();
+    let one = |f: fn(f64) -> f64| -> SpecResult<ValueType> {
+        match values.as_slice() {
+            [v] => Ok(from_number(f(to_number(v)?))),
+            _ => expr_err(format!("{}(number)", name)),
+        }
+    };
+    match name {
+        "lower" => Ok(ValueType::from(text(0).to_lowercase().as_str())),
+        "upper" => Ok(ValueType::from(text(0).to_uppercase().as_str())),
+        "trim" => Ok(ValueType::from(text(0).trim())),
+        "str" => Ok(ValueType::from(text(0).as_str())),
+        "concat" => Ok(ValueType::String(
+            values.iter().map(|x| x.to_string()).collect(),
+        )),
+        "len" => Ok(ValueType::Int(match values.first() {
+            Some(ValueType::List(x)) => x.len(),
+            Some(ValueType::Obj(x)) => x.len(),
+            _ => text(0).chars().count(),
+        } as u64)),
+        "base64" => Ok(ValueType::String(STANDARD.encode(text(0)))),
+        "base64_decode" => match STANDARD.decode(text(0)) {
+            Ok(data) => Ok(ValueType::String(String::from_utf8_lossy(&data).into())),
+            Err(e) => expr_err(format!("base64_decode: {}", e)),
+        },
+        "int" | "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "round" => one(f64::round),
+        "float" => match values.as_slice() {
+            [v] => Ok(ValueType::Float(to_number(v)?)),
+            _ => expr_err("float(number)"),
+        },
+        "min" | "max" => {
+            let mut nums = Vec::new();
+            for v in &values {
+                nums.push(to_number(v)?);
+            }
+            let init = if name == "min" { f64::MAX } else { f64::MIN };
+            match nums.is_empty() {
+                true => expr_err(format!("{}(a, b, ...)", name)),
+                false => Ok(from_number(nums.into_iter().fold(init, |acc, x| {
+                    if name == "min" {
+                        acc.min(x)
+                    } else {
+                        acc.max(x)
+                    }
+                }))),
+            }
+        }
+        "random_password" => {
+            let len = match values.first() {
+                Some(v) => {
+                    let len = to_number(v)?;
+                    if len < 1.0 || len.fract() != 0.0 {
+                        return expr_err(format!("random_password len: {}", v));
+                    }
+                    len as usize
+                }
+                None => DEFAULT_PASSWORD_LEN,
+            };
+            ctx.random_password(len)
+        }
+        _ => expr_err(format!("unknown function: {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str, scope: &ValueDict) -> SpecResult<ValueType> {
+        let mut generated = ValueDict::new();
+        let expr = Expr::from_str(expr)?;
+        expr.eval(&mut ExprContext::new("KEY", scope, &mut generated))
+    }
+
+    fn scope() -> ValueDict {
+        let mut res = super::super::dict::ValueMap::new();
+        res.insert("mem_size".into(), ValueType::Int(4096));
+        let mut dict = ValueDict::new();
+        dict.insert("res", ValueType::Obj(res));
+        dict.insert("ROOT", ValueType::from("/opt"));
+        dict.insert("MOD_NAME", ValueType::from("Redis"));
+        dict.insert("PORT", ValueType::Int(6379));
+        dict
+    }
+
+    #[test]
+    fn test_expr_eval() {
+        let dict = scope();
+        assert_eq!(
+            eval("int(res.mem_size * 0.8)", &dict).unwrap(),
+            ValueType::Int(3276)
+        );
+        assert_eq!(
+            eval("res.mem_size * 0.5", &dict).unwrap(),
+            ValueType::Int(2048)
+        );
+        assert_eq!(
+            eval("'${ROOT}/data/' + lower(MOD_NAME)", &dict).unwrap(),
+            ValueType::from("/opt/data/redis")
+        );
+        assert_eq!(
+            eval("${ROOT} + '/' + PORT", &dict).unwrap(),
+            ValueType::from("/opt/6379")
+        );
+        assert_eq!(eval("PORT - 6380", &dict).unwrap(), ValueType::Float(-1.0));
+        assert_eq!(eval("(1 + 2) * 3 % 4", &dict).unwrap(), ValueType::Int(1));
+        assert_eq!(
+            eval("upper(MOD_NAME) == 'REDIS' && !(PORT < 1024)", &dict).unwrap(),
+            ValueType::Bool(true)
+        );
+        assert_eq!(
+            eval("MOD_NAME =* 'Re*'", &dict).unwrap(),
+            ValueType::Bool(true)
+        );
+        assert_eq!(
+            eval("if(PORT > 1024, 'high', 'low')", &dict).unwrap(),
+            ValueType::from("high")
+        );
+        assert_eq!(
+            eval("default(NOT_DEFINED, max(1, PORT, 3))", &dict).unwrap(),
+            ValueType::Int(6379)
+        );
+        assert_eq!(
+            eval("base64('admin:pass')", &dict).unwrap(),
+            ValueType::from("YWRtaW46cGFzcw==")
+        );
+        assert!(eval("NOT_DEFINED + 1", &dict).is_err());
+        assert!(eval("1 / 0", &dict).is_err());
+        assert!(eval("lower(MOD_NAME", &dict).is_err());
+        assert!(eval("unknown(1)", &dict).is_err());
+        assert_eq!(
+            Expr::from_str("concat(A.x, '${B}', C) + D")
+                .unwrap()
+                .var_refs(),
+            vec!["A", "B", "C", "D"]
+        );
+    }
+
+    #[test]
+    fn test_random_password_cached() {
+        let dict = ValueDict::new();
+        let expr = Expr::from_str("random_password(24)").unwrap();
+        let mut generated = ValueDict::new();
+        let first = expr
+            .eval(&mut ExprContext::new("DB_PASS", &dict, &mut generated))
+            .unwrap();
+        assert_eq!(first.to_string().len(), 24);
+        assert_eq!(generated.get("DB_PASS"), Some(&first));
+        let second = expr
+            .eval(&mut ExprContext::new("DB_PASS", &dict, &mut generated))
+            .unwrap();
+        assert_eq!(first, second);
+
+        assert!(eval("random_password(0)", &dict).is_err());
+        assert!(eval("random_password(-8)", &dict).is_err());
+        assert!(eval("random_password(100000)", &dict).is_err());
+        let max = eval(&format!("random_password({})", MAX_PASSWORD_LEN), &dict).unwrap();
+        assert!(max.to_string().bytes().all(|x| PASSWORD_CHARS.contains(&x)));
+    }
+}
@@ -1,23 +1,119 @@
-use std::{
-    env::{self, current_dir},
-    path::PathBuf,
-};
+use std::path::{Path, PathBuf};
 
-use log::info;
+use derive_getters::Getters;
+use once_cell::sync::Lazy;
 use orion_error::{ErrorOwe, ErrorWith};
 
-use crate::error::SpecResult;
+use crate::{
+    error::SpecResult,
+    module::{ModelSTD, target::TargetRegistry},
+};
 
-pub fn setup_start_env_vars() -> SpecResult<()> {
-    unsafe { std::env::set_var("GXL_OS_SYS", format_os_sys().as_str()) };
-    let start_root = current_dir().owe_sys().want("get current dir")?;
-    unsafe { std::env::set_var("GXL_START_ROOT", start_root.display().to_string()) };
-    let prj_root = find_project_define().unwrap_or(PathBuf::from("UNDEFIN"));
-    unsafe { std::env::set_var("GXL_PRJ_ROOT", format!("{}", prj_root.display())) };
-    Ok(())
+use super::{EnvDict, ValueDict, ValueType};
+
+pub const GXL_OS_SYS: &str = "GXL_OS_SYS";
+pub const GXL_START_ROOT: &str = "GXL_START_ROOT";
+pub const GXL_PRJ_ROOT: &str = "GXL_PRJ_ROOT";
+
+static OS_SYS: Lazy<String> = Lazy::new(format_os_sys);
+
+// 运行时上下文: 代替进程级的环境变量与当前目录, 可在同一进程中并存多份
+#[derive(Getters, Clone, Debug, PartialEq)]
+pub struct RuntimeContext {
+    os_sys: String,
+    start_root: PathBuf,
+    prj_root: Option<PathBuf>,
+    cwd: PathBuf,
+    envs: ValueDict,
+    // 命令行指定的 target, 代替 `model: auto` 的主机探测
+    host_model: Option<ModelSTD>,
+    // target 登记表: 命令行入口读取 ~/.galaxy/target_models.yml, 其他默认内置列表
+    targets: TargetRegistry,
+}
+
+impl Default for RuntimeContext {
+    fn default() -> Self {
+        Self {
+            os_sys: OS_SYS.clone(),
+            start_root: PathBuf::from("."),
+            prj_root: None,
+            cwd: PathBuf::from("."),
+            envs: ValueDict::new(),
+            host_model: None,
+            targets: TargetRegistry::builtin(),
+        }
+    }
+}
+
+impl RuntimeContext {
+    // 以进程当前目录为起点
+    pub fn detect() -> SpecResult<Self> {
+        let start_root = std::env::current_dir().owe_sys().want("get current dir")?;
+        Ok(Self::from_root(start_root).with_targets(TargetRegistry::global().clone()))
+    }
+    pub fn from_root<P: Into<PathBuf>>(start_root: P) -> Self {
+        let start_root = start_root.into();
+        Self {
+            os_sys: OS_SYS.clone(),
+            prj_root: find_project_define(&start_root),
+            cwd: start_root.clone(),
+            start_root,
+            envs: ValueDict::new(),
+            host_model: None,
+            targets: TargetRegistry::builtin(),
+        }
+    }
+    pub fn with_cwd<P: Into<PathBuf>>(mut self, cwd: P) -> Self {
+        self.cwd = cwd.into();
+        self
+    }
+    pub fn with_host_model(mut self, model: Option<ModelSTD>) -> Self {
+        self.host_model = model;
+        self
+    }
+    pub fn with_targets(mut self, targets: TargetRegistry) -> Self {
+        self.targets = targets;
+        self
+    }
+    pub fn with_env<S: Into<String>>(mut self, key: S, value: &str) -> Self {
+        self.envs.insert(key, ValueType::from(value));
+        self
+    }
+    // GXL_* 内置变量 + 叠加的环境变量, 叠加值优先
+    pub fn env_dict(&self) -> EnvDict {
+        let mut dict = self.envs.clone();
+        let prj_root = self.prj_root.clone().unwrap_or(PathBuf::from("UNDEFIN"));
+        let mut builtin = EnvDict::new();
+        builtin.insert(GXL_OS_SYS, ValueType::from(self.os_sys.as_str()));
+        builtin.insert(
+            GXL_START_ROOT,
+            ValueType::String(self.start_root.display().to_string()),
+        );
+        builtin.insert(
+            GXL_PRJ_ROOT,
+            ValueType::String(prj_root.display().to_string()),
+        );
+        dict.merge(&builtin);
+        dict
+    }
+    // dict 优先, 缺失的变量由运行时上下文补充
+    pub fn eval_dict(&self, dict: &EnvDict) -> EnvDict {
+        let mut cur = dict.clone();
+        cur.merge(&self.env_dict());
+        cur
+    }
+    // 相对路径以上下文 cwd 为基准
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        let path = path.as_ref();
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.cwd.join(path)
+        }
+    }
 }
 
-fn get_os_info() -> (String, String, u64) {
+pub(crate) fn get_os_info() -> (String, String, u64) {
     let info = os_info::get();
     let os_type = match info.os_type() {
         os_info::Type::Macos => "macos".to_string(),
@@ -38,15 +134,14 @@ fn format_os_sys() -> String {
     format!("{}_{}_{}", arch, os_type, ver_major)
 }
 
-/// 从当前目录开始向上查找 _gal/project.toml 文件
-/// 如果找到则返回其绝对路径的PathBuf，未找到则返回None
-pub fn find_project_define() -> Option<PathBuf> {
-    let mut current_dir = std::env::current_dir().expect("Failed to get current directory");
+/// 从 start 目录开始向上查找 _gal/project.toml 文件
+/// 如果找到则返回其所在目录，未找到则返回None
+pub fn find_project_define(start: &Path) -> Option<PathBuf> {
+    let mut current_dir = start.to_path_buf();
 
     loop {
         let project_file = current_dir.join("_gal").join("project.toml");
         if project_file.exists() {
-            //let project_root = current_dir.clone();
             return Some(current_dir);
         }
 
@@ -58,38 +153,14 @@ pub fn find_project_define() -> Option<PathBuf> {
 
     None
 }
-pub struct WorkDir {
-    original_dir: PathBuf,
-}
-
-impl WorkDir {
-    #[allow(dead_code)]
-    pub fn change<S: Into<PathBuf>>(target_dir: S) -> std::io::Result<Self> {
-        let original_dir = env::current_dir()?;
-        let target = target_dir.into();
-        info!("set current dir:{}", target.display());
-        env::set_current_dir(target)?;
-        Ok(Self { original_dir })
-    }
-}
-
-impl Drop for WorkDir {
-    fn drop(&mut self) {
-        info!("set current dir:{}", self.original_dir.display());
-        if let Err(e) = env::set_current_dir(&self.original_dir) {
-            log::error!("Failed to restore directory: {}", e);
-        }
-    }
-}
 
 #[cfg(test)]
 mod tests {
 
     use tempfile::TempDir;
 
-    use crate::vars::global::{WorkDir, find_project_define};
+    use super::*;
 
-    #[ignore = "change work dir"]
     #[test]
     fn test_find_project_define_in_current_dir() {
         // 创建临时目录
@@ -99,15 +170,12 @@ mod tests {
         let project_file = gal_dir.join("project.toml");
         std::fs::write(&project_file, "").expect("Failed to create project.toml");
 
-        // 设置当前工作目录为临时目录
-        //env::set_current_dir(temp_dir.path()).expect("Failed to set current dir");
-        let _wd = WorkDir::change(temp_dir.path());
-
-        // 调用函数并断言结果
-        assert!(find_project_define().is_some())
+        assert_eq!(
+            find_project_define(temp_dir.path()),
+            Some(temp_dir.path().to_path_buf())
+        )
     }
 
-    #[ignore = "change work dir"]
     #[test]
     fn test_find_project_define_in_parent_dir() {
         // 创建临时目录结构: temp_dir/child/_gal/project.toml
@@ -119,25 +187,44 @@ mod tests {
         let project_file = gal_dir.join("project.toml");
         std::fs::write(&project_file, "").expect("Failed to create project.toml");
 
-        // 设置当前工作目录为child_dir
-        let _wd = WorkDir::change(&child_dir);
-        //env::set_current_dir(&child_dir).expect("Failed to set current dir");
-
         // 调用函数应找到父目录中的文件
-        assert!(find_project_define().is_some());
+        assert!(find_project_define(&child_dir).is_some());
     }
 
-    #[ignore = "change work dir"]
     #[test]
     fn test_find_project_define_not_found() {
         // 创建临时目录，不创建_gal/project.toml
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
 
-        let _wd = WorkDir::change(temp_dir.path());
-        // 设置当前工作目录为临时目录
-        //env::set_current_dir(temp_dir.path()).expect("Failed to set current dir");
-
         // 调用函数应返回None
-        assert_eq!(find_project_define(), None);
+        assert_eq!(find_project_define(temp_dir.path()), None);
+    }
+
+    #[test]
+    fn test_runtime_env_dict() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let ctx = RuntimeContext::from_root(temp_dir.path())
+            .with_cwd("/srv/prj")
+            .with_env("APP", "galaxy")
+            .with_env(GXL_OS_SYS, "arm64_linux_22");
+        let dict = ctx.env_dict();
+        assert_eq!(dict.get("APP"), Some(&ValueType::from("galaxy")));
+        assert_eq!(
+            dict.get(GXL_OS_SYS),
+            Some(&ValueType::from("arm64_linux_22"))
+        );
+        assert_eq!(dict.get(GXL_PRJ_ROOT), Some(&ValueType::from("UNDEFIN")));
+        assert_eq!(
+            dict.get(GXL_START_ROOT),
+            Some(&ValueType::String(temp_dir.path().display().to_string()))
+        );
+        assert_eq!(ctx.resolve("depends"), PathBuf::from("/srv/prj/depends"));
+        assert_eq!(ctx.resolve("/opt"), PathBuf::from("/opt"));
+
+        let mut user = EnvDict::new();
+        user.insert("APP", ValueType::from("user"));
+        let eval = ctx.eval_dict(&user);
+        assert_eq!(eval.get("APP"), Some(&ValueType::from("user")));
+        assert!(eval.contains_key(GXL_OS_SYS));
     }
 }
@@ -0,0 +1,191 @@
+use std::{fs::read_to_string, path::Path, str::FromStr};
+
+use orion_error::{ErrorOwe, ErrorWith, WithContext};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    error::SpecResult,
+    types::{IniAble, JsonAble, Tomlable, Yamlable},
+};
+
+use super::{ValueDict, ValueType, dict::ValueMap};
+
+pub const FLATTEN_SEP: &str = ".";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValueFormat {
+    // 包括 Helm values.yaml
+    Yaml,
+    Toml,
+    Json,
+    Ini,
+    Env,
+}
+
+impl ValueFormat {
+    // 按扩展名判断, 无法识别时按 yaml 处理; `.env` / `xxx.env` 为 dotenv
+    pub fn from_path(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .unwrap_or_default();
+        if name == ".env" || name.starts_with(".env.") {
+            return ValueFormat::Env;
+        }
+        match path.extension().and_then(|x| x.to_str()) {
+            Some("toml") => ValueFormat::Toml,
+            Some("json") => ValueFormat::Json,
+            Some("ini") => ValueFormat::Ini,
+            Some("env") => ValueFormat::Env,
+            _ => ValueFormat::Yaml,
+        }
+    }
+}
+
+// 嵌套 key 的导入方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    // 保留层次结构
+    #[default]
+    Structured,
+    // 展开为 a.b.c
+    Flatten,
+}
+
+impl FromStr for ImportMode {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "structured" => Ok(ImportMode::Structured),
+            "flatten" => Ok(ImportMode::Flatten),
+            _ => Err(format!("unknown import mode: {}", s)),
+        }
+    }
+}
+
+pub fn import_value_file(path: &Path, mode: ImportMode) -> SpecResult<ValueDict> {
+    let dict = match ValueFormat::from_path(path) {
+        ValueFormat::Yaml => ValueDict::from_yml(path)?,
+        ValueFormat::Toml => ValueDict::from_toml(path)?,
+        ValueFormat::Json => ValueDict::from_json(path)?,
+        ValueFormat::Ini => ValueDict::from_ini(path)?,
+        ValueFormat::Env => {
+            let mut ctx = WithContext::want("load object from dotenv");
+            ctx.with_path("path", path);
+            let content = read_to_string(path).owe_res().with(&ctx)?;
+            parse_dotenv(&content)
+        }
+    };
+    Ok(match mode {
+        ImportMode::Structured => dict,
+        ImportMode::Flatten => flatten(dict),
+    })
+}
+
+// KEY=VALUE, 支持 export 前缀、# 注释和引号
+fn parse_dotenv(content: &str) -> ValueDict {
+    let mut dict = ValueDict::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        if let Some((k, v)) = line.split_once('=') {
+            let v = v.trim();
+            let v = if (v.starts_with('"') && v.ends_with('"') && v.len() >= 2)
+                || (v.starts_with('\'') && v.ends_with('\'') && v.len() >= 2)
+            {
+                &v[1..v.len() - 1]
+            } else {
+                v.split(" #").next().unwrap_or(v).trim_end()
+            };
+            dict.insert(k.trim(), ValueType::from(v));
+        }
+    }
+    dict
+}
+
+pub fn flatten(dict: ValueDict) -> ValueDict {
+    let mut map = ValueMap::new();
+    for (k, v) in dict.dict().clone() {
+        flatten_into(&mut map, k, v);
+    }
+    ValueDict::from(map)
+}
+
+fn flatten_into(map: &mut ValueMap, prefix: String, value: ValueType) {
+    match value {
+        ValueType::Obj(obj) if !obj.is_empty() => {
+            for (k, v) in obj {
+                flatten_into(map, format!("{}{}{}", prefix, FLATTEN_SEP, k), v);
+            }
+        }
+        _ => {
+            map.insert(prefix, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::write;
+
+    #[test]
+    fn test_import_formats() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("value.json");
+        write(&json, r#"{ "user": { "name": "Alice", "age": 30 } }"#).unwrap();
+        let dict = import_value_file(&json, ImportMode::Structured).unwrap();
+        assert_eq!(
+            dict.get("user").map(|x| x.to_string()),
+            Some(r#"{"name":"Alice","age":30}"#.to_string())
+        );
+        let dict = import_value_file(&json, ImportMode::Flatten).unwrap();
+        assert_eq!(dict.get("user.name"), Some(&ValueType::from("Alice")));
+        assert_eq!(dict.get("user.age"), Some(&ValueType::Int(30)));
+
+        let toml = dir.path().join("value.toml");
+        write(&toml, "port = 80\n[db]\nhost = \"10.0.0.1\"\n").unwrap();
+        let dict = import_value_file(&toml, ImportMode::Flatten).unwrap();
+        assert_eq!(dict.get("port"), Some(&ValueType::Int(80)));
+        assert_eq!(dict.get("db.host"), Some(&ValueType::from("10.0.0.1")));
+
+        let ini = dir.path().join("value.ini");
+        write(&ini, "[db]\nhost=10.0.0.2\n").unwrap();
+        let dict = import_value_file(&ini, ImportMode::Flatten).unwrap();
+        assert_eq!(dict.get("db.host"), Some(&ValueType::from("10.0.0.2")));
+
+        let helm = dir.path().join("values.yaml");
+        write(
+            &helm,
+            "image:\n  repository: nginx\n  tag: \"1.25\"\nreplicaCount: 2\n",
+        )
+        .unwrap();
+        let dict = import_value_file(&helm, ImportMode::Flatten).unwrap();
+        assert_eq!(dict.get("image.tag"), Some(&ValueType::from("1.25")));
+        assert_eq!(dict.get("replicaCount"), Some(&ValueType::Int(2)));
+    }
+
+    #[test]
+    fn test_import_dotenv() {
+        let dir = tempfile::tempdir().unwrap();
+        let env = dir.path().join(".env");
+        write(
+            &env,
+            "# comment\nexport DB_HOST=10.0.0.1\nDB_PASS=\"a b#c\"\nDB_PORT=3306 # port\n\n",
+        )
+        .unwrap();
+        assert_eq!(ValueFormat::from_path(&env), ValueFormat::Env);
+        let dict = import_value_file(&env, ImportMode::Structured).unwrap();
+        assert_eq!(dict.get("DB_HOST"), Some(&ValueType::from("10.0.0.1")));
+        assert_eq!(dict.get("DB_PASS"), Some(&ValueType::from("a b#c")));
+        assert_eq!(dict.get("DB_PORT"), Some(&ValueType::from("3306")));
+        assert_eq!(
+            ValueFormat::from_path(Path::new("prod.env")),
+            ValueFormat::Env
+        );
+    }
+}
@@ -0,0 +1,72 @@
+// yaml 文本中 key 的定位, 行列号均从 1 开始
+
+fn unquote(s: &str) -> &str {
+    s.trim().trim_matches(|c| c == '"' || c == '\'')
+}
+
+// value 文件中的顶层 key, 返回 (行, 值所在列)
+pub fn value_key_location(content: &str, key: &str) -> Option<(usize, usize)> {
+    for (idx, line) in content.lines().enumerate() {
+        if line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        if let Some((k, v)) = line.split_once(':')
+            && unquote(k) == key
+        {
+            let column = line.len() - v.trim_start().len() + 1;
+            return Some((idx + 1, column));
+        }
+    }
+    None
+}
+
+// vars 文件中 `name: key` 所在行
+pub fn var_name_line(content: &str, key: &str) -> Option<usize> {
+    var_name_lines(content, key).first().copied()
+}
+
+// 同名变量可能重复声明, 返回所有行
+pub fn var_name_lines(content: &str, key: &str) -> Vec<usize> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let item = line.trim_start().trim_start_matches('-').trim_start();
+            item.strip_prefix("name:")
+                .is_some_and(|v| unquote(v) == key)
+        })
+        .map(|(idx, _)| idx + 1)
+        .collect()
+}
+
+// toml/ini/dotenv 中 `key = value` 所在行, 只匹配最后一段 key
+pub fn assign_key_line(content: &str, key: &str) -> Option<usize> {
+    let last = key.rsplit('.').next().unwrap_or(key);
+    content
+        .lines()
+        .position(|line| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split_once('=')
+                .is_some_and(|(k, _)| unquote(k) == key || unquote(k) == last)
+        })
+        .map(|idx| idx + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_location() {
+        let content = "# comment\nname: mysql\n  port: 1\n\"port\":   3306\n";
+        assert_eq!(value_key_location(content, "name"), Some((2, 7)));
+        assert_eq!(value_key_location(content, "port"), Some((4, 11)));
+        assert_eq!(value_key_location(content, "none"), None);
+        let vars = "vars:\n  - name: HOST\n    value: a\n  - name: 'PORT'\n";
+        assert_eq!(var_name_line(vars, "PORT"), Some(4));
+        let env = "# db\nexport DB_HOST=a\n[db]\nport = 1\n";
+        assert_eq!(assign_key_line(env, "DB_HOST"), Some(2));
+        assert_eq!(assign_key_line(env, "db.port"), Some(4));
+    }
+}
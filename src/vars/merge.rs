@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use serde_derive::{Deserialize, Serialize};
+
+use super::{ValueType, dict::ValueMap};
+
+// 列表合并策略; map 总是深度合并, 标量以高优先级为准
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeStrategy {
+    #[default]
+    Replace,
+    Append,
+    Prepend,
+    Unique,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MergePolicy {
+    default: MergeStrategy,
+    keys: HashMap<String, MergeStrategy>,
+}
+
+impl MergePolicy {
+    pub fn new(default: MergeStrategy) -> Self {
+        Self {
+            default,
+            keys: HashMap::new(),
+        }
+    }
+    pub fn with_key<S: Into<String>>(mut self, key: S, strategy: MergeStrategy) -> Self {
+        self.keys.insert(key.into(), strategy);
+        self
+    }
+    pub fn strategy(&self, key: &str) -> MergeStrategy {
+        self.keys.get(key).copied().unwrap_or(self.default)
+    }
+}
+
+// upper 优先级高于 lower; upper 中的 !reset 保留为删除标记, 由 strip_reset 统一移除
+pub fn merge_value(upper: &ValueType, lower: &ValueType, strategy: MergeStrategy) -> ValueType {
+    match (upper, lower) {
+        (ValueType::Obj(up), ValueType::Obj(low)) => ValueType::Obj(merge_map(up, low, strategy)),
+        (ValueType::List(up), ValueType::List(low)) => {
+            ValueType::List(merge_list(up, low, strategy))
+        }
+        _ => upper.clone(),
+    }
+}
+
+pub fn merge_map(upper: &ValueMap, lower: &ValueMap, strategy: MergeStrategy) -> ValueMap {
+    let mut map = upper.clone();
+    for (k, low_v) in lower {
+        if let Some(up_v) = upper.get(k) {
+            map.insert(k.clone(), merge_value(up_v, low_v, strategy));
+        } else {
+            map.insert(k.clone(), low_v.clone());
+        }
+    }
+    map
+}
+
+fn merge_list(upper: &[ValueType], lower: &[ValueType], strategy: MergeStrategy) -> Vec<ValueType> {
+    match strategy {
+        MergeStrategy::Replace => upper.to_vec(),
+        MergeStrategy::Append => lower.iter().chain(upper).cloned().collect(),
+        MergeStrategy::Prepend => upper.iter().chain(lower).cloned().collect(),
+        MergeStrategy::Unique => {
+            let mut list: Vec<ValueType> = Vec::new();
+            for item in lower.iter().chain(upper) {
+                if !list.contains(item) {
+                    list.push(item.clone());
+                }
+            }
+            list
+        }
+    }
+}
+
+// 移除 !reset 标记及其覆盖的值, 返回 None 表示整个值被删除
+pub fn strip_reset(value: ValueType) -> Option<ValueType> {
+    match value {
+        ValueType::Reset => None,
+        ValueType::Obj(map) => Some(ValueType::Obj(
+            map.into_iter()
+                .filter_map(|(k, v)| strip_reset(v).map(|v| (k, v)))
+                .collect(),
+        )),
+        ValueType::List(list) => Some(ValueType::List(
+            list.into_iter().filter_map(strip_reset).collect(),
+        )),
+        _ => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(items: &[&str]) -> ValueType {
+        ValueType::List(items.iter().map(|x| ValueType::from(*x)).collect())
+    }
+
+    #[test]
+    fn test_merge_list_strategy() {
+        let up = list(&["b", "c"]);
+        let low = list(&["a", "b"]);
+        assert_eq!(
+            merge_value(&up, &low, MergeStrategy::Replace),
+            list(&["b", "c"])
+        );
+        assert_eq!(
+            merge_value(&up, &low, MergeStrategy::Append),
+            list(&["a", "b", "b", "c"])
+        );
+        assert_eq!(
+            merge_value(&up, &low, MergeStrategy::Prepend),
+            list(&["b", "c", "a", "b"])
+        );
+        assert_eq!(
+            merge_value(&up, &low, MergeStrategy::Unique),
+            list(&["a", "b", "c"])
+        );
+    }
+
+    #[test]
+    fn test_deep_merge_and_reset() {
+        let up: ValueType = serde_yaml::from_str(
+            r#"
+db:
+  host: 10.0.0.1
+  debug: !reset
+tags: [x]
+"#,
+        )
+        .unwrap();
+        let low: ValueType = serde_yaml::from_str(
+            r#"
+db:
+  host: 127.0.0.1
+  port: 3306
+  debug: true
+tags: [a]
+"#,
+        )
+        .unwrap();
+        let merged = merge_value(&up, &low, MergeStrategy::Append);
+        let merged = strip_reset(merged).unwrap();
+        let expect: ValueType = serde_yaml::from_str(
+            r#"
+db:
+  host: 10.0.0.1
+  port: 3306
+tags: [a, x]
+"#,
+        )
+        .unwrap();
+        assert_eq!(merged, expect);
+        assert_eq!(strip_reset(ValueType::Reset), None);
+    }
+}
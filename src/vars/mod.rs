@@ -3,16 +3,35 @@ mod constraint;
 mod definition;
 mod dict;
 mod env_eval;
+mod explain;
+mod expr;
 mod global;
+mod import;
+mod location;
+mod merge;
 mod origin;
+mod profile;
+mod schema;
 mod types;
 pub use collection::VarCollection;
 pub use constraint::{ValueConstraint, ValueScope};
 pub use definition::VarDefinition;
-pub use dict::ValueDict;
-pub use global::setup_start_env_vars;
-pub use origin::OriginDict;
+pub use dict::{ValueDict, ValueMap};
+pub use env_eval::env_var_refs;
+pub use explain::{ENV_SOURCE, LayerHit, LayerKind, ValueExplainer, ValueLayer, VarExplain};
+pub use expr::{ArithOP, Expr, ExprContext};
+pub(crate) use global::get_os_info;
+pub use global::{GXL_OS_SYS, GXL_PRJ_ROOT, GXL_START_ROOT, RuntimeContext, find_project_define};
+pub use import::{ImportMode, ValueFormat, flatten, import_value_file};
+pub use location::{value_key_location, var_name_lines};
+pub use merge::{MergePolicy, MergeStrategy, merge_value, strip_reset};
 pub use origin::OriginValue;
+pub use origin::{
+    GLOBAL_SOURCE, MOD_CUST_SOURCE, MOD_DEFAULT_SOURCE, MOD_EXPR_SOURCE, MOD_SHARED_SOURCE,
+};
+pub use origin::{OriginDict, OriginMap};
+pub use profile::{profile_exists_under, profile_source, profile_value_file};
+pub use schema::{ValueIssue, validate_value_file};
 pub use types::EnvDict;
 pub use types::EnvEvalable;
-pub use types::ValueType;
+pub use types::{RESET_TAG, ValueType};
@@ -4,10 +4,23 @@ use derive_getters::Getters;
 use derive_more::Deref;
 use serde_derive::{Deserialize, Serialize};
 
-use super::{EnvDict, EnvEvalable, ValueDict, dict::ValueMap, types::ValueType};
+use crate::secret::redact_value;
+
+use super::{
+    EnvDict, EnvEvalable, MergePolicy, ValueDict,
+    dict::ValueMap,
+    merge::{merge_value, strip_reset},
+    types::ValueType,
+};
 
 pub type OriginMap = HashMap<String, OriginValue>;
 
+pub const GLOBAL_SOURCE: &str = "global";
+pub const MOD_CUST_SOURCE: &str = "mod-cust";
+pub const MOD_DEFAULT_SOURCE: &str = "mod-default";
+pub const MOD_EXPR_SOURCE: &str = "mod-expr";
+pub const MOD_SHARED_SOURCE: &str = "mod-shared";
+
 impl EnvEvalable<OriginMap> for OriginMap {
     fn env_eval(self, edict: &EnvDict) -> OriginMap {
         let mut origins = HashMap::new();
@@ -60,6 +73,13 @@ impl OriginValue {
         self.origin = Some(origin.into());
         self
     }
+    // secret 引用替换为 ******, 用于 _used.yml 与报告输出
+    pub fn redacted(self) -> Self {
+        Self {
+            origin: self.origin,
+            value: redact_value(self.value),
+        }
+    }
 }
 
 impl From<ValueDict> for OriginDict {
@@ -89,13 +109,36 @@ impl OriginDict {
             }
         }
     }
+    // self 优先级高于 other, 相同 key 深度合并, 来源保留 self 的
     pub fn merge(&mut self, other: &Self) {
+        self.merge_with(other, &MergePolicy::default());
+    }
+    pub fn merge_with(&mut self, other: &Self, policy: &MergePolicy) {
         for (k, v) in other.iter() {
-            if !self.contains_key(k) {
+            if let Some(cur) = self.dict.get_mut(k) {
+                cur.value = merge_value(&cur.value, &v.value, policy.strategy(k));
+            } else {
                 self.dict.insert(k.clone(), v.clone());
             }
         }
     }
+    // 移除 !reset 删除标记
+    pub fn strip_reset(&mut self) {
+        let dict = std::mem::take(&mut self.dict);
+        self.dict = dict
+            .into_iter()
+            .filter_map(|(k, v)| {
+                let origin = v.origin;
+                strip_reset(v.value).map(|value| (k, OriginValue { origin, value }))
+            })
+            .collect();
+    }
+    // upper 的优先级高于 self, 相同的 key 以 upper 为准
+    pub fn overlay(&mut self, upper: Self) {
+        let mut merged = upper;
+        merged.merge(self);
+        *self = merged;
+    }
     pub fn export_value(&self) -> ValueMap {
         let mut map = ValueMap::new();
         for (k, v) in &self.dict {
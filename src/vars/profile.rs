@@ -0,0 +1,55 @@
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::const_vars::PROFILES_DIR;
+
+// profile 叠加文件: <value_root>/profiles/<profile>.yml
+pub fn profile_value_file(value_root: &Path, profile: &str) -> PathBuf {
+    value_root
+        .join(PROFILES_DIR)
+        .join(format!("{}.yml", profile))
+}
+
+// root 下任一层 (全局或各模块) 是否提供了该 profile
+pub fn profile_exists_under(root: &Path, profile: &str) -> bool {
+    let name = format!("{}.yml", profile);
+    WalkDir::new(root)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|x| x.file_type().is_file() && x.file_name() == name.as_str())
+        .any(|x| {
+            x.path()
+                .parent()
+                .and_then(|p| p.file_name())
+                .is_some_and(|p| p == PROFILES_DIR)
+        })
+}
+
+// OriginDict 中记录的来源, 如: global-profile:prod
+pub fn profile_source(layer: &str, profile: &str) -> String {
+    format!("{}-profile:{}", layer, profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_value_file() {
+        let file = profile_value_file(Path::new("/prj/values"), "prod");
+        assert_eq!(file, PathBuf::from("/prj/values/profiles/prod.yml"));
+        assert_eq!(profile_source("global", "prod"), "global-profile:prod");
+    }
+
+    #[test]
+    fn test_profile_exists_under() {
+        let dir = tempfile::tempdir().unwrap();
+        let mod_profiles = dir.path().join("mods/redis/x86-ubt22-k8s/profiles");
+        std::fs::create_dir_all(&mod_profiles).unwrap();
+        std::fs::write(mod_profiles.join("prod.yml"), "KEY: prod").unwrap();
+        std::fs::write(dir.path().join("dev.yml"), "KEY: dev").unwrap();
+        assert!(profile_exists_under(dir.path(), "prod"));
+        assert!(!profile_exists_under(dir.path(), "dev"));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use derive_getters::Getters;
+use orion_error::{ErrorOwe, ErrorWith};
+use serde_derive::Serialize;
+use serde_json::{Map, Value, json};
+
+use crate::error::SpecResult;
+
+use super::{ValueConstraint, ValueDict, ValueType, VarCollection, location::value_key_location};
+
+pub const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn type_name(value: &ValueType) -> &'static str {
+    match value {
+        ValueType::String(_) => "string",
+        ValueType::Bool(_) => "boolean",
+        ValueType::Int(_) => "integer",
+        ValueType::Float(_) => "number",
+        ValueType::List(_) => "array",
+        ValueType::Obj(_) => "object",
+        ValueType::Reset => "null",
+    }
+}
+
+impl VarCollection {
+    // 由 vars.yml 生成 JSON Schema, 类型取自默认值
+    pub fn json_schema(&self) -> Value {
+        let mut properties = Map::new();
+        for var in self.vars() {
+            let value = var.var_value();
+            let mut prop = Map::new();
+            prop.insert("type".into(), json!(type_name(&value)));
+            prop.insert("default".into(), json!(value));
+            if let Some(desc) = var.desc() {
+                prop.insert("description".into(), json!(desc));
+            }
+            match var.constr() {
+                Some(ValueConstraint::Locked) => {
+                    prop.insert("readOnly".into(), json!(true));
+                }
+                Some(ValueConstraint::Scope(scope)) => {
+                    prop.insert("minimum".into(), json!(scope.beg));
+                    prop.insert("maximum".into(), json!(scope.end));
+                }
+                Some(ValueConstraint::Options(options)) => {
+                    prop.insert("enum".into(), json!(options));
+                }
+                Some(ValueConstraint::Pattern(pattern)) => {
+                    prop.insert("pattern".into(), json!(pattern));
+                }
+                None => {}
+            }
+            properties.insert(var.name().to_string(), Value::Object(prop));
+        }
+        json!({
+            "$schema": JSON_SCHEMA_DRAFT,
+            "type": "object",
+            "properties": properties,
+            "additionalProperties": true,
+        })
+    }
+}
+
+#[derive(Getters, Clone, Debug, Serialize, PartialEq)]
+pub struct ValueIssue {
+    path: PathBuf,
+    key: Option<String>,
+    line: Option<usize>,
+    column: Option<usize>,
+    message: String,
+}
+
+impl Display for ValueIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ": {}", key)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+// 按 schema 校验 value.yml, 只覆盖 json_schema 生成的关键字
+pub fn validate_value_file(schema: &Value, path: &Path) -> SpecResult<Vec<ValueIssue>> {
+    let content = std::fs::read_to_string(path).owe_res().with(path)?;
+    Ok(validate_value_str(schema, path, &content))
+}
+
+fn validate_value_str(schema: &Value, path: &Path, content: &str) -> Vec<ValueIssue> {
+    let dict: ValueDict = match serde_yaml::from_str(content) {
+        Ok(dict) => dict,
+        Err(e) => {
+            let location = e.location();
+            return vec![ValueIssue {
+                path: path.to_path_buf(),
+                key: None,
+                line: location.as_ref().map(|x| x.line()),
+                column: location.as_ref().map(|x| x.column()),
+                message: e.to_string(),
+            }];
+        }
+    };
+    let mut issues = Vec::new();
+    for (key, value) in dict.iter() {
+        // key 可能含 `/` `~`, 不能直接拼成 JSON pointer
+        let Some(prop) = schema.get("properties").and_then(|x| x.get(key)) else {
+            continue;
+        };
+        let location = value_key_location(content, key);
+        for message in check_value(value, prop) {
+            issues.push(ValueIssue {
+                path: path.to_path_buf(),
+                key: Some(key.clone()),
+                line: location.map(|x| x.0),
+                column: location.map(|x| x.1),
+                message,
+            });
+        }
+    }
+    issues
+}
+
+fn check_value(value: &ValueType, prop: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    if value.is_reset() {
+        return errors;
+    }
+    // 锁定的值允许写成与默认值相同
+    if prop.get("readOnly") == Some(&Value::Bool(true)) {
+        if prop.get("default") == Some(&json!(value)) {
+            return errors;
+        }
+        errors.push("locked value can not be overridden".into());
+        return errors;
+    }
+    // ${VAR} 引用在 localize 时才求值, 跳过类型检查
+    if let ValueType::String(s) = value
+        && s.contains("${")
+    {
+        return errors;
+    }
+    if let Some(expect) = prop.get("type").and_then(|x| x.as_str()) {
+        let actual = type_name(value);
+        let matched = actual == expect || (expect == "number" && actual == "integer");
+        if !matched {
+            errors.push(format!("expect {}, got {} ({})", expect, actual, value));
+            return errors;
+        }
+    }
+    let number = match value {
+        ValueType::Int(v) => Some(*v as f64),
+        ValueType::Float(v) => Some(*v),
+        _ => None,
+    };
+    if let Some(num) = number {
+        if let Some(min) = prop.get("minimum").and_then(|x| x.as_f64())
+            && num < min
+        {
+            errors.push(format!("{} is less than minimum {}", value, min));
+        }
+        if let Some(max) = prop.get("maximum").and_then(|x| x.as_f64())
+            && num > max
+        {
+            errors.push(format!("{} is greater than maximum {}", value, max));
+        }
+    }
+    if let Some(options) = prop.get("enum").and_then(|x| x.as_array())
+        && !options.contains(&json!(value))
+    {
+        errors.push(format!(
+            "{} is not one of {}",
+            value,
+            Value::from(options.clone())
+        ));
+    }
+    if let (Some(pattern), ValueType::String(s)) =
+        (prop.get("pattern").and_then(|x| x.as_str()), value)
+    {
+        match regex::Regex::new(pattern) {
+            Ok(re) if !re.is_match(s) => {
+                errors.push(format!("'{}' does not match pattern {}", s, pattern))
+            }
+            Err(e) => errors.push(format!("bad pattern {}: {}", pattern, e)),
+            _ => {}
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vars::VarDefinition;
+
+    fn make_vars() -> VarCollection {
+        VarCollection::define(vec![
+            VarDefinition::from(("PORT", 3306)).with_constr(ValueConstraint::scope(1024, 65535)),
+            VarDefinition::from(("MODE", "single"))
+                .with_desc("deploy mode")
+                .with_constr(ValueConstraint::Options(vec![
+                    ValueType::from("single"),
+                    ValueType::from("cluster"),
+                ])),
+            VarDefinition::from(("USER", "admin"))
+                .with_constr(ValueConstraint::Pattern("^[a-z]+$".into())),
+            VarDefinition::from(("DATA_DIR", "/data")).with_constr(ValueConstraint::Locked),
+            VarDefinition::from(("RATIO", 0.5)),
+        ])
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = make_vars().json_schema();
+        assert_eq!(schema["properties"]["PORT"]["type"], "integer");
+        assert_eq!(schema["properties"]["PORT"]["default"], 3306);
+        assert_eq!(schema["properties"]["PORT"]["maximum"], 65535);
+        assert_eq!(schema["properties"]["MODE"]["description"], "deploy mode");
+        assert_eq!(schema["properties"]["MODE"]["enum"][1], "cluster");
+        assert_eq!(schema["properties"]["DATA_DIR"]["readOnly"], true);
+        assert_eq!(schema["properties"]["RATIO"]["type"], "number");
+    }
+
+    #[test]
+    fn test_validate_value() {
+        let schema = make_vars().json_schema();
+        let path = PathBuf::from("value.yml");
+        let content = r#"PORT: 80
+MODE: cluster
+USER: Admin
+DATA_DIR: /opt
+RATIO: 1
+OTHER: any
+HOST_PORT: ${PORT}
+"#;
+        let issues = validate_value_str(&schema, &path, content);
+        let keys: Vec<_> = issues.iter().filter_map(|x| x.key().clone()).collect();
+        assert_eq!(keys, vec!["PORT", "USER", "DATA_DIR"]);
+        assert_eq!(issues[0].line(), &Some(1));
+        assert_eq!(issues[0].column(), &Some(7));
+        assert_eq!(issues[1].line(), &Some(3));
+        assert!(issues[0].to_string().starts_with("value.yml:1:7: PORT:"));
+
+        let issues = validate_value_str(&schema, &path, "PORT: \"3306\"\nMODE: multi\n");
+        assert_eq!(issues.len(), 2);
+        assert!(issues[0].message().contains("expect integer"));
+
+        // 锁定值与默认值相同时不报错
+        let issues = validate_value_str(&schema, &path, "DATA_DIR: /data\n");
+        assert!(issues.is_empty());
+
+        // key 中的 `/` `~` 按原样匹配
+        let vars = VarCollection::define(vec![
+            VarDefinition::from(("a/b", 1)),
+            VarDefinition::from(("c~d", 1)),
+        ]);
+        let issues = validate_value_str(&vars.json_schema(), &path, "a/b: x\nc~d: y\n");
+        assert_eq!(issues.len(), 2);
+
+        let issues = validate_value_str(&schema, &path, "PORT: 1\n  bad: [\n");
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].key().is_none());
+        assert!(issues[0].line().is_some());
+    }
+}
@@ -1,6 +1,8 @@
-use super::{ValueDict, env_eval::expand_env_vars};
-use derive_more::Display;
-use serde_derive::{Deserialize, Serialize};
+use std::fmt::Display;
+
+use super::{ValueDict, dict::ValueMap, env_eval::expand_env_vars};
+
+pub const RESET_TAG: &str = "reset";
 
 pub type EnvDict = ValueDict;
 pub trait EnvEvalable<T> {
@@ -19,25 +21,46 @@ impl EnvEvalable<Option<String>> for Option<String> {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Display)]
-#[serde(untagged)]
-//#[derive(Clone, Debug, PartialEq, Display, From)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ValueType {
     String(String),
     Bool(bool),
     Int(u64),
     Float(f64),
+    List(Vec<ValueType>),
+    Obj(ValueMap),
+    // `!reset` 删除标记, 合并后会被移除
+    Reset,
 }
 impl EnvEvalable<ValueType> for ValueType {
     fn env_eval(self, dict: &EnvDict) -> ValueType {
         match self {
             ValueType::String(v) => ValueType::String(v.env_eval(dict)),
+            ValueType::List(v) => {
+                ValueType::List(v.into_iter().map(|x| x.env_eval(dict)).collect())
+            }
+            ValueType::Obj(v) => ValueType::Obj(v.env_eval(dict)),
             _ => self,
         }
     }
 }
 
-/*
+impl Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValueType::String(v) => write!(f, "{}", v),
+            ValueType::Bool(v) => write!(f, "{}", v),
+            ValueType::Int(v) => write!(f, "{}", v),
+            ValueType::Float(v) => write!(f, "{}", v),
+            ValueType::List(_) | ValueType::Obj(_) => {
+                let json = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+                write!(f, "{}", json)
+            }
+            ValueType::Reset => write!(f, "!{}", RESET_TAG),
+        }
+    }
+}
+
 impl serde::Serialize for ValueType {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -48,6 +71,11 @@ impl serde::Serialize for ValueType {
             ValueType::Bool(v) => v.serialize(serializer),
             ValueType::Int(v) => v.serialize(serializer),
             ValueType::Float(v) => v.serialize(serializer),
+            ValueType::List(v) => v.serialize(serializer),
+            ValueType::Obj(v) => v.serialize(serializer),
+            ValueType::Reset => {
+                serializer.serialize_newtype_variant("ValueType", 6, RESET_TAG, &())
+            }
         }
     }
 }
@@ -57,48 +85,100 @@ impl<'de> serde::Deserialize<'de> for ValueType {
     where
         D: serde::Deserializer<'de>,
     {
-        // 使用 serde::DeserializeSeed 来避免多次使用 deserializer
         struct ValueTypeVisitor;
 
-        impl serde::de::Visitor<'_> for ValueTypeVisitor {
+        impl<'de> serde::de::Visitor<'de> for ValueTypeVisitor {
             type Value = ValueType;
 
             fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-                formatter.write_str("a string, bool, integer or float value")
+                formatter.write_str("a string, bool, number, list, map or !reset")
+            }
+
+            // yaml `~` / 空值 / json null 视为空串, 仅 `!reset` 才删除
+            fn visit_unit<E>(self) -> Result<Self::Value, E> {
+                Ok(ValueType::String(String::new()))
+            }
+
+            fn visit_none<E>(self) -> Result<Self::Value, E> {
+                Ok(ValueType::String(String::new()))
             }
 
             fn visit_bool<E>(self, v: bool) -> Result<Self::Value, E> {
-                Ok(ValueType::Bool(VarValue::from(v)))
+                Ok(ValueType::Bool(v))
             }
 
             fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
-                Ok(ValueType::Int(VarValue::from(v as u64)))
+                // 负数无法用 u64 表示, 与原 untagged 行为一致退化为 Float
+                if v >= 0 {
+                    Ok(ValueType::Int(v as u64))
+                } else {
+                    Ok(ValueType::Float(v as f64))
+                }
             }
 
             fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
-                Ok(ValueType::Int(VarValue::from(v)))
+                Ok(ValueType::Int(v))
             }
 
             fn visit_f64<E>(self, v: f64) -> Result<Self::Value, E> {
-                Ok(ValueType::Float(VarValue::from(v)))
+                Ok(ValueType::Float(v))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> {
+                Ok(ValueType::String(v.to_string()))
+            }
+
+            fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
+                Ok(ValueType::String(v))
             }
 
-            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
             where
-                E: serde::de::Error,
+                A: serde::de::SeqAccess<'de>,
             {
-                Ok(ValueType::String(VarValue::from(v)))
+                let mut list = Vec::new();
+                while let Some(item) = seq.next_element()? {
+                    list.push(item);
+                }
+                Ok(ValueType::List(list))
             }
 
-            fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
-                Ok(ValueType::String(VarValue::from(v)))
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut obj = ValueMap::new();
+                while let Some((k, v)) = map.next_entry::<String, ValueType>()? {
+                    obj.insert(k, v);
+                }
+                Ok(ValueType::Obj(obj))
+            }
+
+            // yaml tag: `key: !reset`
+            fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::EnumAccess<'de>,
+            {
+                use serde::de::{Error, VariantAccess};
+                let (tag, variant) = data.variant::<String>()?;
+                if tag == RESET_TAG {
+                    variant.newtype_variant::<serde::de::IgnoredAny>()?;
+                    Ok(ValueType::Reset)
+                } else {
+                    Err(A::Error::custom(format!("unknown value tag: !{}", tag)))
+                }
             }
         }
 
         deserializer.deserialize_any(ValueTypeVisitor)
     }
 }
-    */
+
+impl ValueType {
+    pub fn is_reset(&self) -> bool {
+        matches!(self, ValueType::Reset)
+    }
+}
 
 impl From<&str> for ValueType {
     fn from(value: &str) -> Self {
@@ -120,3 +200,66 @@ impl From<f64> for ValueType {
         Self::Float(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_type_yaml() {
+        let content = r#"
+name: mysql
+port: 3306
+neg: -1
+ratio: 0.5
+enable: true
+args: [a, b]
+conf:
+  host: ${HOST}
+  ports: [80, 443]
+removed: !reset
+"#;
+        let dict: ValueDict = serde_yaml::from_str(content).unwrap();
+        assert_eq!(dict.get("port"), Some(&ValueType::Int(3306)));
+        assert_eq!(dict.get("neg"), Some(&ValueType::Float(-1.0)));
+        assert_eq!(
+            dict.get("args"),
+            Some(&ValueType::List(vec![
+                ValueType::from("a"),
+                ValueType::from("b")
+            ]))
+        );
+        assert!(dict.get("removed").unwrap().is_reset());
+        let mut env = EnvDict::new();
+        env.insert("HOST", ValueType::from("10.0.0.1"));
+        let conf = dict.get("conf").unwrap().clone().env_eval(&env);
+        assert_eq!(conf.to_string(), r#"{"host":"10.0.0.1","ports":[80,443]}"#);
+
+        let yaml = serde_yaml::to_string(&dict).unwrap();
+        let loaded: ValueDict = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(dict, loaded);
+        let json = serde_json::to_string(dict.get("conf").unwrap()).unwrap();
+        let loaded: ValueType = serde_json::from_str(&json).unwrap();
+        assert_eq!(dict.get("conf"), Some(&loaded));
+    }
+
+    #[test]
+    fn test_value_type_null() {
+        let content = r#"
+name: mysql
+empty:
+removed: ~
+dropped: !reset
+"#;
+        let dict: ValueDict = serde_yaml::from_str(content).unwrap();
+        assert_eq!(dict.get("empty"), Some(&ValueType::from("")));
+        assert_eq!(dict.get("removed"), Some(&ValueType::from("")));
+        assert!(dict.get("dropped").unwrap().is_reset());
+
+        let json: ValueType = serde_json::from_str("null").unwrap();
+        assert_eq!(json, ValueType::from(""));
+        let yaml = serde_yaml::to_string(&dict).unwrap();
+        let loaded: ValueDict = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(dict, loaded);
+    }
+}